@@ -0,0 +1,599 @@
+// TIG's UI uses the pattern `tig_challenges::<challenge_name>` to automatically detect your algorithm's challenge
+use anyhow::{anyhow, Result};
+use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tig_challenges::job_scheduling::*;
+
+const NONE: usize = usize::MAX;
+
+#[derive(Serialize, Deserialize)]
+pub struct Hyperparameters {
+    pub num_restarts: usize,
+    pub max_iterations: usize,
+    pub max_stagnation: usize,
+    pub max_neighbours: usize,
+    pub tabu_tenure: usize,
+}
+
+impl Default for Hyperparameters {
+    fn default() -> Self {
+        Self {
+            num_restarts: 100,
+            max_iterations: 1000,
+            max_stagnation: 200,
+            max_neighbours: 100,
+            tabu_tenure: 8,
+        }
+    }
+}
+
+pub fn help() {
+    println!("Tabu search on the disjunctive graph with N5/N7 critical block neighbourhoods");
+    println!("and machine reassignment of critical operations.");
+    println!();
+    println!("Hyperparameters:");
+    println!(
+        "  num_restarts   - randomised dispatching runs for the initial solution (default: 100)"
+    );
+    println!("  max_iterations - tabu search iterations (default: 1000)");
+    println!("  max_stagnation - iterations without improvement before restarting from the best solution (default: 200)");
+    println!(
+        "  max_neighbours - neighbours evaluated per iteration, sampled if exceeded (default: 100)"
+    );
+    println!("  tabu_tenure    - minimum tenure of a moved operation, randomised up to double (default: 8)");
+}
+
+pub fn solve_challenge(
+    challenge: &Challenge,
+    save_solution: &dyn Fn(&Solution) -> Result<()>,
+    hyperparameters: &Option<Map<String, Value>>,
+) -> Result<()> {
+    let hyperparameters = match hyperparameters {
+        Some(hyperparameters) => {
+            serde_json::from_value::<Hyperparameters>(Value::Object(hyperparameters.clone()))
+                .map_err(|e| anyhow!("Failed to parse hyperparameters: {}", e))?
+        }
+        None => Hyperparameters::default(),
+    };
+    let mut rng = SmallRng::from_seed(challenge.seed);
+    let instance = Instance::new(challenge);
+
+    // Step 1: Initial solution from dispatching with different priority rules
+    let mut best_solution: Option<(u32, Solution)> = None;
+    // earliest due date and minimum slack rules only apply to weighted tardiness
+    let num_rules = if challenge.has_due_dates() {
+        NUM_RULES + 2
+    } else {
+        NUM_RULES
+    };
+    for restart in 0..hyperparameters.num_restarts.max(num_rules) {
+        let rule = restart % num_rules;
+        let top_k = if restart < num_rules {
+            1
+        } else {
+            2 + restart % 3
+        };
+        let builder = dispatch(challenge, &instance, rule, top_k, &mut rng)?;
+        let solution = ScheduleBuilder::left_shift(challenge, &builder.to_solution()?)?;
+        if best_solution
+            .as_ref()
+            .is_none_or(|(objective, _)| solution.objective() < *objective)
+        {
+            best_solution = Some((solution.objective(), solution.to_solution()?));
+        }
+    }
+    let (_, best_solution) = best_solution.unwrap();
+    save_solution(&best_solution)?;
+    // the disjunctive graph only models makespan without setups, releases or breakdowns
+    if challenge.has_due_dates() || challenge.has_side_constraints() {
+        return Ok(());
+    }
+
+    // Step 2: Tabu search over machine sequences and machine assignments
+    let mut current = Schedule::new(&instance, &best_solution);
+    let mut evaluator = Evaluator::new(&instance);
+    let mut best_makespan = evaluator.evaluate(&instance, &current).unwrap();
+    let mut best = current.clone();
+    let mut tabu_until = vec![0usize; instance.num_ops()];
+    let mut stagnation = 0;
+
+    for iteration in 1..=hyperparameters.max_iterations {
+        if stagnation >= hyperparameters.max_stagnation {
+            current = best.clone();
+            tabu_until.iter_mut().for_each(|t| *t = 0);
+            stagnation = 0;
+        }
+
+        evaluator.evaluate(&instance, &current).unwrap();
+        let (mut moves, mut n7_moves) = evaluator.neighbourhood(&instance, &current);
+        if moves.is_empty() && n7_moves.is_empty() {
+            break;
+        }
+        // N5 and reassignment moves take priority, N7 moves fill the remaining budget
+        if moves.len() > hyperparameters.max_neighbours {
+            moves.shuffle(&mut rng);
+            moves.truncate(hyperparameters.max_neighbours);
+        } else {
+            let remaining = hyperparameters.max_neighbours - moves.len();
+            if n7_moves.len() > remaining {
+                n7_moves.shuffle(&mut rng);
+                n7_moves.truncate(remaining);
+            }
+            moves.extend(n7_moves);
+        }
+
+        // (makespan, move, moved op), best non-tabu and best overall as fallback
+        let mut best_move: Option<(u32, Move, usize)> = None;
+        let mut fallback_move: Option<(u32, Move, usize)> = None;
+        for mv in moves {
+            let op = current.moved_op(&mv);
+            let undo = current.apply(&instance, mv);
+            let makespan = evaluator.evaluate(&instance, &current);
+            current.apply(&instance, undo);
+            let makespan = match makespan {
+                Some(makespan) => makespan,
+                None => continue,
+            };
+            let is_tabu = tabu_until[op] > iteration && makespan >= best_makespan;
+            let slot = if is_tabu {
+                &mut fallback_move
+            } else {
+                &mut best_move
+            };
+            if slot.as_ref().is_none_or(|(m, _, _)| makespan < *m) {
+                *slot = Some((makespan, mv, op));
+            }
+        }
+
+        let (makespan, mv, op) = match best_move.or(fallback_move) {
+            Some(m) => m,
+            None => break,
+        };
+        current.apply(&instance, mv);
+        let tenure = hyperparameters.tabu_tenure;
+        tabu_until[op] = iteration + tenure + rng.gen_range(0..=tenure);
+
+        if makespan < best_makespan {
+            best_makespan = makespan;
+            best = current.clone();
+            stagnation = 0;
+        } else {
+            stagnation += 1;
+        }
+    }
+
+    evaluator.evaluate(&instance, &best).unwrap();
+    save_solution(&evaluator.to_solution(&instance, &best))?;
+    Ok(())
+}
+
+const NUM_RULES: usize = 5;
+
+struct Instance {
+    job_first_op: Vec<usize>,
+    job_prev: Vec<usize>,
+    job_next: Vec<usize>,
+    op_machines: Vec<Vec<(usize, u32)>>,
+    num_machines: usize,
+}
+
+impl Instance {
+    fn new(challenge: &Challenge) -> Self {
+        let builder = ScheduleBuilder::new(challenge);
+        let mut job_first_op = vec![0];
+        let mut job_prev = Vec::new();
+        let mut job_next = Vec::new();
+        let mut op_machines = Vec::new();
+        for job in 0..builder.num_jobs() {
+            let first = op_machines.len();
+            let num_ops = builder.num_ops(job);
+            for op_idx in 0..num_ops {
+                let mut machines = builder
+                    .processing_times(job, op_idx)
+                    .iter()
+                    .map(|(&m, &t)| (m, t))
+                    .collect::<Vec<_>>();
+                machines.sort_unstable();
+                op_machines.push(machines);
+                job_prev.push(if op_idx == 0 {
+                    NONE
+                } else {
+                    first + op_idx - 1
+                });
+                job_next.push(if op_idx + 1 == num_ops {
+                    NONE
+                } else {
+                    first + op_idx + 1
+                });
+            }
+            job_first_op.push(op_machines.len());
+        }
+        Self {
+            job_first_op,
+            job_prev,
+            job_next,
+            op_machines,
+            num_machines: challenge.num_machines,
+        }
+    }
+
+    fn num_ops(&self) -> usize {
+        self.op_machines.len()
+    }
+
+    fn processing_time(&self, op: usize, machine: usize) -> u32 {
+        self.op_machines[op]
+            .iter()
+            .find(|&&(m, _)| m == machine)
+            .unwrap()
+            .1
+    }
+}
+
+// Non-delay dispatching. Whenever a machine is idle, it is given one of the ready operations for
+// which it is the machine with the earliest completion, chosen by the priority rule (randomised
+// over the top_k best when top_k > 1)
+fn dispatch<'a>(
+    challenge: &'a Challenge,
+    instance: &Instance,
+    rule: usize,
+    top_k: usize,
+    rng: &mut SmallRng,
+) -> Result<ScheduleBuilder<'a>> {
+    let mut builder = ScheduleBuilder::new(challenge);
+    let num_jobs = builder.num_jobs();
+    let mut remaining_work = (0..num_jobs)
+        .map(|job| {
+            (instance.job_first_op[job]..instance.job_first_op[job + 1])
+                .map(|op| work(instance, op))
+                .sum::<f64>()
+        })
+        .collect::<Vec<f64>>();
+    let mut machines = (0..instance.num_machines).collect::<Vec<usize>>();
+    let mut time = 0;
+
+    while !builder.is_complete() {
+        if top_k > 1 {
+            machines.shuffle(rng);
+        }
+        for &machine in &machines {
+            if builder.machine_available_time(machine) > time {
+                continue;
+            }
+            let mut candidates = Vec::new();
+            for (job, &job_work) in remaining_work.iter().enumerate() {
+                let op = match builder.next_op(job) {
+                    Some(op_idx) if builder.job_ready_time(job) <= time => {
+                        instance.job_first_op[job] + op_idx
+                    }
+                    _ => continue,
+                };
+                let proc_time = match instance.op_machines[op]
+                    .iter()
+                    .find(|&&(m, _)| m == machine)
+                {
+                    Some(&(_, proc_time)) => proc_time,
+                    None => continue,
+                };
+                let earliest_end = instance.op_machines[op]
+                    .iter()
+                    .map(|&(m, t)| time.max(builder.machine_available_time(m)) + t)
+                    .min()
+                    .unwrap();
+                if time + proc_time != earliest_end {
+                    continue;
+                }
+                let priority = match rule {
+                    0 => -job_work,
+                    1 => -((instance.job_first_op[job + 1] - op) as f64),
+                    2 => instance.op_machines[op].len() as f64,
+                    3 => proc_time as f64,
+                    4 => -(proc_time as f64),
+                    5 => challenge.job_due_dates[job] as f64,
+                    _ => challenge.job_due_dates[job] as f64 - time as f64 - job_work,
+                };
+                candidates.push((priority, proc_time, job, op));
+            }
+            if candidates.is_empty() {
+                continue;
+            }
+            candidates.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+            let pick = rng.gen_range(0..top_k.max(1).min(candidates.len()));
+            let (_, _, job, op) = candidates[pick];
+            builder.append(job, machine)?;
+            remaining_work[job] -= work(instance, op);
+        }
+
+        time = (0..instance.num_machines)
+            .map(|m| builder.machine_available_time(m))
+            .chain(
+                (0..num_jobs)
+                    .filter(|&job| builder.next_op(job).is_some())
+                    .map(|job| builder.job_ready_time(job)),
+            )
+            .filter(|&t| t > time)
+            .min()
+            .unwrap_or(time);
+    }
+    Ok(builder)
+}
+
+// blend of average and minimum processing time over eligible machines
+fn work(instance: &Instance, op: usize) -> f64 {
+    let machines = &instance.op_machines[op];
+    let min = machines.iter().map(|&(_, t)| t).min().unwrap_or(0) as f64;
+    let avg = machines.iter().map(|&(_, t)| t as f64).sum::<f64>() / machines.len().max(1) as f64;
+    avg * 0.7 + min * 0.3
+}
+
+#[derive(Clone, Copy)]
+enum Move {
+    // move the operation at position `from` to position `to` in the sequence of `machine`
+    Shift {
+        machine: usize,
+        from: usize,
+        to: usize,
+    },
+    // move `op` to position `pos` in the sequence of `machine`
+    Reassign {
+        op: usize,
+        machine: usize,
+        pos: usize,
+    },
+}
+
+#[derive(Clone)]
+struct Schedule {
+    machine_of: Vec<usize>,
+    proc_time: Vec<u32>,
+    machine_seq: Vec<Vec<usize>>,
+}
+
+impl Schedule {
+    fn new(instance: &Instance, solution: &Solution) -> Self {
+        let mut machine_of = vec![0; instance.num_ops()];
+        let mut proc_time = vec![0; instance.num_ops()];
+        let mut machine_ops = vec![Vec::new(); instance.num_machines];
+        for (job, schedule) in solution.job_schedule.iter().enumerate() {
+            for (op_idx, &(machine, start)) in schedule.iter().enumerate() {
+                let op = instance.job_first_op[job] + op_idx;
+                machine_of[op] = machine;
+                proc_time[op] = instance.processing_time(op, machine);
+                machine_ops[machine].push((start, op));
+            }
+        }
+        let machine_seq = machine_ops
+            .into_iter()
+            .map(|mut ops| {
+                ops.sort_unstable();
+                ops.into_iter().map(|(_, op)| op).collect()
+            })
+            .collect();
+        Self {
+            machine_of,
+            proc_time,
+            machine_seq,
+        }
+    }
+
+    fn moved_op(&self, mv: &Move) -> usize {
+        match *mv {
+            Move::Shift { machine, from, .. } => self.machine_seq[machine][from],
+            Move::Reassign { op, .. } => op,
+        }
+    }
+
+    // applies the move and returns the move that undoes it
+    fn apply(&mut self, instance: &Instance, mv: Move) -> Move {
+        match mv {
+            Move::Shift { machine, from, to } => {
+                let op = self.machine_seq[machine].remove(from);
+                self.machine_seq[machine].insert(to, op);
+                Move::Shift {
+                    machine,
+                    from: to,
+                    to: from,
+                }
+            }
+            Move::Reassign { op, machine, pos } => {
+                let old_machine = self.machine_of[op];
+                let old_pos = self.machine_seq[old_machine]
+                    .iter()
+                    .position(|&o| o == op)
+                    .unwrap();
+                self.machine_seq[old_machine].remove(old_pos);
+                self.machine_seq[machine].insert(pos, op);
+                self.machine_of[op] = machine;
+                self.proc_time[op] = instance.processing_time(op, machine);
+                Move::Reassign {
+                    op,
+                    machine: old_machine,
+                    pos: old_pos,
+                }
+            }
+        }
+    }
+}
+
+struct Evaluator {
+    heads: Vec<u32>,
+    in_degree: Vec<u32>,
+    machine_prev: Vec<usize>,
+    machine_next: Vec<usize>,
+    seq_pos: Vec<usize>,
+    queue: Vec<usize>,
+}
+
+impl Evaluator {
+    fn new(instance: &Instance) -> Self {
+        let n = instance.num_ops();
+        Self {
+            heads: vec![0; n],
+            in_degree: vec![0; n],
+            machine_prev: vec![NONE; n],
+            machine_next: vec![NONE; n],
+            seq_pos: vec![0; n],
+            queue: Vec::with_capacity(n),
+        }
+    }
+
+    // longest path from the source to every operation. Returns None if the graph is cyclic
+    fn evaluate(&mut self, instance: &Instance, schedule: &Schedule) -> Option<u32> {
+        for seq in &schedule.machine_seq {
+            for (pos, &op) in seq.iter().enumerate() {
+                self.seq_pos[op] = pos;
+                self.machine_prev[op] = if pos == 0 { NONE } else { seq[pos - 1] };
+                self.machine_next[op] = seq.get(pos + 1).copied().unwrap_or(NONE);
+            }
+        }
+        self.queue.clear();
+        for op in 0..instance.num_ops() {
+            self.heads[op] = 0;
+            self.in_degree[op] =
+                (instance.job_prev[op] != NONE) as u32 + (self.machine_prev[op] != NONE) as u32;
+            if self.in_degree[op] == 0 {
+                self.queue.push(op);
+            }
+        }
+        let mut makespan = 0;
+        let mut i = 0;
+        while i < self.queue.len() {
+            let op = self.queue[i];
+            i += 1;
+            let end = self.heads[op] + schedule.proc_time[op];
+            makespan = makespan.max(end);
+            for next in [instance.job_next[op], self.machine_next[op]] {
+                if next != NONE {
+                    self.heads[next] = self.heads[next].max(end);
+                    self.in_degree[next] -= 1;
+                    if self.in_degree[next] == 0 {
+                        self.queue.push(next);
+                    }
+                }
+            }
+        }
+        (self.queue.len() == instance.num_ops()).then_some(makespan)
+    }
+
+    // critical path of the last evaluated schedule, split into blocks of consecutive
+    // operations on the same machine. Each block is (machine, first seq pos, last seq pos)
+    fn critical_blocks(
+        &self,
+        instance: &Instance,
+        schedule: &Schedule,
+    ) -> Vec<(usize, usize, usize)> {
+        let mut op = (0..instance.num_ops())
+            .max_by_key(|&op| {
+                (
+                    self.heads[op] + schedule.proc_time[op],
+                    std::cmp::Reverse(op),
+                )
+            })
+            .unwrap();
+        let mut path = vec![op];
+        while self.heads[op] > 0 {
+            let head = self.heads[op];
+            let mp = self.machine_prev[op];
+            let jp = instance.job_prev[op];
+            op = if mp != NONE && self.heads[mp] + schedule.proc_time[mp] == head {
+                mp
+            } else if jp != NONE && self.heads[jp] + schedule.proc_time[jp] == head {
+                jp
+            } else {
+                break;
+            };
+            path.push(op);
+        }
+        path.reverse();
+
+        let mut blocks: Vec<(usize, usize, usize)> = Vec::new();
+        for &op in &path {
+            let machine = schedule.machine_of[op];
+            let pos = self.seq_pos[op];
+            match blocks.last_mut() {
+                Some(block) if block.0 == machine && block.2 + 1 == pos => block.2 = pos,
+                _ => blocks.push((machine, pos, pos)),
+            }
+        }
+        blocks
+    }
+
+    // Moves around the critical blocks. Returns the N5 swaps of the first/last two operations
+    // of each block together with reassignments of critical operations to other eligible
+    // machines (at the position matching their head), followed by the remaining N7 moves
+    // of operations to the front, back or interior of their block
+    fn neighbourhood(&self, instance: &Instance, schedule: &Schedule) -> (Vec<Move>, Vec<Move>) {
+        let blocks = self.critical_blocks(instance, schedule);
+        let num_blocks = blocks.len();
+        let mut n5_moves = Vec::new();
+        let mut n7_moves = Vec::new();
+        for (b, &(machine, first, last)) in blocks.iter().enumerate() {
+            for pos in first..=last {
+                if b > 0 && pos > first {
+                    let moves = if pos == first + 1 {
+                        &mut n5_moves
+                    } else {
+                        &mut n7_moves
+                    };
+                    moves.push(Move::Shift {
+                        machine,
+                        from: pos,
+                        to: first,
+                    });
+                }
+                if b + 1 < num_blocks && pos < last {
+                    let moves = if pos + 1 == last {
+                        &mut n5_moves
+                    } else {
+                        &mut n7_moves
+                    };
+                    moves.push(Move::Shift {
+                        machine,
+                        from: pos,
+                        to: last,
+                    });
+                }
+                if pos > first && pos < last {
+                    n7_moves.push(Move::Shift {
+                        machine,
+                        from: first,
+                        to: pos,
+                    });
+                    n7_moves.push(Move::Shift {
+                        machine,
+                        from: last,
+                        to: pos,
+                    });
+                }
+
+                let op = schedule.machine_seq[machine][pos];
+                for &(other, _) in &instance.op_machines[op] {
+                    if other == machine {
+                        continue;
+                    }
+                    let seq = &schedule.machine_seq[other];
+                    let head = self.heads[op];
+                    n5_moves.push(Move::Reassign {
+                        op,
+                        machine: other,
+                        pos: seq.partition_point(|&o| self.heads[o] < head),
+                    });
+                }
+            }
+        }
+        (n5_moves, n7_moves)
+    }
+
+    fn to_solution(&self, instance: &Instance, schedule: &Schedule) -> Solution {
+        Solution {
+            job_schedule: (0..instance.job_first_op.len() - 1)
+                .map(|job| {
+                    (instance.job_first_op[job]..instance.job_first_op[job + 1])
+                        .map(|op| (schedule.machine_of[op], self.heads[op]))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,717 @@
+// TIG's UI uses the pattern `tig_challenges::<challenge_name>` to automatically detect your algorithm's challenge
+use anyhow::{anyhow, Result};
+use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tig_challenges::job_scheduling::*;
+
+const NONE: usize = usize::MAX;
+
+#[derive(Serialize, Deserialize)]
+pub struct Hyperparameters {
+    pub num_restarts: usize,
+    pub population_size: usize,
+    pub num_generations: usize,
+    pub num_elites: usize,
+    pub tournament_size: usize,
+    pub mutation_rate: f64,
+    pub local_search_steps: usize,
+}
+
+impl Default for Hyperparameters {
+    fn default() -> Self {
+        Self {
+            num_restarts: 100,
+            population_size: 50,
+            num_generations: 200,
+            num_elites: 2,
+            tournament_size: 3,
+            mutation_rate: 0.2,
+            local_search_steps: 100,
+        }
+    }
+}
+
+pub fn help() {
+    println!("Genetic algorithm with machine assignment + operation sequence encoding.");
+    println!("Chromosomes are decoded into active schedules by inserting operations into the");
+    println!("earliest idle gap on their assigned machine. The population is seeded with");
+    println!("randomised dispatching solutions, and the elites and the best child of each generation are improved");
+    println!("by a descent over N5 swaps and machine reassignments on its critical path.");
+    println!();
+    println!("Hyperparameters:");
+    println!("  num_restarts    - randomised dispatching runs used to seed the population (default: 100)");
+    println!("  population_size - number of chromosomes (default: 50)");
+    println!("  num_generations - number of generations (default: 200)");
+    println!("  num_elites      - best chromosomes copied unchanged into the next generation (default: 2)");
+    println!("  tournament_size - chromosomes competing in each parent selection (default: 3)");
+    println!("  mutation_rate   - probability of mutating each part of a child (default: 0.2)");
+    println!("  local_search_steps - descent steps applied to the best child of each generation (default: 100)");
+}
+
+pub fn solve_challenge(
+    challenge: &Challenge,
+    save_solution: &dyn Fn(&Solution) -> Result<()>,
+    hyperparameters: &Option<Map<String, Value>>,
+) -> Result<()> {
+    let hyperparameters = match hyperparameters {
+        Some(hyperparameters) => {
+            serde_json::from_value::<Hyperparameters>(Value::Object(hyperparameters.clone()))
+                .map_err(|e| anyhow!("Failed to parse hyperparameters: {}", e))?
+        }
+        None => Hyperparameters::default(),
+    };
+    let population_size = hyperparameters.population_size.max(2);
+    let mut rng = SmallRng::from_seed(challenge.seed);
+    let instance = Instance::new(challenge);
+
+    // Step 1: Seed the population with the best distinct dispatching solutions, and fill the
+    // rest with random sequences on shortest processing time or random machines
+    let mut seeds = Vec::new();
+    // earliest due date and minimum slack rules only apply to weighted tardiness
+    let num_rules = if challenge.has_due_dates() {
+        NUM_RULES + 2
+    } else {
+        NUM_RULES
+    };
+    for restart in 0..hyperparameters.num_restarts.max(num_rules) {
+        let rule = restart % num_rules;
+        let top_k = if restart < num_rules {
+            1
+        } else {
+            2 + restart % 4
+        };
+        let builder = dispatch(challenge, &instance, rule, top_k, &mut rng)?;
+        seeds.push(Chromosome::encode(&instance, &builder.to_solution()?));
+    }
+    let mut population = Vec::with_capacity(population_size);
+    for mut chromosome in seeds {
+        chromosome.objective = chromosome.decode(challenge, &instance)?.objective();
+        population.push(chromosome);
+    }
+    population.sort_by_key(|c| c.objective);
+    population.dedup_by(|a, b| a.os == b.os && a.ma == b.ma);
+    population.truncate(population_size / 2);
+    while population.len() < population_size {
+        let mut chromosome = Chromosome::random(&instance, &mut rng);
+        chromosome.objective = chromosome.decode(challenge, &instance)?.objective();
+        population.push(chromosome);
+    }
+    let mut evaluator = Evaluator::new(&instance);
+    for chromosome in population.iter_mut().take(hyperparameters.num_elites) {
+        local_search(
+            challenge,
+            &instance,
+            &mut evaluator,
+            chromosome,
+            hyperparameters.local_search_steps,
+        )?;
+    }
+    population.sort_by_key(|c| c.objective);
+    save_solution(&population[0].decode(challenge, &instance)?.to_solution()?)?;
+
+    // Step 2: Evolve with tournament selection, POX + uniform crossover and elitism
+    let mut best_objective = population[0].objective;
+    for _ in 0..hyperparameters.num_generations {
+        let mut next = population[..hyperparameters.num_elites.min(population_size)].to_vec();
+        while next.len() < population_size {
+            let p1 = tournament(&population, hyperparameters.tournament_size, &mut rng);
+            let p2 = tournament(&population, hyperparameters.tournament_size, &mut rng);
+            let mut child = p1.crossover(p2, &instance, &mut rng);
+            child.mutate(&instance, hyperparameters.mutation_rate, &mut rng);
+            child.objective = child.decode(challenge, &instance)?.objective();
+            next.push(child);
+        }
+        next.sort_by_key(|c| c.objective);
+        let elites = hyperparameters.num_elites.min(population_size);
+        if let Some(child) = next[elites..].iter_mut().min_by_key(|c| c.objective) {
+            local_search(
+                challenge,
+                &instance,
+                &mut evaluator,
+                child,
+                hyperparameters.local_search_steps,
+            )?;
+        }
+        next.sort_by_key(|c| c.objective);
+        population = next;
+        if population[0].objective < best_objective {
+            best_objective = population[0].objective;
+            save_solution(&population[0].decode(challenge, &instance)?.to_solution()?)?;
+        }
+    }
+    Ok(())
+}
+
+const NUM_RULES: usize = 5;
+
+struct Instance {
+    job_first_op: Vec<usize>,
+    job_prev: Vec<usize>,
+    job_next: Vec<usize>,
+    op_machines: Vec<Vec<(usize, u32)>>,
+    num_machines: usize,
+}
+
+impl Instance {
+    fn new(challenge: &Challenge) -> Self {
+        let builder = ScheduleBuilder::new(challenge);
+        let mut job_first_op = vec![0];
+        let mut job_prev = Vec::new();
+        let mut job_next = Vec::new();
+        let mut op_machines = Vec::new();
+        for job in 0..builder.num_jobs() {
+            let first = op_machines.len();
+            let num_ops = builder.num_ops(job);
+            for op_idx in 0..num_ops {
+                let mut machines = builder
+                    .processing_times(job, op_idx)
+                    .iter()
+                    .map(|(&m, &t)| (m, t))
+                    .collect::<Vec<_>>();
+                machines.sort_unstable();
+                op_machines.push(machines);
+                job_prev.push(if op_idx == 0 {
+                    NONE
+                } else {
+                    first + op_idx - 1
+                });
+                job_next.push(if op_idx + 1 == num_ops {
+                    NONE
+                } else {
+                    first + op_idx + 1
+                });
+            }
+            job_first_op.push(op_machines.len());
+        }
+        Self {
+            job_first_op,
+            job_prev,
+            job_next,
+            op_machines,
+            num_machines: challenge.num_machines,
+        }
+    }
+
+    fn num_jobs(&self) -> usize {
+        self.job_first_op.len() - 1
+    }
+
+    fn num_ops(&self) -> usize {
+        self.op_machines.len()
+    }
+
+    fn processing_time(&self, op: usize, machine: usize) -> u32 {
+        self.op_machines[op]
+            .iter()
+            .find(|&&(m, _)| m == machine)
+            .unwrap()
+            .1
+    }
+}
+
+#[derive(Clone)]
+struct Chromosome {
+    // operation sequence: job ids, the k-th occurrence of a job is its k-th operation
+    os: Vec<usize>,
+    // machine assignment: machine of each operation
+    ma: Vec<usize>,
+    // Challenge::evaluate_objective of the decoded schedule
+    objective: u32,
+}
+
+impl Chromosome {
+    fn encode(instance: &Instance, solution: &Solution) -> Self {
+        let mut ops = Vec::with_capacity(instance.num_ops());
+        let mut ma = vec![0; instance.num_ops()];
+        for (job, schedule) in solution.job_schedule.iter().enumerate() {
+            for (op_idx, &(machine, start)) in schedule.iter().enumerate() {
+                ops.push((start, job));
+                ma[instance.job_first_op[job] + op_idx] = machine;
+            }
+        }
+        ops.sort_unstable();
+        Self {
+            os: ops.into_iter().map(|(_, job)| job).collect(),
+            ma,
+            objective: u32::MAX,
+        }
+    }
+
+    fn random(instance: &Instance, rng: &mut SmallRng) -> Self {
+        let mut os = (0..instance.num_jobs())
+            .flat_map(|job| {
+                std::iter::repeat_n(
+                    job,
+                    instance.job_first_op[job + 1] - instance.job_first_op[job],
+                )
+            })
+            .collect::<Vec<usize>>();
+        os.shuffle(rng);
+        let shortest = rng.gen_bool(0.5);
+        let ma = instance
+            .op_machines
+            .iter()
+            .map(|machines| {
+                if shortest {
+                    machines.iter().min_by_key(|&&(_, t)| t).unwrap().0
+                } else {
+                    machines.choose(rng).unwrap().0
+                }
+            })
+            .collect();
+        Self {
+            os,
+            ma,
+            objective: u32::MAX,
+        }
+    }
+
+    fn decode<'a>(
+        &self,
+        challenge: &'a Challenge,
+        instance: &Instance,
+    ) -> Result<ScheduleBuilder<'a>> {
+        let mut builder = ScheduleBuilder::new(challenge);
+        for &job in &self.os {
+            let op_idx = builder
+                .next_op(job)
+                .ok_or_else(|| anyhow!("Job {} has no remaining operations", job))?;
+            builder.insert(job, self.ma[instance.job_first_op[job] + op_idx])?;
+        }
+        Ok(builder)
+    }
+
+    // POX for the operation sequence: jobs in a random subset keep their positions from self,
+    // the remaining positions are filled in the order of other. Uniform crossover for machines
+    fn crossover(&self, other: &Self, instance: &Instance, rng: &mut SmallRng) -> Self {
+        let keep = (0..instance.num_jobs())
+            .map(|_| rng.gen_bool(0.5))
+            .collect::<Vec<bool>>();
+        let mut fill = other.os.iter().filter(|&&job| !keep[job]);
+        let os = self
+            .os
+            .iter()
+            .map(|&job| {
+                if keep[job] {
+                    job
+                } else {
+                    *fill.next().unwrap()
+                }
+            })
+            .collect();
+        let ma = self
+            .ma
+            .iter()
+            .zip(&other.ma)
+            .map(|(&a, &b)| if rng.gen_bool(0.5) { a } else { b })
+            .collect();
+        Self {
+            os,
+            ma,
+            objective: u32::MAX,
+        }
+    }
+
+    fn mutate(&mut self, instance: &Instance, mutation_rate: f64, rng: &mut SmallRng) {
+        if rng.gen_bool(mutation_rate) {
+            // move a random operation a short distance along the sequence
+            let len = self.os.len();
+            let from = rng.gen_range(0..len);
+            let window = instance.num_jobs().max(1);
+            let to = rng.gen_range(from.saturating_sub(window)..(from + window).min(len));
+            let job = self.os.remove(from);
+            self.os.insert(to, job);
+        }
+        if rng.gen_bool(mutation_rate) {
+            let op = rng.gen_range(0..instance.num_ops());
+            self.ma[op] = instance.op_machines[op].choose(rng).unwrap().0;
+        }
+    }
+}
+
+// Steepest descent on the disjunctive graph of the decoded chromosome. The improved schedule
+// is encoded back into the chromosome (Lamarckian learning)
+fn local_search(
+    challenge: &Challenge,
+    instance: &Instance,
+    evaluator: &mut Evaluator,
+    chromosome: &mut Chromosome,
+    max_steps: usize,
+) -> Result<()> {
+    // the disjunctive graph only models makespan without setups, releases or breakdowns
+    if challenge.has_due_dates() || challenge.has_side_constraints() {
+        return Ok(());
+    }
+    let solution = chromosome.decode(challenge, instance)?.to_solution()?;
+    let mut schedule = Schedule::new(instance, &solution);
+    let mut makespan = evaluator.evaluate(instance, &schedule).unwrap();
+    for _ in 0..max_steps {
+        evaluator.evaluate(instance, &schedule).unwrap();
+        let mut best_move = None;
+        for mv in evaluator.neighbourhood(instance, &schedule) {
+            let undo = schedule.apply(instance, mv);
+            let result = evaluator.evaluate(instance, &schedule);
+            schedule.apply(instance, undo);
+            if let Some(m) = result.filter(|&m| m < makespan) {
+                makespan = m;
+                best_move = Some(mv);
+            }
+        }
+        match best_move {
+            Some(mv) => {
+                schedule.apply(instance, mv);
+            }
+            None => break,
+        }
+    }
+    evaluator.evaluate(instance, &schedule).unwrap();
+    *chromosome = Chromosome::encode(instance, &evaluator.to_solution(instance, &schedule));
+    chromosome.objective = chromosome.decode(challenge, instance)?.objective();
+    Ok(())
+}
+
+fn tournament<'a>(
+    population: &'a [Chromosome],
+    tournament_size: usize,
+    rng: &mut SmallRng,
+) -> &'a Chromosome {
+    (0..tournament_size.max(1))
+        .map(|_| &population[rng.gen_range(0..population.len())])
+        .min_by_key(|c| c.objective)
+        .unwrap()
+}
+
+// Non-delay dispatching. Whenever a machine is idle, it is given one of the ready operations for
+// which it is the machine with the earliest completion, chosen by the priority rule (randomised
+// over the top_k best when top_k > 1)
+fn dispatch<'a>(
+    challenge: &'a Challenge,
+    instance: &Instance,
+    rule: usize,
+    top_k: usize,
+    rng: &mut SmallRng,
+) -> Result<ScheduleBuilder<'a>> {
+    let mut builder = ScheduleBuilder::new(challenge);
+    let num_jobs = builder.num_jobs();
+    let mut remaining_work = (0..num_jobs)
+        .map(|job| {
+            (instance.job_first_op[job]..instance.job_first_op[job + 1])
+                .map(|op| work(instance, op))
+                .sum::<f64>()
+        })
+        .collect::<Vec<f64>>();
+    let mut machines = (0..instance.num_machines).collect::<Vec<usize>>();
+    let mut time = 0;
+
+    while !builder.is_complete() {
+        if top_k > 1 {
+            machines.shuffle(rng);
+        }
+        for &machine in &machines {
+            if builder.machine_available_time(machine) > time {
+                continue;
+            }
+            let mut candidates = Vec::new();
+            for (job, &job_work) in remaining_work.iter().enumerate() {
+                let op = match builder.next_op(job) {
+                    Some(op_idx) if builder.job_ready_time(job) <= time => {
+                        instance.job_first_op[job] + op_idx
+                    }
+                    _ => continue,
+                };
+                let proc_time = match instance.op_machines[op]
+                    .iter()
+                    .find(|&&(m, _)| m == machine)
+                {
+                    Some(&(_, proc_time)) => proc_time,
+                    None => continue,
+                };
+                let earliest_end = instance.op_machines[op]
+                    .iter()
+                    .map(|&(m, t)| time.max(builder.machine_available_time(m)) + t)
+                    .min()
+                    .unwrap();
+                if time + proc_time != earliest_end {
+                    continue;
+                }
+                let priority = match rule {
+                    0 => -job_work,
+                    1 => -((instance.job_first_op[job + 1] - op) as f64),
+                    2 => instance.op_machines[op].len() as f64,
+                    3 => proc_time as f64,
+                    4 => -(proc_time as f64),
+                    5 => challenge.job_due_dates[job] as f64,
+                    _ => challenge.job_due_dates[job] as f64 - time as f64 - job_work,
+                };
+                candidates.push((priority, proc_time, job, op));
+            }
+            if candidates.is_empty() {
+                continue;
+            }
+            candidates.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+            let pick = rng.gen_range(0..top_k.max(1).min(candidates.len()));
+            let (_, _, job, op) = candidates[pick];
+            builder.append(job, machine)?;
+            remaining_work[job] -= work(instance, op);
+        }
+
+        time = (0..instance.num_machines)
+            .map(|m| builder.machine_available_time(m))
+            .chain(
+                (0..num_jobs)
+                    .filter(|&job| builder.next_op(job).is_some())
+                    .map(|job| builder.job_ready_time(job)),
+            )
+            .filter(|&t| t > time)
+            .min()
+            .unwrap_or(time);
+    }
+    Ok(builder)
+}
+
+// blend of average and minimum processing time over eligible machines
+fn work(instance: &Instance, op: usize) -> f64 {
+    let machines = &instance.op_machines[op];
+    let min = machines.iter().map(|&(_, t)| t).min().unwrap_or(0) as f64;
+    let avg = machines.iter().map(|&(_, t)| t as f64).sum::<f64>() / machines.len().max(1) as f64;
+    avg * 0.7 + min * 0.3
+}
+
+#[derive(Clone, Copy)]
+enum Move {
+    // move the operation at position `from` to position `to` in the sequence of `machine`
+    Shift {
+        machine: usize,
+        from: usize,
+        to: usize,
+    },
+    // move `op` to position `pos` in the sequence of `machine`
+    Reassign {
+        op: usize,
+        machine: usize,
+        pos: usize,
+    },
+}
+
+#[derive(Clone)]
+struct Schedule {
+    machine_of: Vec<usize>,
+    proc_time: Vec<u32>,
+    machine_seq: Vec<Vec<usize>>,
+}
+
+impl Schedule {
+    fn new(instance: &Instance, solution: &Solution) -> Self {
+        let mut machine_of = vec![0; instance.num_ops()];
+        let mut proc_time = vec![0; instance.num_ops()];
+        let mut machine_ops = vec![Vec::new(); instance.num_machines];
+        for (job, schedule) in solution.job_schedule.iter().enumerate() {
+            for (op_idx, &(machine, start)) in schedule.iter().enumerate() {
+                let op = instance.job_first_op[job] + op_idx;
+                machine_of[op] = machine;
+                proc_time[op] = instance.processing_time(op, machine);
+                machine_ops[machine].push((start, op));
+            }
+        }
+        let machine_seq = machine_ops
+            .into_iter()
+            .map(|mut ops| {
+                ops.sort_unstable();
+                ops.into_iter().map(|(_, op)| op).collect()
+            })
+            .collect();
+        Self {
+            machine_of,
+            proc_time,
+            machine_seq,
+        }
+    }
+
+    // applies the move and returns the move that undoes it
+    fn apply(&mut self, instance: &Instance, mv: Move) -> Move {
+        match mv {
+            Move::Shift { machine, from, to } => {
+                let op = self.machine_seq[machine].remove(from);
+                self.machine_seq[machine].insert(to, op);
+                Move::Shift {
+                    machine,
+                    from: to,
+                    to: from,
+                }
+            }
+            Move::Reassign { op, machine, pos } => {
+                let old_machine = self.machine_of[op];
+                let old_pos = self.machine_seq[old_machine]
+                    .iter()
+                    .position(|&o| o == op)
+                    .unwrap();
+                self.machine_seq[old_machine].remove(old_pos);
+                self.machine_seq[machine].insert(pos, op);
+                self.machine_of[op] = machine;
+                self.proc_time[op] = instance.processing_time(op, machine);
+                Move::Reassign {
+                    op,
+                    machine: old_machine,
+                    pos: old_pos,
+                }
+            }
+        }
+    }
+}
+
+struct Evaluator {
+    heads: Vec<u32>,
+    in_degree: Vec<u32>,
+    machine_prev: Vec<usize>,
+    machine_next: Vec<usize>,
+    seq_pos: Vec<usize>,
+    queue: Vec<usize>,
+}
+
+impl Evaluator {
+    fn new(instance: &Instance) -> Self {
+        let n = instance.num_ops();
+        Self {
+            heads: vec![0; n],
+            in_degree: vec![0; n],
+            machine_prev: vec![NONE; n],
+            machine_next: vec![NONE; n],
+            seq_pos: vec![0; n],
+            queue: Vec::with_capacity(n),
+        }
+    }
+
+    // longest path from the source to every operation. Returns None if the graph is cyclic
+    fn evaluate(&mut self, instance: &Instance, schedule: &Schedule) -> Option<u32> {
+        for seq in &schedule.machine_seq {
+            for (pos, &op) in seq.iter().enumerate() {
+                self.seq_pos[op] = pos;
+                self.machine_prev[op] = if pos == 0 { NONE } else { seq[pos - 1] };
+                self.machine_next[op] = seq.get(pos + 1).copied().unwrap_or(NONE);
+            }
+        }
+        self.queue.clear();
+        for op in 0..instance.num_ops() {
+            self.heads[op] = 0;
+            self.in_degree[op] =
+                (instance.job_prev[op] != NONE) as u32 + (self.machine_prev[op] != NONE) as u32;
+            if self.in_degree[op] == 0 {
+                self.queue.push(op);
+            }
+        }
+        let mut makespan = 0;
+        let mut i = 0;
+        while i < self.queue.len() {
+            let op = self.queue[i];
+            i += 1;
+            let end = self.heads[op] + schedule.proc_time[op];
+            makespan = makespan.max(end);
+            for next in [instance.job_next[op], self.machine_next[op]] {
+                if next != NONE {
+                    self.heads[next] = self.heads[next].max(end);
+                    self.in_degree[next] -= 1;
+                    if self.in_degree[next] == 0 {
+                        self.queue.push(next);
+                    }
+                }
+            }
+        }
+        (self.queue.len() == instance.num_ops()).then_some(makespan)
+    }
+
+    // critical path of the last evaluated schedule, split into blocks of consecutive
+    // operations on the same machine. Each block is (machine, first seq pos, last seq pos)
+    fn critical_blocks(
+        &self,
+        instance: &Instance,
+        schedule: &Schedule,
+    ) -> Vec<(usize, usize, usize)> {
+        let mut op = (0..instance.num_ops())
+            .max_by_key(|&op| {
+                (
+                    self.heads[op] + schedule.proc_time[op],
+                    std::cmp::Reverse(op),
+                )
+            })
+            .unwrap();
+        let mut path = vec![op];
+        while self.heads[op] > 0 {
+            let head = self.heads[op];
+            let mp = self.machine_prev[op];
+            let jp = instance.job_prev[op];
+            op = if mp != NONE && self.heads[mp] + schedule.proc_time[mp] == head {
+                mp
+            } else if jp != NONE && self.heads[jp] + schedule.proc_time[jp] == head {
+                jp
+            } else {
+                break;
+            };
+            path.push(op);
+        }
+        path.reverse();
+
+        let mut blocks: Vec<(usize, usize, usize)> = Vec::new();
+        for &op in &path {
+            let machine = schedule.machine_of[op];
+            let pos = self.seq_pos[op];
+            match blocks.last_mut() {
+                Some(block) if block.0 == machine && block.2 + 1 == pos => block.2 = pos,
+                _ => blocks.push((machine, pos, pos)),
+            }
+        }
+        blocks
+    }
+
+    // N5 swaps of the first/last two operations of each critical block, and reassignments of
+    // critical operations to other eligible machines at the position matching their head
+    fn neighbourhood(&self, instance: &Instance, schedule: &Schedule) -> Vec<Move> {
+        let blocks = self.critical_blocks(instance, schedule);
+        let num_blocks = blocks.len();
+        let mut moves = Vec::new();
+        for (b, &(machine, first, last)) in blocks.iter().enumerate() {
+            if b > 0 && first < last {
+                moves.push(Move::Shift {
+                    machine,
+                    from: first + 1,
+                    to: first,
+                });
+            }
+            if b + 1 < num_blocks && first < last && (b == 0 || first + 1 < last) {
+                moves.push(Move::Shift {
+                    machine,
+                    from: last - 1,
+                    to: last,
+                });
+            }
+            for pos in first..=last {
+                let op = schedule.machine_seq[machine][pos];
+                for &(other, _) in &instance.op_machines[op] {
+                    if other == machine {
+                        continue;
+                    }
+                    let seq = &schedule.machine_seq[other];
+                    let head = self.heads[op];
+                    moves.push(Move::Reassign {
+                        op,
+                        machine: other,
+                        pos: seq.partition_point(|&o| self.heads[o] < head),
+                    });
+                }
+            }
+        }
+        moves
+    }
+
+    fn to_solution(&self, instance: &Instance, schedule: &Schedule) -> Solution {
+        Solution {
+            job_schedule: (0..instance.job_first_op.len() - 1)
+                .map(|job| {
+                    (instance.job_first_op[job]..instance.job_first_op[job + 1])
+                        .map(|op| (schedule.machine_of[op], self.heads[op]))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+}
@@ -1,6 +1,8 @@
-// c007_a001
+pub mod disjunctive_tabu;
+pub use disjunctive_tabu as c007_a001;
 
-// c007_a002
+pub mod genetic_fjsp;
+pub use genetic_fjsp as c007_a002;
 
 // c007_a003
 
@@ -0,0 +1,55 @@
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use tig_algorithms::job_scheduling::{disjunctive_tabu, genetic_fjsp};
+use tig_challenges::job_scheduling::{Challenge, Scenario, Solution, Track};
+
+type SolveFn = fn(
+    &Challenge,
+    &dyn Fn(&Solution) -> anyhow::Result<()>,
+    &Option<Map<String, Value>>,
+) -> anyhow::Result<()>;
+
+// objectives of the sota baseline on the instances below. tig-algorithms hides the baselines
+// behind `hide_verification`, so they are recorded from `Challenge::compute_sota_baseline`
+const SOTA_OBJECTIVES: [(Scenario, u32); 5] = [
+    (Scenario::FLOW_SHOP, 22878),
+    (Scenario::HYBRID_FLOW_SHOP, 8954),
+    (Scenario::JOB_SHOP, 11345),
+    (Scenario::FJSP_MEDIUM, 9723),
+    (Scenario::FJSP_HIGH, 5555),
+];
+
+// slow without optimisations, run with `cargo test --release --features c007 -- --ignored`
+#[test]
+#[ignore]
+fn test_algorithms_beat_sota_baseline() {
+    let algorithms: [(&str, SolveFn); 2] = [
+        ("disjunctive_tabu", disjunctive_tabu::solve_challenge),
+        ("genetic_fjsp", genetic_fjsp::solve_challenge),
+    ];
+    for (s, sota_objective) in SOTA_OBJECTIVES {
+        let challenge = Challenge::generate_instance(&[5u8; 32], &Track { n: 50, s }).unwrap();
+        for (name, solve_challenge) in algorithms {
+            let solution = RefCell::new(None);
+            solve_challenge(
+                &challenge,
+                &|s: &Solution| {
+                    *solution.borrow_mut() = Some(s.clone());
+                    Ok(())
+                },
+                &None,
+            )
+            .unwrap();
+            let solution = solution.into_inner().unwrap();
+            let objective = challenge.evaluate_objective(&solution).unwrap();
+            assert!(
+                objective < sota_objective,
+                "{} on {}: {} not better than sota {}",
+                name,
+                s,
+                objective,
+                sota_objective
+            );
+        }
+    }
+}
@@ -22,6 +22,8 @@ class CodeState(FromDict):
     round_active: Optional[int]
     round_merged: Optional[int]
     banned: bool
+    lifecycle: List[dict]
+    bans: List[dict]
 
 @dataclass
 class CodeBlockData(FromDict):
@@ -221,9 +223,11 @@ class PlayerDetails(FromDict):
 class PlayerState(FromDict):
     total_fees_paid: PreciseNumber
     available_fee_balance: PreciseNumber
-    delegatees: Optional[dict]
+    delegatees: List[dict]
     votes: dict
-    reward_share: Optional[dict]
+    vote_delegatee: Optional[dict]
+    reward_share: List[dict]
+    coinbase: List[dict]
 
 @dataclass
 class PlayerBlockData(FromDict):
@@ -239,10 +243,18 @@ class Player(FromDict):
     state: PlayerState
     block_data: Optional[PlayerBlockData]
 
+@dataclass
+class BinaryArtifact(FromDict):
+    compile_success: bool
+    hash: Optional[str]
+
 @dataclass
 class BinaryDetails(FromDict):
     compile_success: bool
     download_url: Optional[str]
+    so_by_arch: Dict[str, BinaryArtifact]
+    ptx: Optional[BinaryArtifact]
+    toolchain_version: Optional[str]
 
 @dataclass
 class BinaryState(FromDict):
@@ -63,6 +63,14 @@ class JobManager:
             if bin.details.download_url is None:
                 logger.error(f"batch {x.benchmark_id}: no download_url found for {bin.algorithm_id}. skipping job")
                 continue
+            # hashes tig-runtime checks the compiled artifacts against
+            so_hashes = {
+                arch: artifact.hash
+                for arch, artifact in bin.details.so_by_arch.items()
+                if artifact.compile_success
+            }
+            ptx = bin.details.ptx
+            ptx_hash = ptx.hash if ptx is not None and ptx.compile_success else None
             batch_size = next(
                 (s["batch_size"] for s in algo_selection if s["algorithm_id"] == x.settings.algorithm_id),
                 None
@@ -87,10 +95,12 @@ class JobManager:
                         challenge,
                         algorithm,
                         download_url,
+                        so_hashes,
+                        ptx_hash,
                         block_started,
                         start_time
                     )
-                    VALUES (%s, %s, %s, %s, %s, %s, %s, %s, %s, %s, %s, %s, (EXTRACT(EPOCH FROM NOW()) * 1000)::BIGINT)
+                    VALUES (%s, %s, %s, %s, %s, %s, %s, %s, %s, %s, %s, %s, %s, %s, (EXTRACT(EPOCH FROM NOW()) * 1000)::BIGINT)
                     ON CONFLICT (benchmark_id) DO NOTHING;
                     """,
                     (
@@ -105,6 +115,8 @@ class JobManager:
                         c_name,
                         a_name,
                         bin.details.download_url,
+                        json.dumps(so_hashes),
+                        ptx_hash,
                         x.details.block_started,
                     )
                 ),
@@ -45,6 +45,8 @@ class SlaveManager:
                             'sampled_nonces', A.sampled_nonces,
                             'fuel_budget', B.fuel_budget,
                             'download_url', B.download_url,
+                            'so_hashes', B.so_hashes,
+                            'ptx_hash', B.ptx_hash,
                             'rand_hash', B.rand_hash,
                             'batch_size', B.batch_size,
                             'batch_idx', A.batch_idx,
@@ -78,6 +80,8 @@ class SlaveManager:
                             'sampled_nonces', NULL,
                             'fuel_budget', B.fuel_budget,
                             'download_url', B.download_url,
+                            'so_hashes', B.so_hashes,
+                            'ptx_hash', B.ptx_hash,
                             'rand_hash', B.rand_hash,
                             'batch_size', B.batch_size,
                             'batch_idx', A.batch_idx,
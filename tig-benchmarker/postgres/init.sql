@@ -14,6 +14,8 @@ CREATE TABLE IF NOT EXISTS job (
     challenge TEXT NOT NULL,
     algorithm TEXT NOT NULL,
     download_url TEXT NOT NULL,
+    so_hashes JSONB NOT NULL DEFAULT '{}',
+    ptx_hash TEXT,
     block_started INTEGER NOT NULL,
     sampled_nonces JSONB,
     benchmark_submit_time BIGINT,
@@ -27,6 +29,10 @@ CREATE TABLE IF NOT EXISTS job (
     stopped BOOLEAN
 );
 
+-- columns added after the table was first created
+ALTER TABLE job ADD COLUMN IF NOT EXISTS so_hashes JSONB NOT NULL DEFAULT '{}';
+ALTER TABLE job ADD COLUMN IF NOT EXISTS ptx_hash TEXT;
+
 CREATE INDEX idx_job_batch_size ON job(batch_size);
 CREATE INDEX idx_job_block_started ON job(block_started);
 CREATE INDEX idx_job_challenge ON job(challenge);
@@ -75,10 +75,21 @@ def run_tig_runtime(nonce, batch, so_path, ptx_path, results_dir):
         cmd += [
             "--hyperparameters", json.dumps(batch["hyperparameters"], separators=(',',':')),
         ]
+    # legacy binaries have no hashes, and are run unchecked
+    if len(batch["so_hashes"]) > 0:
+        if CPU_ARCH not in batch["so_hashes"]:
+            raise Exception(f"no approved binary for {CPU_ARCH}")
+        cmd += [
+            "--so-hash", batch["so_hashes"][CPU_ARCH],
+        ]
     if ptx_path is not None:
         cmd += [
             "--ptx", ptx_path,
         ]
+        if batch["ptx_hash"] is not None:
+            cmd += [
+                "--ptx-hash", batch["ptx_hash"],
+            ]
     logger.debug(f"computing nonce: {' '.join(cmd[:4] + [f"'{cmd[4]}'"] + cmd[5:])}")
     process = subprocess.Popen(
         cmd, stdout=subprocess.PIPE, stderr=subprocess.PIPE, text=True
@@ -21,14 +21,41 @@ fn min_processing_time(operation: &HashMap<usize, u32>) -> f64 {
     operation.values().copied().min().unwrap_or(0) as f64
 }
 
+// earliest start of an operation on a machine, after setup and outside unavailability windows
+fn op_start_time(
+    challenge: &Challenge,
+    time: u32,
+    machine_available_time: &[u32],
+    machine_last_product: &[Option<usize>],
+    machine: usize,
+    product: usize,
+    proc_time: u32,
+) -> u32 {
+    let setup_time =
+        machine_last_product[machine].map_or(0, |prev| challenge.setup_time(prev, product));
+    let ready = time.max(machine_available_time[machine]) + setup_time;
+    challenge.earliest_available_start(machine, ready, proc_time)
+}
+
 fn earliest_end_time(
+    challenge: &Challenge,
     time: u32,
     machine_available_time: &[u32],
+    machine_last_product: &[Option<usize>],
+    product: usize,
     operation: &HashMap<usize, u32>,
 ) -> u32 {
     let mut earliest_end = u32::MAX;
     for (&machine_id, &proc_time) in operation.iter() {
-        let start = time.max(machine_available_time[machine_id]);
+        let start = op_start_time(
+            challenge,
+            time,
+            machine_available_time,
+            machine_last_product,
+            machine_id,
+            product,
+            proc_time,
+        );
         let end = start + proc_time;
         if end < earliest_end {
             earliest_end = end;
@@ -44,6 +71,8 @@ enum DispatchRule {
     LeastFlexibility,
     ShortestProcTime,
     LongestProcTime,
+    EarliestDueDate,
+    MinimumSlack,
 }
 
 #[derive(Clone, Copy)]
@@ -57,11 +86,12 @@ struct Candidate {
 
 struct ScheduleResult {
     job_schedule: Vec<Vec<(usize, u32)>>,
-    makespan: u32,
+    // weighted tardiness if the challenge has due dates, otherwise makespan
+    objective: u32,
 }
 
 struct RestartResult {
-    makespan: u32,
+    objective: u32,
     rule: DispatchRule,
     random_top_k: usize,
     seed: u64,
@@ -106,8 +136,11 @@ fn run_dispatch_rule(
     let num_machines = challenge.num_machines;
 
     let mut job_next_op_idx = vec![0usize; num_jobs];
-    let mut job_ready_time = vec![0u32; num_jobs];
+    let mut job_ready_time = (0..num_jobs)
+        .map(|job| challenge.release_time(job))
+        .collect::<Vec<u32>>();
     let mut machine_available_time = vec![0u32; num_machines];
+    let mut machine_last_product = vec![None; num_machines];
     let mut job_schedule = job_ops_len
         .iter()
         .map(|&ops_len| Vec::with_capacity(ops_len))
@@ -153,8 +186,23 @@ fn run_dispatch_rule(
                         None => continue,
                     };
 
-                    let earliest_end = earliest_end_time(time, &machine_available_time, op_times);
-                    let machine_end = time.max(machine_available_time[machine]) + proc_time;
+                    let earliest_end = earliest_end_time(
+                        challenge,
+                        time,
+                        &machine_available_time,
+                        &machine_last_product,
+                        product,
+                        op_times,
+                    );
+                    let machine_end = op_start_time(
+                        challenge,
+                        time,
+                        &machine_available_time,
+                        &machine_last_product,
+                        machine,
+                        product,
+                        proc_time,
+                    ) + proc_time;
                     if machine_end != earliest_end {
                         continue;
                     }
@@ -168,6 +216,12 @@ fn run_dispatch_rule(
                         DispatchRule::LeastFlexibility => -(flexibility as f64),
                         DispatchRule::ShortestProcTime => -(proc_time as f64),
                         DispatchRule::LongestProcTime => proc_time as f64,
+                        DispatchRule::EarliestDueDate => -(challenge.job_due_dates[job] as f64),
+                        DispatchRule::MinimumSlack => {
+                            -(challenge.job_due_dates[job] as f64
+                                - time as f64
+                                - job_remaining_work[job])
+                        }
                     };
 
                     candidates.push(Candidate {
@@ -223,8 +277,23 @@ fn run_dispatch_rule(
                         None => continue,
                     };
 
-                    let earliest_end = earliest_end_time(time, &machine_available_time, op_times);
-                    let machine_end = time.max(machine_available_time[machine]) + proc_time;
+                    let earliest_end = earliest_end_time(
+                        challenge,
+                        time,
+                        &machine_available_time,
+                        &machine_last_product,
+                        product,
+                        op_times,
+                    );
+                    let machine_end = op_start_time(
+                        challenge,
+                        time,
+                        &machine_available_time,
+                        &machine_last_product,
+                        machine,
+                        product,
+                        proc_time,
+                    ) + proc_time;
                     if machine_end != earliest_end {
                         continue;
                     }
@@ -238,6 +307,12 @@ fn run_dispatch_rule(
                         DispatchRule::LeastFlexibility => -(flexibility as f64),
                         DispatchRule::ShortestProcTime => -(proc_time as f64),
                         DispatchRule::LongestProcTime => proc_time as f64,
+                        DispatchRule::EarliestDueDate => -(challenge.job_due_dates[job] as f64),
+                        DispatchRule::MinimumSlack => {
+                            -(challenge.job_due_dates[job] as f64
+                                - time as f64
+                                - job_remaining_work[job])
+                        }
                     };
 
                     let candidate = Candidate {
@@ -264,13 +339,22 @@ fn run_dispatch_rule(
                 let op_times = &challenge.product_processing_times[product][op_idx];
                 let proc_time = op_times[&machine];
 
-                let start_time = time.max(machine_available_time[machine]);
+                let start_time = op_start_time(
+                    challenge,
+                    time,
+                    &machine_available_time,
+                    &machine_last_product,
+                    machine,
+                    product,
+                    proc_time,
+                );
                 let end_time = start_time + proc_time;
 
                 job_schedule[job].push((machine, start_time));
                 job_next_op_idx[job] += 1;
                 job_ready_time[job] = end_time;
                 machine_available_time[machine] = end_time;
+                machine_last_product[machine] = Some(product);
                 job_remaining_work[job] -= product_work_times[product][op_idx];
                 if job_remaining_work[job] < 0.0 {
                     job_remaining_work[job] = 0.0;
@@ -309,10 +393,27 @@ fn run_dispatch_rule(
         })?;
     }
 
-    let makespan = job_ready_time.iter().copied().max().unwrap_or(0);
+    let objective = if challenge.has_due_dates() {
+        // job_ready_time is the completion time of each job
+        let weighted_tardiness = job_ready_time
+            .iter()
+            .zip(
+                challenge
+                    .job_due_dates
+                    .iter()
+                    .zip(challenge.job_weights.iter()),
+            )
+            .map(|(&completion_time, (&due_date, &weight))| {
+                completion_time.saturating_sub(due_date) as u64 * weight as u64
+            })
+            .sum::<u64>();
+        weighted_tardiness.min(u32::MAX as u64) as u32
+    } else {
+        job_ready_time.iter().copied().max().unwrap_or(0)
+    };
     Ok(ScheduleResult {
         job_schedule,
-        makespan,
+        objective,
     })
 }
 
@@ -396,13 +497,17 @@ fn solve_challenge_with_params(
         job_total_work.push(work_ops.iter().sum());
     }
 
-    let rules = [
+    let mut rules = vec![
         DispatchRule::MostWorkRemaining,
         DispatchRule::MostOpsRemaining,
         DispatchRule::LeastFlexibility,
         DispatchRule::ShortestProcTime,
         DispatchRule::LongestProcTime,
     ];
+    if challenge.has_due_dates() {
+        rules.push(DispatchRule::EarliestDueDate);
+        rules.push(DispatchRule::MinimumSlack);
+    }
 
     let mut best_result: Option<ScheduleResult> = None;
     for rule in rules.iter().copied() {
@@ -418,7 +523,7 @@ fn solve_challenge_with_params(
         )?;
         let is_better = best_result
             .as_ref()
-            .map_or(true, |best| result.makespan < best.makespan);
+            .map_or(true, |best| result.objective < best.objective);
         if is_better {
             best_result = Some(result);
         }
@@ -446,8 +551,8 @@ fn solve_challenge_with_params(
                 Some(random_top_k),
                 Some(&mut local_rng),
             )?;
-            let makespan = result.makespan;
-            let is_better = makespan < best_result.makespan;
+            let objective = result.objective;
+            let is_better = objective < best_result.objective;
             if is_better {
                 best_result = result;
                 save_best(&best_result)?;
@@ -455,12 +560,12 @@ fn solve_challenge_with_params(
 
             if top_k > 0 {
                 top_restarts.push(RestartResult {
-                    makespan,
+                    objective,
                     rule,
                     random_top_k,
                     seed,
                 });
-                top_restarts.sort_by(|a, b| a.makespan.cmp(&b.makespan));
+                top_restarts.sort_by(|a, b| a.objective.cmp(&b.objective));
                 if top_restarts.len() > top_k {
                     top_restarts.pop();
                 }
@@ -489,7 +594,7 @@ fn solve_challenge_with_params(
                     Some(local_k),
                     Some(&mut local_rng),
                 )?;
-                if result.makespan < best_result.makespan {
+                if result.objective < best_result.objective {
                     best_result = result;
                     save_best(&best_result)?;
                 }
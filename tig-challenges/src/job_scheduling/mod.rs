@@ -1,5 +1,6 @@
 use crate::QUALITY_PRECISION;
 mod baselines;
+mod schedule;
 use anyhow::{anyhow, Result};
 use rand::{
     distributions::Distribution,
@@ -7,6 +8,7 @@ use rand::{
     Rng, SeedableRng,
 };
 use rand_distr::Normal;
+pub use schedule::{ScheduleBuilder, ScheduledOp};
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
@@ -16,6 +18,14 @@ pub struct ScenarioConfig {
     pub reentrance_level: f32,
     pub flow_structure: f32,
     pub product_mix_ratio: f32,
+    // mean sequence-dependent setup time between products, relative to the mean processing time. 0 disables setups
+    pub setup_time_ratio: f32,
+    // job release times are spread over this fraction of the estimated horizon
+    pub release_spread: f32,
+    // due date allowance on top of the job's processing time, relative to it. 0 disables due dates and the objective is makespan, otherwise it is total weighted tardiness
+    pub due_date_slack: f32,
+    // fraction of the estimated horizon each machine is unavailable for. 0 disables unavailability windows
+    pub unavailability_ratio: f32,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -25,6 +35,9 @@ pub enum Scenario {
     JOB_SHOP,
     FJSP_MEDIUM,
     FJSP_HIGH,
+    FJSP_SETUP,
+    FJSP_TARDINESS,
+    FJSP_BREAKDOWNS,
 }
 
 impl From<Scenario> for ScenarioConfig {
@@ -35,30 +48,80 @@ impl From<Scenario> for ScenarioConfig {
                 reentrance_level: 0.2,
                 flow_structure: 0.0,
                 product_mix_ratio: 0.5,
+                setup_time_ratio: 0.0,
+                release_spread: 0.0,
+                due_date_slack: 0.0,
+                unavailability_ratio: 0.0,
             },
             Scenario::HYBRID_FLOW_SHOP => ScenarioConfig {
                 avg_op_flexibility: 3.0,
                 reentrance_level: 0.2,
                 flow_structure: 0.0,
                 product_mix_ratio: 0.5,
+                setup_time_ratio: 0.0,
+                release_spread: 0.0,
+                due_date_slack: 0.0,
+                unavailability_ratio: 0.0,
             },
             Scenario::JOB_SHOP => ScenarioConfig {
                 avg_op_flexibility: 1.0,
                 reentrance_level: 0.0,
                 flow_structure: 0.4,
                 product_mix_ratio: 1.0,
+                setup_time_ratio: 0.0,
+                release_spread: 0.0,
+                due_date_slack: 0.0,
+                unavailability_ratio: 0.0,
             },
             Scenario::FJSP_MEDIUM => ScenarioConfig {
                 avg_op_flexibility: 3.0,
                 reentrance_level: 0.2,
                 flow_structure: 0.4,
                 product_mix_ratio: 1.0,
+                setup_time_ratio: 0.0,
+                release_spread: 0.0,
+                due_date_slack: 0.0,
+                unavailability_ratio: 0.0,
             },
             Scenario::FJSP_HIGH => ScenarioConfig {
                 avg_op_flexibility: 10.0,
                 reentrance_level: 0.0,
                 flow_structure: 1.0,
                 product_mix_ratio: 1.0,
+                setup_time_ratio: 0.0,
+                release_spread: 0.0,
+                due_date_slack: 0.0,
+                unavailability_ratio: 0.0,
+            },
+            Scenario::FJSP_SETUP => ScenarioConfig {
+                avg_op_flexibility: 3.0,
+                reentrance_level: 0.2,
+                flow_structure: 0.4,
+                product_mix_ratio: 1.0,
+                setup_time_ratio: 0.3,
+                release_spread: 0.0,
+                due_date_slack: 0.0,
+                unavailability_ratio: 0.0,
+            },
+            Scenario::FJSP_TARDINESS => ScenarioConfig {
+                avg_op_flexibility: 3.0,
+                reentrance_level: 0.2,
+                flow_structure: 0.4,
+                product_mix_ratio: 1.0,
+                setup_time_ratio: 0.0,
+                release_spread: 0.5,
+                due_date_slack: 2.0,
+                unavailability_ratio: 0.0,
+            },
+            Scenario::FJSP_BREAKDOWNS => ScenarioConfig {
+                avg_op_flexibility: 3.0,
+                reentrance_level: 0.2,
+                flow_structure: 0.4,
+                product_mix_ratio: 1.0,
+                setup_time_ratio: 0.0,
+                release_spread: 0.0,
+                due_date_slack: 0.0,
+                unavailability_ratio: 0.1,
             },
         }
     }
@@ -72,6 +135,9 @@ impl std::fmt::Display for Scenario {
             Scenario::JOB_SHOP => write!(f, "job_shop"),
             Scenario::FJSP_MEDIUM => write!(f, "fjsp_medium"),
             Scenario::FJSP_HIGH => write!(f, "fjsp_high"),
+            Scenario::FJSP_SETUP => write!(f, "fjsp_setup"),
+            Scenario::FJSP_TARDINESS => write!(f, "fjsp_tardiness"),
+            Scenario::FJSP_BREAKDOWNS => write!(f, "fjsp_breakdowns"),
         }
     }
 }
@@ -86,6 +152,9 @@ impl std::str::FromStr for Scenario {
             "job_shop" => Ok(Scenario::JOB_SHOP),
             "fjsp_medium" => Ok(Scenario::FJSP_MEDIUM),
             "fjsp_high" => Ok(Scenario::FJSP_HIGH),
+            "fjsp_setup" => Ok(Scenario::FJSP_SETUP),
+            "fjsp_tardiness" => Ok(Scenario::FJSP_TARDINESS),
+            "fjsp_breakdowns" => Ok(Scenario::FJSP_BREAKDOWNS),
             _ => Err(anyhow::anyhow!("Invalid scenario type: {}", s)),
         }
     }
@@ -121,6 +190,20 @@ pub struct Challenge {
     pub jobs_per_product: Vec<usize>,
     // each product has a sequence of operations, and each operation has a map of eligible machines to processing times
     pub product_processing_times: Vec<Vec<HashMap<usize, u32>>>,
+    // setup time on any machine when switching from one product to another. Empty if the scenario has no setups
+    #[serde(default)]
+    pub product_setup_times: Vec<Vec<u32>>,
+    // earliest start of each job. Empty if all jobs are released at 0
+    #[serde(default)]
+    pub job_release_times: Vec<u32>,
+    // due date and tardiness weight of each job. Empty if the objective is makespan
+    #[serde(default)]
+    pub job_due_dates: Vec<u32>,
+    #[serde(default)]
+    pub job_weights: Vec<u32>,
+    // sorted, non-overlapping [start, end) windows in which each machine cannot process. Empty if machines are always available
+    #[serde(default)]
+    pub machine_unavailability: Vec<Vec<(u32, u32)>>,
 }
 
 impl Challenge {
@@ -131,6 +214,10 @@ impl Challenge {
             reentrance_level,
             flow_structure,
             product_mix_ratio,
+            setup_time_ratio,
+            release_spread,
+            due_date_slack,
+            unavailability_ratio,
         } = track.s.clone().into();
         let n_jobs = 50;
         let n_machines = n_jobs / 2 + 5;
@@ -275,6 +362,94 @@ impl Challenge {
             })
             .collect::<Vec<_>>();
 
+        // side constraints are generated after the base instance so that scenarios without
+        // them are unchanged
+        let job_products = jobs_per_product
+            .iter()
+            .enumerate()
+            .flat_map(|(product, &num_jobs)| std::iter::repeat_n(product, num_jobs))
+            .collect::<Vec<usize>>();
+        let job_work = job_products
+            .iter()
+            .map(|&p| {
+                product_processing_times[p]
+                    .iter()
+                    .map(|op| op.values().sum::<u32>() as f32 / op.len() as f32)
+                    .sum::<f32>()
+            })
+            .collect::<Vec<f32>>();
+        let horizon = job_work.iter().sum::<f32>() / n_machines as f32;
+        let mean_proc_time = (base_proc_time_min + base_proc_time_max) as f32 / 2.0;
+
+        let product_setup_times = if setup_time_ratio > 0.0 {
+            let max_setup_time = (2.0 * setup_time_ratio * mean_proc_time) as u32;
+            (0..n_products)
+                .map(|p| {
+                    (0..n_products)
+                        .map(|q| {
+                            if p == q {
+                                0
+                            } else {
+                                rng.gen_range(0..=max_setup_time)
+                            }
+                        })
+                        .collect()
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let job_release_times = if release_spread > 0.0 {
+            let max_release_time = (release_spread * horizon) as u32;
+            (0..n_jobs)
+                .map(|_| rng.gen_range(0..=max_release_time))
+                .collect::<Vec<u32>>()
+        } else {
+            Vec::new()
+        };
+        let (job_due_dates, job_weights) = if due_date_slack > 0.0 {
+            (0..n_jobs)
+                .map(|j| {
+                    let release_time = job_release_times.get(j).copied().unwrap_or(0);
+                    let allowance = job_work[j] * (1.0 + due_date_slack * rng.r#gen::<f32>());
+                    (release_time + allowance as u32, rng.gen_range(1..=4))
+                })
+                .unzip()
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        let machine_unavailability = if unavailability_ratio > 0.0 {
+            (0..n_machines)
+                .map(|_| {
+                    let num_windows = rng.gen_range(1..=3);
+                    let window_len = 1.max((unavailability_ratio * horizon) as u32 / num_windows);
+                    let mut windows = (0..num_windows)
+                        .map(|_| {
+                            let start = rng.gen_range(0..horizon as u32);
+                            (start, start + window_len)
+                        })
+                        .collect::<Vec<(u32, u32)>>();
+                    windows.sort_unstable();
+                    // merge overlapping windows
+                    windows
+                        .into_iter()
+                        .fold(Vec::new(), |mut acc, (start, end)| {
+                            match acc.last_mut() {
+                                Some((_, prev_end)) if start <= *prev_end => {
+                                    *prev_end = (*prev_end).max(end)
+                                }
+                                _ => acc.push((start, end)),
+                            }
+                            acc
+                        })
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
         Ok(Challenge {
             seed: seed.clone(),
             num_jobs: n_jobs,
@@ -282,10 +457,87 @@ impl Challenge {
             num_operations: n_op_types,
             jobs_per_product,
             product_processing_times,
+            product_setup_times,
+            job_release_times,
+            job_due_dates,
+            job_weights,
+            machine_unavailability,
         })
     }
 
+    pub fn has_setup_times(&self) -> bool {
+        !self.product_setup_times.is_empty()
+    }
+
+    pub fn has_due_dates(&self) -> bool {
+        !self.job_due_dates.is_empty()
+    }
+
+    /// Whether the instance has any constraint beyond job precedence and machine capacity
+    pub fn has_side_constraints(&self) -> bool {
+        self.has_setup_times()
+            || !self.job_release_times.is_empty()
+            || !self.machine_unavailability.is_empty()
+    }
+
+    pub fn release_time(&self, job: usize) -> u32 {
+        self.job_release_times.get(job).copied().unwrap_or(0)
+    }
+
+    /// Setup time on a machine when an operation of `next_product` follows one of `prev_product`
+    pub fn setup_time(&self, prev_product: usize, next_product: usize) -> u32 {
+        match self.product_setup_times.get(prev_product) {
+            Some(row) => row[next_product],
+            None => 0,
+        }
+    }
+
+    /// Earliest start at or after `time` such that an operation of `proc_time` on `machine`
+    /// does not overlap an unavailability window
+    pub fn earliest_available_start(&self, machine: usize, time: u32, proc_time: u32) -> u32 {
+        let mut start = time;
+        if let Some(windows) = self.machine_unavailability.get(machine) {
+            for &(window_start, window_end) in windows {
+                if start.saturating_add(proc_time) <= window_start {
+                    break;
+                }
+                start = start.max(window_end);
+            }
+        }
+        start
+    }
+
     pub fn evaluate_makespan(&self, solution: &Solution) -> Result<u32> {
+        let completion_times = self.evaluate_completion_times(solution)?;
+        Ok(completion_times.into_iter().max().unwrap_or(0))
+    }
+
+    pub fn evaluate_weighted_tardiness(&self, solution: &Solution) -> Result<u32> {
+        if !self.has_due_dates() {
+            return Err(anyhow!("Challenge has no due dates"));
+        }
+        let completion_times = self.evaluate_completion_times(solution)?;
+        let weighted_tardiness = completion_times
+            .iter()
+            .zip(self.job_due_dates.iter().zip(self.job_weights.iter()))
+            .map(|(&completion_time, (&due_date, &weight))| {
+                completion_time.saturating_sub(due_date) as u64 * weight as u64
+            })
+            .sum::<u64>();
+        Ok(weighted_tardiness.min(u32::MAX as u64) as u32)
+    }
+
+    /// Total weighted tardiness if the challenge has due dates, otherwise makespan. Lower is better
+    pub fn evaluate_objective(&self, solution: &Solution) -> Result<u32> {
+        if self.has_due_dates() {
+            self.evaluate_weighted_tardiness(solution)
+        } else {
+            self.evaluate_makespan(solution)
+        }
+    }
+
+    // validates the solution and returns the completion time of each job
+    fn evaluate_completion_times(&self, solution: &Solution) -> Result<Vec<u32>> {
         if solution.job_schedule.len() != self.num_jobs {
             return Err(anyhow!(
                 "Expecting solution to have {} jobs. Got {}",
@@ -294,8 +546,9 @@ impl Challenge {
             ));
         }
         let mut job = 0;
-        let mut machine_usage = HashMap::<usize, Vec<(u32, u32)>>::new();
-        let mut makespan = 0u32;
+        // (start, finish, product) of each operation on each machine
+        let mut machine_usage = HashMap::<usize, Vec<(u32, u32, usize)>>::new();
+        let mut completion_times = Vec::with_capacity(self.num_jobs);
         for (product, num_jobs) in self.jobs_per_product.iter().enumerate() {
             for _ in 0..*num_jobs {
                 let schedule = &solution.job_schedule[job];
@@ -309,7 +562,7 @@ impl Challenge {
                         schedule.len(),
                     ));
                 }
-                let mut min_start_time = 0;
+                let mut min_start_time = self.release_time(job);
                 for (op_idx, &(machine, start_time)) in schedule.iter().enumerate() {
                     let eligible_machines = &processing_times[op_idx];
                     if !eligible_machines.contains_key(&machine) {
@@ -317,38 +570,55 @@ impl Challenge {
                     }
                     if start_time < min_start_time {
                         return Err(anyhow!(
-                            "Job {} schedule contains operation starting before previous is complete",
+                            "Job {} schedule contains operation starting before previous is complete or job is released",
+                            job,
+                        ));
+                    }
+                    let proc_time = eligible_machines[&machine];
+                    if self.earliest_available_start(machine, start_time, proc_time) != start_time {
+                        return Err(anyhow!(
+                            "Job {} schedule contains operation overlapping unavailability of machine {}",
                             job,
+                            machine,
                         ));
                     }
-                    let finish_time = start_time + eligible_machines[&machine];
-                    machine_usage
-                        .entry(machine)
-                        .or_default()
-                        .push((start_time, finish_time));
+                    let finish_time = start_time + proc_time;
+                    machine_usage.entry(machine).or_default().push((
+                        start_time,
+                        finish_time,
+                        product,
+                    ));
                     min_start_time = finish_time;
                 }
                 // min_start_time is the finish time of the job
-                if min_start_time > makespan {
-                    makespan = min_start_time;
-                }
+                completion_times.push(min_start_time);
                 job += 1;
             }
         }
 
         for (machine, usage) in machine_usage.iter_mut() {
-            usage.sort_by_key(|&(start, _)| start);
+            usage.sort_by_key(|&(start, _, _)| start);
             for i in 1..usage.len() {
-                if usage[i].0 < usage[i - 1].1 {
+                let (_, prev_finish, prev_product) = usage[i - 1];
+                let (start, _, product) = usage[i];
+                if start < prev_finish {
                     return Err(anyhow!(
                         "Machine {} is scheduled with overlapping jobs",
                         machine,
                     ));
                 }
+                if start < prev_finish.saturating_add(self.setup_time(prev_product, product)) {
+                    return Err(anyhow!(
+                        "Machine {} is scheduled without setup time between products {} and {}",
+                        machine,
+                        prev_product,
+                        product,
+                    ));
+                }
             }
         }
 
-        Ok(makespan)
+        Ok(completion_times)
     }
 
     conditional_pub!(
@@ -377,19 +647,34 @@ impl Challenge {
 
     conditional_pub!(
         fn evaluate_solution(&self, solution: &Solution) -> Result<i32> {
-            let makespan = self.evaluate_makespan(solution)?;
+            let objective = self.evaluate_objective(solution)?;
             let greedy_solution = self.compute_greedy_baseline()?;
-            let greedy_makespan = self.evaluate_makespan(&greedy_solution)?;
-            if makespan > greedy_makespan {
+            let greedy_objective = self.evaluate_objective(&greedy_solution)?;
+            if objective > greedy_objective {
+                let (name, baseline_name) = if self.has_due_dates() {
+                    ("Weighted tardiness", "weighted tardiness")
+                } else {
+                    ("Makespan", "makespan")
+                };
                 return Err(anyhow!(
-                    "Makespan {} must be better than greedy baseline makespan {}",
-                    makespan,
-                    greedy_makespan
+                    "{} {} must be better than greedy baseline {} {}",
+                    name,
+                    objective,
+                    baseline_name,
+                    greedy_objective
                 ));
             }
             let sota_solution = self.compute_sota_baseline()?;
-            let sota_makespan = self.evaluate_makespan(&sota_solution)?;
-            let quality = (sota_makespan as f64 - makespan as f64) / sota_makespan as f64;
+            let sota_objective = self.evaluate_objective(&sota_solution)?;
+            let quality = if sota_objective > 0 {
+                (sota_objective as f64 - objective as f64) / sota_objective as f64
+            } else if objective > 0 {
+                // sota has no weighted tardiness, so the gap is relative to the greedy baseline,
+                // which is positive as it is no better than the solution
+                -(objective as f64) / greedy_objective as f64
+            } else {
+                0.0
+            };
             let quality = quality.clamp(-10.0, 10.0) * QUALITY_PRECISION as f64;
             let quality = quality.round() as i32;
             Ok(quality)
@@ -0,0 +1,347 @@
+use super::{Challenge, Solution};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// An operation placed on a machine timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledOp {
+    pub job: usize,
+    pub op_idx: usize,
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Incrementally builds a `Solution` for a job scheduling challenge.
+///
+/// Jobs are expanded from `jobs_per_product` in the same order `Challenge::evaluate_makespan`
+/// expects them. Operations of a job must be scheduled in sequence, but jobs can be
+/// interleaved in any order. Each machine keeps a timeline of operations sorted by start time,
+/// so an operation can either be appended after the last operation on a machine, or inserted
+/// into the earliest idle gap that fits it (left-shift).
+///
+/// Release times, sequence-dependent setup times and machine unavailability windows are respected
+/// by every scheduling method, so a complete builder always yields a valid `Solution`.
+#[derive(Debug, Clone)]
+pub struct ScheduleBuilder<'a> {
+    challenge: &'a Challenge,
+    job_products: Vec<usize>,
+    job_schedule: Vec<Vec<(usize, u32)>>,
+    job_ready_time: Vec<u32>,
+    machine_timelines: Vec<Vec<ScheduledOp>>,
+    remaining_ops: usize,
+    makespan: u32,
+    weighted_tardiness: u64,
+}
+
+impl<'a> ScheduleBuilder<'a> {
+    pub fn new(challenge: &'a Challenge) -> Self {
+        let job_products = challenge
+            .jobs_per_product
+            .iter()
+            .enumerate()
+            .flat_map(|(product, &num_jobs)| std::iter::repeat_n(product, num_jobs))
+            .collect::<Vec<usize>>();
+        let remaining_ops = job_products
+            .iter()
+            .map(|&p| challenge.product_processing_times[p].len())
+            .sum();
+        Self {
+            challenge,
+            job_schedule: job_products
+                .iter()
+                .map(|&p| Vec::with_capacity(challenge.product_processing_times[p].len()))
+                .collect(),
+            job_ready_time: (0..job_products.len())
+                .map(|job| challenge.release_time(job))
+                .collect(),
+            machine_timelines: vec![Vec::new(); challenge.num_machines],
+            job_products,
+            remaining_ops,
+            makespan: 0,
+            weighted_tardiness: 0,
+        }
+    }
+
+    /// Rebuilds `solution` by re-inserting its operations in order of start time into the
+    /// earliest gap that fits. Without setup times, the result is never worse than `solution`
+    pub fn left_shift(challenge: &'a Challenge, solution: &Solution) -> Result<Self> {
+        challenge.evaluate_makespan(solution)?;
+        let mut ops = solution
+            .job_schedule
+            .iter()
+            .enumerate()
+            .flat_map(|(job, schedule)| {
+                schedule
+                    .iter()
+                    .enumerate()
+                    .map(move |(op_idx, &(machine, start))| (start, job, op_idx, machine))
+            })
+            .collect::<Vec<_>>();
+        ops.sort_unstable();
+        let mut builder = Self::new(challenge);
+        for (_, job, _, machine) in ops {
+            builder.insert(job, machine)?;
+        }
+        Ok(builder)
+    }
+
+    pub fn num_jobs(&self) -> usize {
+        self.job_products.len()
+    }
+
+    pub fn job_products(&self) -> &[usize] {
+        &self.job_products
+    }
+
+    pub fn job_product(&self, job: usize) -> usize {
+        self.job_products[job]
+    }
+
+    pub fn num_ops(&self, job: usize) -> usize {
+        self.challenge.product_processing_times[self.job_products[job]].len()
+    }
+
+    /// Eligible machines and their processing times for operation `op_idx` of `job`
+    pub fn processing_times(&self, job: usize, op_idx: usize) -> &'a HashMap<usize, u32> {
+        &self.challenge.product_processing_times[self.job_products[job]][op_idx]
+    }
+
+    /// Index of the next unscheduled operation of `job`, if any
+    pub fn next_op(&self, job: usize) -> Option<usize> {
+        let op_idx = self.job_schedule[job].len();
+        (op_idx < self.num_ops(job)).then_some(op_idx)
+    }
+
+    /// Time at which the last scheduled operation of `job` completes, or its release time if none
+    pub fn job_ready_time(&self, job: usize) -> u32 {
+        self.job_ready_time[job]
+    }
+
+    /// Time at which the last operation on `machine` completes
+    pub fn machine_available_time(&self, machine: usize) -> u32 {
+        self.machine_timelines[machine]
+            .last()
+            .map_or(0, |op| op.end)
+    }
+
+    pub fn machine_timeline(&self, machine: usize) -> &[ScheduledOp] {
+        &self.machine_timelines[machine]
+    }
+
+    pub fn job_schedule(&self) -> &[Vec<(usize, u32)>] {
+        &self.job_schedule
+    }
+
+    pub fn remaining_ops(&self) -> usize {
+        self.remaining_ops
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.remaining_ops == 0
+    }
+
+    pub fn makespan(&self) -> u32 {
+        self.makespan
+    }
+
+    /// Total weighted tardiness of the completed jobs
+    pub fn weighted_tardiness(&self) -> u32 {
+        self.weighted_tardiness.min(u32::MAX as u64) as u32
+    }
+
+    /// Value of `Challenge::evaluate_objective` for the scheduled operations
+    pub fn objective(&self) -> u32 {
+        if self.challenge.has_due_dates() {
+            self.weighted_tardiness()
+        } else {
+            self.makespan
+        }
+    }
+
+    /// Earliest start of the next operation of `job` on `machine` if appended after the last
+    /// operation on the machine
+    pub fn append_start(&self, job: usize, machine: usize) -> Result<u32> {
+        let proc_time = self.processing_time(job, machine)?;
+        let timeline = &self.machine_timelines[machine];
+        let ready = self.job_ready_time[job].max(self.ready_after(job, timeline.last()));
+        Ok(self
+            .challenge
+            .earliest_available_start(machine, ready, proc_time))
+    }
+
+    /// Earliest start of the next operation of `job` on `machine`, filling idle gaps
+    pub fn insert_start(&self, job: usize, machine: usize) -> Result<u32> {
+        let proc_time = self.processing_time(job, machine)?;
+        Ok(self.find_gap(job, machine, proc_time).1)
+    }
+
+    /// Eligible machine on which the next operation of `job` finishes earliest when filling
+    /// idle gaps. Returns `(machine, start)`, ties broken by lowest machine index
+    pub fn best_machine(&self, job: usize) -> Result<(usize, u32)> {
+        let op_idx = self.check_next_op(job)?;
+        let mut machines = self
+            .processing_times(job, op_idx)
+            .iter()
+            .collect::<Vec<_>>();
+        machines.sort_unstable();
+        machines
+            .into_iter()
+            .map(|(&machine, &proc_time)| {
+                let start = self.find_gap(job, machine, proc_time).1;
+                (start + proc_time, machine, start)
+            })
+            .min()
+            .map(|(_, machine, start)| (machine, start))
+            .ok_or_else(|| anyhow!("Job {} operation {} has no eligible machine", job, op_idx))
+    }
+
+    /// Schedules the next operation of `job` on `machine` after the last operation on the
+    /// machine. Returns the start time
+    pub fn append(&mut self, job: usize, machine: usize) -> Result<u32> {
+        let start = self.append_start(job, machine)?;
+        let pos = self.machine_timelines[machine].len();
+        self.place(job, machine, start, pos);
+        Ok(start)
+    }
+
+    /// Schedules the next operation of `job` on `machine` in the earliest idle gap that fits
+    /// it. Returns the start time
+    pub fn insert(&mut self, job: usize, machine: usize) -> Result<u32> {
+        let proc_time = self.processing_time(job, machine)?;
+        let (pos, start) = self.find_gap(job, machine, proc_time);
+        self.place(job, machine, start, pos);
+        Ok(start)
+    }
+
+    /// Schedules the next operation of `job` on `machine` at exactly `start`
+    pub fn insert_at(&mut self, job: usize, machine: usize, start: u32) -> Result<()> {
+        let proc_time = self.processing_time(job, machine)?;
+        if start < self.job_ready_time[job] {
+            return Err(anyhow!(
+                "Job {} operation cannot start at {} before previous completes at {}",
+                job,
+                start,
+                self.job_ready_time[job]
+            ));
+        }
+        let timeline = &self.machine_timelines[machine];
+        let pos = timeline.partition_point(|op| op.start < start);
+        let overlaps_prev = start < self.ready_after(job, pos.checked_sub(1).map(|p| &timeline[p]));
+        let overlaps_next = timeline
+            .get(pos)
+            .is_some_and(|next| start + proc_time > self.latest_end_before(job, next));
+        let overlaps_unavailable = self
+            .challenge
+            .earliest_available_start(machine, start, proc_time)
+            != start;
+        if overlaps_prev || overlaps_next || overlaps_unavailable {
+            return Err(anyhow!(
+                "Machine {} is busy during [{}, {})",
+                machine,
+                start,
+                start + proc_time
+            ));
+        }
+        self.place(job, machine, start, pos);
+        Ok(())
+    }
+
+    pub fn to_solution(&self) -> Result<Solution> {
+        if !self.is_complete() {
+            return Err(anyhow!(
+                "Schedule is incomplete. {} operations remain",
+                self.remaining_ops
+            ));
+        }
+        Ok(Solution {
+            job_schedule: self.job_schedule.clone(),
+        })
+    }
+
+    fn check_next_op(&self, job: usize) -> Result<usize> {
+        if job >= self.job_products.len() {
+            return Err(anyhow!("Job {} is out of bounds", job));
+        }
+        self.next_op(job)
+            .ok_or_else(|| anyhow!("Job {} has no remaining operations", job))
+    }
+
+    fn processing_time(&self, job: usize, machine: usize) -> Result<u32> {
+        let op_idx = self.check_next_op(job)?;
+        self.processing_times(job, op_idx)
+            .get(&machine)
+            .copied()
+            .ok_or_else(|| {
+                anyhow!(
+                    "Machine {} is not eligible for job {} operation {}",
+                    machine,
+                    job,
+                    op_idx
+                )
+            })
+    }
+
+    // returns (timeline position, start time) of the earliest gap fitting the operation
+    fn find_gap(&self, job: usize, machine: usize, proc_time: u32) -> (usize, u32) {
+        let ready = self.job_ready_time[job];
+        let timeline = &self.machine_timelines[machine];
+        let mut prev = None;
+        for (pos, op) in timeline.iter().enumerate() {
+            let start = self.challenge.earliest_available_start(
+                machine,
+                ready.max(self.ready_after(job, prev)),
+                proc_time,
+            );
+            if start + proc_time <= self.latest_end_before(job, op) {
+                return (pos, start);
+            }
+            prev = Some(op);
+        }
+        let start = self.challenge.earliest_available_start(
+            machine,
+            ready.max(self.ready_after(job, prev)),
+            proc_time,
+        );
+        (timeline.len(), start)
+    }
+
+    // earliest time an operation of `job` can start after `prev` on the same machine
+    fn ready_after(&self, job: usize, prev: Option<&ScheduledOp>) -> u32 {
+        prev.map_or(0, |prev| {
+            prev.end
+                + self
+                    .challenge
+                    .setup_time(self.job_products[prev.job], self.job_products[job])
+        })
+    }
+
+    // latest time an operation of `job` can end before `next` on the same machine
+    fn latest_end_before(&self, job: usize, next: &ScheduledOp) -> u32 {
+        next.start.saturating_sub(
+            self.challenge
+                .setup_time(self.job_products[job], self.job_products[next.job]),
+        )
+    }
+
+    fn place(&mut self, job: usize, machine: usize, start: u32, pos: usize) {
+        let op_idx = self.job_schedule[job].len();
+        let end = start + self.processing_times(job, op_idx)[&machine];
+        self.machine_timelines[machine].insert(
+            pos,
+            ScheduledOp {
+                job,
+                op_idx,
+                start,
+                end,
+            },
+        );
+        self.job_schedule[job].push((machine, start));
+        self.job_ready_time[job] = end;
+        self.remaining_ops -= 1;
+        self.makespan = self.makespan.max(end);
+        if self.challenge.has_due_dates() && self.next_op(job).is_none() {
+            let tardiness = end.saturating_sub(self.challenge.job_due_dates[job]);
+            self.weighted_tardiness += tardiness as u64 * self.challenge.job_weights[job] as u64;
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use crate::QUALITY_PRECISION;
 mod baselines;
+mod state;
 use anyhow::{anyhow, Result};
 use rand::{rngs::SmallRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
+pub use state::KnapsackState;
 use std::{cell::RefCell, collections::HashSet, f64::consts::PI};
 
 /// Generate a sample from lognormal distribution using Box-Muller transform
@@ -0,0 +1,228 @@
+use super::{Challenge, Solution};
+use anyhow::{anyhow, Result};
+
+/// Incrementally maintained knapsack selection.
+///
+/// Tracks the selected set, its total weight and total value, together with the
+/// contribution of every item w.r.t. the current selection:
+///
+/// `contribution[i] = values[i] + sum_{j in selected, j != i} interaction_values[i][j]`
+///
+/// so that the value delta of adding, removing or swapping items is O(1) to query and
+/// O(n) to apply, instead of the O(k^2) of `Challenge::evaluate_total_value`.
+///
+/// The capacity constraint is not enforced by `add`/`remove`/`swap`, allowing local
+/// searches to pass through infeasible states. Use `is_feasible` before saving.
+#[derive(Debug, Clone)]
+pub struct KnapsackState<'a> {
+    challenge: &'a Challenge,
+    selected: Vec<usize>,
+    position: Vec<Option<usize>>,
+    contributions: Vec<i32>,
+    total_weight: u32,
+    total_value: i32,
+}
+
+impl<'a> KnapsackState<'a> {
+    pub fn new(challenge: &'a Challenge) -> Self {
+        Self {
+            challenge,
+            selected: Vec::new(),
+            position: vec![None; challenge.num_items],
+            contributions: challenge.values.iter().map(|&v| v as i32).collect(),
+            total_weight: 0,
+            total_value: 0,
+        }
+    }
+
+    pub fn from_solution(challenge: &'a Challenge, solution: &Solution) -> Result<Self> {
+        let mut state = Self::new(challenge);
+        for &item in &solution.items {
+            state.add(item)?;
+        }
+        Ok(state)
+    }
+
+    pub fn to_solution(&self) -> Solution {
+        Solution {
+            items: self.selected.clone(),
+        }
+    }
+
+    pub fn selected(&self) -> &[usize] {
+        &self.selected
+    }
+
+    pub fn is_selected(&self, item: usize) -> bool {
+        self.position[item].is_some()
+    }
+
+    pub fn contribution(&self, item: usize) -> i32 {
+        self.contributions[item]
+    }
+
+    pub fn contributions(&self) -> &[i32] {
+        &self.contributions
+    }
+
+    pub fn total_weight(&self) -> u32 {
+        self.total_weight
+    }
+
+    /// Unclamped total value. `Challenge::evaluate_total_value` clamps negative totals to 0
+    pub fn total_value(&self) -> i32 {
+        self.total_value
+    }
+
+    pub fn remaining_capacity(&self) -> i64 {
+        self.challenge.max_weight as i64 - self.total_weight as i64
+    }
+
+    pub fn is_feasible(&self) -> bool {
+        self.total_weight <= self.challenge.max_weight
+    }
+
+    /// Change in total value if `item` (unselected) was added
+    pub fn add_delta(&self, item: usize) -> i32 {
+        self.contributions[item]
+    }
+
+    /// Change in total value if `item` (selected) was removed
+    pub fn remove_delta(&self, item: usize) -> i32 {
+        -(self.contributions[item] - self.challenge.interaction_values[item][item])
+    }
+
+    /// Change in total value if `remove_item` (selected) was swapped for `add_item` (unselected)
+    pub fn swap_delta(&self, remove_item: usize, add_item: usize) -> i32 {
+        self.add_delta(add_item) + self.remove_delta(remove_item)
+            - self.challenge.interaction_values[add_item][remove_item]
+    }
+
+    /// Change in total weight if `remove_item` was swapped for `add_item`
+    pub fn swap_weight_delta(&self, remove_item: usize, add_item: usize) -> i64 {
+        self.challenge.weights[add_item] as i64 - self.challenge.weights[remove_item] as i64
+    }
+
+    pub fn add(&mut self, item: usize) -> Result<()> {
+        self.check_item(item)?;
+        if self.is_selected(item) {
+            return Err(anyhow!("Item ({}) is already selected", item));
+        }
+        self.total_value += self.add_delta(item);
+        self.total_weight += self.challenge.weights[item];
+        self.position[item] = Some(self.selected.len());
+        self.selected.push(item);
+        for (x, c) in self.contributions.iter_mut().enumerate() {
+            *c += self.challenge.interaction_values[x][item];
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, item: usize) -> Result<()> {
+        self.check_item(item)?;
+        let pos = match self.position[item] {
+            Some(pos) => pos,
+            None => return Err(anyhow!("Item ({}) is not selected", item)),
+        };
+        self.total_value += self.remove_delta(item);
+        self.total_weight -= self.challenge.weights[item];
+        self.selected.swap_remove(pos);
+        if let Some(&moved) = self.selected.get(pos) {
+            self.position[moved] = Some(pos);
+        }
+        self.position[item] = None;
+        for (x, c) in self.contributions.iter_mut().enumerate() {
+            *c -= self.challenge.interaction_values[x][item];
+        }
+        Ok(())
+    }
+
+    /// Replaces `remove_item` with `add_item` in a single pass over the contributions
+    pub fn swap(&mut self, remove_item: usize, add_item: usize) -> Result<()> {
+        self.check_item(remove_item)?;
+        self.check_item(add_item)?;
+        let pos = match self.position[remove_item] {
+            Some(pos) => pos,
+            None => return Err(anyhow!("Item ({}) is not selected", remove_item)),
+        };
+        if self.is_selected(add_item) {
+            return Err(anyhow!("Item ({}) is already selected", add_item));
+        }
+        self.total_value += self.swap_delta(remove_item, add_item);
+        self.total_weight = self.total_weight - self.challenge.weights[remove_item]
+            + self.challenge.weights[add_item];
+        self.selected[pos] = add_item;
+        self.position[add_item] = Some(pos);
+        self.position[remove_item] = None;
+        for (x, c) in self.contributions.iter_mut().enumerate() {
+            let row = &self.challenge.interaction_values[x];
+            *c += row[add_item] - row[remove_item];
+        }
+        Ok(())
+    }
+
+    /// Recomputes weight, value and contributions from scratch and checks them against the
+    /// incremental state and `Challenge::evaluate_total_value`. O(n * k), intended for debugging
+    pub fn check_consistency(&self) -> Result<()> {
+        let total_weight: u32 = self
+            .selected
+            .iter()
+            .map(|&i| self.challenge.weights[i])
+            .sum();
+        if total_weight != self.total_weight {
+            return Err(anyhow!(
+                "Total weight mismatch. Tracked: {}, Actual: {}",
+                self.total_weight,
+                total_weight
+            ));
+        }
+        for x in 0..self.challenge.num_items {
+            let contribution = self.challenge.values[x] as i32
+                + self
+                    .selected
+                    .iter()
+                    .filter(|&&j| j != x)
+                    .map(|&j| self.challenge.interaction_values[x][j])
+                    .sum::<i32>()
+                + if self.is_selected(x) {
+                    self.challenge.interaction_values[x][x]
+                } else {
+                    0
+                };
+            if contribution != self.contributions[x] {
+                return Err(anyhow!(
+                    "Contribution mismatch for item ({}). Tracked: {}, Actual: {}",
+                    x,
+                    self.contributions[x],
+                    contribution
+                ));
+            }
+        }
+        for (pos, &item) in self.selected.iter().enumerate() {
+            if self.position[item] != Some(pos) {
+                return Err(anyhow!("Position mismatch for item ({})", item));
+            }
+        }
+        if self.position.iter().filter(|p| p.is_some()).count() != self.selected.len() {
+            return Err(anyhow!("Selected set and positions are out of sync"));
+        }
+        if self.is_feasible() {
+            let total_value = self.challenge.evaluate_total_value(&self.to_solution())?;
+            if total_value != self.total_value.max(0) as u32 {
+                return Err(anyhow!(
+                    "Total value mismatch. Tracked: {}, Actual: {}",
+                    self.total_value,
+                    total_value
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_item(&self, item: usize) -> Result<()> {
+        if item >= self.challenge.num_items {
+            return Err(anyhow!("Item ({}) is out of bounds", item));
+        }
+        Ok(())
+    }
+}
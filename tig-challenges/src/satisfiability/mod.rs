@@ -1,4 +1,5 @@
 use crate::QUALITY_PRECISION;
+mod state;
 use anyhow::{anyhow, Result};
 use ndarray::{Array2, Axis};
 use rand::{
@@ -7,6 +8,7 @@ use rand::{
     Rng, SeedableRng,
 };
 use serde::{Deserialize, Serialize};
+pub use state::SatState;
 
 impl_kv_string_serde! {
     Track {
@@ -0,0 +1,305 @@
+use super::{Challenge, Solution};
+use anyhow::{anyhow, Result};
+
+/// Incrementally maintained clause state for local search over a truth assignment.
+///
+/// Clauses are normalised on construction: duplicate literals are merged and tautological
+/// clauses (containing both `x` and `-x`) are dropped, as they can never be unsatisfied.
+/// Clause indices returned by this type refer to the normalised clauses (see `clause`).
+///
+/// Per variable, tracks:
+/// * make score: number of unsatisfied clauses that become satisfied if the variable is flipped
+/// * break score: number of satisfied clauses that become unsatisfied if the variable is flipped
+///
+/// Flipping a variable only touches the clauses the variable occurs in. The set of unsatisfied
+/// clauses supports O(1) insertion, removal and random access.
+#[derive(Debug, Clone)]
+pub struct SatState {
+    variables: Vec<bool>,
+    clauses: Vec<Vec<i32>>,
+    pos_occurrences: Vec<Vec<usize>>,
+    neg_occurrences: Vec<Vec<usize>>,
+    num_true_literals: Vec<u32>,
+    // sum of variable indexes of the true literals in each clause. When a clause has a
+    // single true literal, this is the index of its critical variable
+    true_var_sum: Vec<usize>,
+    make_scores: Vec<u32>,
+    break_scores: Vec<u32>,
+    unsat_clauses: Vec<usize>,
+    unsat_position: Vec<Option<usize>>,
+}
+
+impl SatState {
+    pub fn new(challenge: &Challenge, variables: Vec<bool>) -> Result<Self> {
+        if variables.len() != challenge.num_variables {
+            return Err(anyhow!(
+                "Invalid number of variables. Expected: {}, Actual: {}",
+                challenge.num_variables,
+                variables.len()
+            ));
+        }
+        let num_variables = challenge.num_variables;
+        let mut clauses = Vec::with_capacity(challenge.clauses.len());
+        for clause in &challenge.clauses {
+            let mut clause = clause.clone();
+            if let Some(&literal) = clause
+                .iter()
+                .find(|&&l| l == 0 || l.unsigned_abs() as usize > num_variables)
+            {
+                return Err(anyhow!("Literal ({}) is out of bounds", literal));
+            }
+            clause.sort_unstable();
+            clause.dedup();
+            if clause.iter().any(|&l| clause.contains(&-l)) {
+                continue;
+            }
+            clauses.push(clause);
+        }
+
+        let mut pos_occurrences = vec![Vec::new(); num_variables];
+        let mut neg_occurrences = vec![Vec::new(); num_variables];
+        for (c, clause) in clauses.iter().enumerate() {
+            for &literal in clause {
+                let var = literal.unsigned_abs() as usize - 1;
+                if literal > 0 {
+                    pos_occurrences[var].push(c);
+                } else {
+                    neg_occurrences[var].push(c);
+                }
+            }
+        }
+
+        let num_clauses = clauses.len();
+        let mut state = Self {
+            variables,
+            clauses,
+            pos_occurrences,
+            neg_occurrences,
+            num_true_literals: vec![0; num_clauses],
+            true_var_sum: vec![0; num_clauses],
+            make_scores: vec![0; num_variables],
+            break_scores: vec![0; num_variables],
+            unsat_clauses: Vec::new(),
+            unsat_position: vec![None; num_clauses],
+        };
+        for c in 0..num_clauses {
+            for &literal in &state.clauses[c] {
+                let var = literal.unsigned_abs() as usize - 1;
+                if state.is_literal_true(literal) {
+                    state.num_true_literals[c] += 1;
+                    state.true_var_sum[c] += var;
+                }
+            }
+            match state.num_true_literals[c] {
+                0 => {
+                    state.insert_unsat(c);
+                    for &literal in &state.clauses[c] {
+                        state.make_scores[literal.unsigned_abs() as usize - 1] += 1;
+                    }
+                }
+                1 => state.break_scores[state.true_var_sum[c]] += 1,
+                _ => {}
+            }
+        }
+        Ok(state)
+    }
+
+    pub fn from_solution(challenge: &Challenge, solution: &Solution) -> Result<Self> {
+        Self::new(challenge, solution.variables.clone())
+    }
+
+    pub fn to_solution(&self) -> Solution {
+        Solution {
+            variables: self.variables.clone(),
+        }
+    }
+
+    pub fn variables(&self) -> &[bool] {
+        &self.variables
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.variables.len()
+    }
+
+    pub fn clauses(&self) -> &[Vec<i32>] {
+        &self.clauses
+    }
+
+    pub fn clause(&self, clause_idx: usize) -> &[i32] {
+        &self.clauses[clause_idx]
+    }
+
+    /// Indexes of the clauses containing the positive literal of `var` (0-indexed)
+    pub fn pos_occurrences(&self, var: usize) -> &[usize] {
+        &self.pos_occurrences[var]
+    }
+
+    /// Indexes of the clauses containing the negative literal of `var` (0-indexed)
+    pub fn neg_occurrences(&self, var: usize) -> &[usize] {
+        &self.neg_occurrences[var]
+    }
+
+    pub fn num_true_literals(&self, clause_idx: usize) -> u32 {
+        self.num_true_literals[clause_idx]
+    }
+
+    /// The only variable satisfying the clause, if it is satisfied by exactly one literal
+    pub fn critical_var(&self, clause_idx: usize) -> Option<usize> {
+        match self.num_true_literals[clause_idx] {
+            1 => Some(self.true_var_sum[clause_idx]),
+            _ => None,
+        }
+    }
+
+    pub fn make_score(&self, var: usize) -> u32 {
+        self.make_scores[var]
+    }
+
+    pub fn break_score(&self, var: usize) -> u32 {
+        self.break_scores[var]
+    }
+
+    /// Change in number of satisfied clauses if `var` is flipped
+    pub fn score(&self, var: usize) -> i64 {
+        self.make_scores[var] as i64 - self.break_scores[var] as i64
+    }
+
+    pub fn unsat_clauses(&self) -> &[usize] {
+        &self.unsat_clauses
+    }
+
+    pub fn num_unsat(&self) -> usize {
+        self.unsat_clauses.len()
+    }
+
+    pub fn is_clause_sat(&self, clause_idx: usize) -> bool {
+        self.num_true_literals[clause_idx] > 0
+    }
+
+    pub fn is_satisfied(&self) -> bool {
+        self.unsat_clauses.is_empty()
+    }
+
+    pub fn is_literal_true(&self, literal: i32) -> bool {
+        let value = self.variables[literal.unsigned_abs() as usize - 1];
+        (literal > 0) == value
+    }
+
+    /// Flips `var` (0-indexed), updating clause counts, scores and the unsatisfied set
+    pub fn flip(&mut self, var: usize) {
+        self.variables[var] = !self.variables[var];
+        let (made_true, made_false) = if self.variables[var] {
+            (&self.pos_occurrences[var], &self.neg_occurrences[var])
+        } else {
+            (&self.neg_occurrences[var], &self.pos_occurrences[var])
+        };
+
+        for &c in made_true {
+            self.num_true_literals[c] += 1;
+            self.true_var_sum[c] += var;
+            match self.num_true_literals[c] {
+                1 => {
+                    let pos = self.unsat_position[c].take().unwrap();
+                    self.unsat_clauses.swap_remove(pos);
+                    if let Some(&moved) = self.unsat_clauses.get(pos) {
+                        self.unsat_position[moved] = Some(pos);
+                    }
+                    for &literal in &self.clauses[c] {
+                        self.make_scores[literal.unsigned_abs() as usize - 1] -= 1;
+                    }
+                    self.break_scores[var] += 1;
+                }
+                2 => {
+                    let critical = self.true_var_sum[c] - var;
+                    self.break_scores[critical] -= 1;
+                }
+                _ => {}
+            }
+        }
+
+        for &c in made_false {
+            self.num_true_literals[c] -= 1;
+            self.true_var_sum[c] -= var;
+            match self.num_true_literals[c] {
+                0 => {
+                    self.unsat_position[c] = Some(self.unsat_clauses.len());
+                    self.unsat_clauses.push(c);
+                    for &literal in &self.clauses[c] {
+                        self.make_scores[literal.unsigned_abs() as usize - 1] += 1;
+                    }
+                    self.break_scores[var] -= 1;
+                }
+                1 => {
+                    let critical = self.true_var_sum[c];
+                    self.break_scores[critical] += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Recomputes all counts, scores and the unsatisfied set from scratch and checks them
+    /// against the incremental state and `Challenge::evaluate_solution`. O(total literals),
+    /// intended for debugging
+    pub fn check_consistency(&self, challenge: &Challenge) -> Result<()> {
+        let fresh = Self::new(challenge, self.variables.clone())?;
+        if fresh.clauses != self.clauses {
+            return Err(anyhow!("Clauses do not match challenge"));
+        }
+        for c in 0..self.clauses.len() {
+            if fresh.num_true_literals[c] != self.num_true_literals[c] {
+                return Err(anyhow!(
+                    "True literal count mismatch for clause ({}). Tracked: {}, Actual: {}",
+                    c,
+                    self.num_true_literals[c],
+                    fresh.num_true_literals[c]
+                ));
+            }
+            if fresh.critical_var(c) != self.critical_var(c) {
+                return Err(anyhow!(
+                    "Critical variable mismatch for clause ({}). Tracked: {:?}, Actual: {:?}",
+                    c,
+                    self.critical_var(c),
+                    fresh.critical_var(c)
+                ));
+            }
+            if fresh.unsat_position[c].is_some() != self.unsat_position[c].is_some() {
+                return Err(anyhow!("Unsatisfied set mismatch for clause ({})", c));
+            }
+        }
+        for (pos, &c) in self.unsat_clauses.iter().enumerate() {
+            if self.unsat_position[c] != Some(pos) {
+                return Err(anyhow!("Unsatisfied position mismatch for clause ({})", c));
+            }
+        }
+        for var in 0..self.variables.len() {
+            if fresh.make_scores[var] != self.make_scores[var]
+                || fresh.break_scores[var] != self.break_scores[var]
+            {
+                return Err(anyhow!(
+                    "Score mismatch for variable ({}). Tracked: make={} break={}, Actual: make={} break={}",
+                    var,
+                    self.make_scores[var],
+                    self.break_scores[var],
+                    fresh.make_scores[var],
+                    fresh.break_scores[var]
+                ));
+            }
+        }
+        let satisfied = challenge.evaluate_solution(&self.to_solution())? > 0;
+        if satisfied != self.is_satisfied() {
+            return Err(anyhow!(
+                "Satisfied mismatch. Tracked: {}, Actual: {}",
+                self.is_satisfied(),
+                satisfied
+            ));
+        }
+        Ok(())
+    }
+
+    fn insert_unsat(&mut self, clause_idx: usize) {
+        self.unsat_position[clause_idx] = Some(self.unsat_clauses.len());
+        self.unsat_clauses.push(clause_idx);
+    }
+}
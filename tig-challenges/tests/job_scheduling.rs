@@ -0,0 +1,241 @@
+use tig_challenges::job_scheduling::{Challenge, Scenario, ScheduleBuilder, Track};
+
+fn generate_challenge(s: Scenario) -> Challenge {
+    Challenge::generate_instance(&[5u8; 32], &Track { n: 50, s }).unwrap()
+}
+
+#[test]
+fn test_schedule_builder_expands_jobs() {
+    let challenge = generate_challenge(Scenario::FJSP_MEDIUM);
+    let builder = ScheduleBuilder::new(&challenge);
+    assert_eq!(builder.num_jobs(), challenge.num_jobs);
+    let mut job = 0;
+    for (product, &num_jobs) in challenge.jobs_per_product.iter().enumerate() {
+        for _ in 0..num_jobs {
+            assert_eq!(builder.job_product(job), product);
+            assert_eq!(
+                builder.num_ops(job),
+                challenge.product_processing_times[product].len()
+            );
+            assert_eq!(builder.next_op(job), Some(0));
+            job += 1;
+        }
+    }
+    assert!(builder.to_solution().is_err());
+}
+
+#[test]
+fn test_schedule_builder_insert_and_append() {
+    for s in [
+        Scenario::FLOW_SHOP,
+        Scenario::HYBRID_FLOW_SHOP,
+        Scenario::JOB_SHOP,
+        Scenario::FJSP_MEDIUM,
+        Scenario::FJSP_HIGH,
+    ] {
+        let challenge = generate_challenge(s);
+        let mut inserted = ScheduleBuilder::new(&challenge);
+        let mut appended = ScheduleBuilder::new(&challenge);
+        while !inserted.is_complete() {
+            for job in 0..inserted.num_jobs() {
+                if inserted.next_op(job).is_none() {
+                    continue;
+                }
+                let (machine, start) = inserted.best_machine(job).unwrap();
+                assert_eq!(inserted.insert(job, machine).unwrap(), start);
+                appended.append(job, machine).unwrap();
+            }
+        }
+        assert!(appended.is_complete());
+
+        let solution = inserted.to_solution().unwrap();
+        assert_eq!(
+            challenge.evaluate_makespan(&solution).unwrap(),
+            inserted.makespan()
+        );
+        let solution = appended.to_solution().unwrap();
+        assert_eq!(
+            challenge.evaluate_makespan(&solution).unwrap(),
+            appended.makespan()
+        );
+        assert!(inserted.makespan() <= appended.makespan());
+
+        let shifted = ScheduleBuilder::left_shift(&challenge, &solution).unwrap();
+        let shifted_solution = shifted.to_solution().unwrap();
+        assert_eq!(
+            challenge.evaluate_makespan(&shifted_solution).unwrap(),
+            shifted.makespan()
+        );
+        assert!(shifted.makespan() <= appended.makespan());
+    }
+}
+
+#[test]
+fn test_schedule_builder_insert_at() {
+    let challenge = generate_challenge(Scenario::JOB_SHOP);
+    let mut builder = ScheduleBuilder::new(&challenge);
+    let machine = *builder.processing_times(0, 0).keys().next().unwrap();
+    let proc_time = builder.processing_times(0, 0)[&machine];
+    let other_job = (1..builder.num_jobs())
+        .find(|&j| builder.processing_times(j, 0).contains_key(&machine))
+        .unwrap();
+
+    builder.insert_at(0, machine, 10).unwrap();
+    assert_eq!(builder.job_ready_time(0), 10 + proc_time);
+    assert_eq!(builder.makespan(), 10 + proc_time);
+    assert!(builder.insert_at(other_job, machine, 10).is_err());
+    assert!(builder
+        .insert_at(other_job, machine, 9 + proc_time)
+        .is_err());
+
+    // fills the gap before job 0 only if it fits
+    let other_time = builder.processing_times(other_job, 0)[&machine];
+    let start = builder.insert(other_job, machine).unwrap();
+    if other_time <= 10 {
+        assert_eq!(start, 0);
+    } else {
+        assert_eq!(start, 10 + proc_time);
+    }
+    assert_eq!(builder.machine_timeline(machine).len(), 2);
+    assert!(
+        builder.machine_timeline(machine)[0].start < builder.machine_timeline(machine)[1].start
+    );
+}
+
+fn build_schedule(challenge: &Challenge) -> ScheduleBuilder<'_> {
+    let mut builder = ScheduleBuilder::new(challenge);
+    while !builder.is_complete() {
+        for job in 0..builder.num_jobs() {
+            if builder.next_op(job).is_some() {
+                let (machine, _) = builder.best_machine(job).unwrap();
+                builder.insert(job, machine).unwrap();
+            }
+        }
+    }
+    builder
+}
+
+#[test]
+fn test_side_constraint_scenarios() {
+    for s in [
+        Scenario::FLOW_SHOP,
+        Scenario::HYBRID_FLOW_SHOP,
+        Scenario::JOB_SHOP,
+        Scenario::FJSP_MEDIUM,
+        Scenario::FJSP_HIGH,
+    ] {
+        let challenge = generate_challenge(s);
+        assert!(!challenge.has_side_constraints());
+        assert!(!challenge.has_due_dates());
+    }
+
+    let challenge = generate_challenge(Scenario::FJSP_SETUP);
+    assert!(challenge.has_setup_times());
+    assert!(!challenge.has_due_dates());
+    let challenge = generate_challenge(Scenario::FJSP_TARDINESS);
+    assert!(challenge.has_due_dates());
+    assert_eq!(challenge.job_release_times.len(), challenge.num_jobs);
+    assert_eq!(challenge.job_weights.len(), challenge.num_jobs);
+    let challenge = generate_challenge(Scenario::FJSP_BREAKDOWNS);
+    assert_eq!(
+        challenge.machine_unavailability.len(),
+        challenge.num_machines
+    );
+
+    for s in [
+        Scenario::FJSP_SETUP,
+        Scenario::FJSP_TARDINESS,
+        Scenario::FJSP_BREAKDOWNS,
+    ] {
+        assert_eq!(s.to_string().parse::<Scenario>().unwrap(), s);
+        let challenge = generate_challenge(s);
+        let builder = build_schedule(&challenge);
+        let solution = builder.to_solution().unwrap();
+        assert_eq!(
+            challenge.evaluate_objective(&solution).unwrap(),
+            builder.objective()
+        );
+        assert_eq!(
+            challenge.evaluate_makespan(&solution).unwrap(),
+            builder.makespan()
+        );
+    }
+}
+
+#[cfg(not(feature = "hide_verification"))]
+#[test]
+fn test_side_constraint_baselines() {
+    for s in [
+        Scenario::FJSP_SETUP,
+        Scenario::FJSP_TARDINESS,
+        Scenario::FJSP_BREAKDOWNS,
+    ] {
+        let challenge = generate_challenge(s);
+        // greedy is valid and no better than sota
+        let greedy = challenge.compute_greedy_baseline().unwrap();
+        assert!(challenge.evaluate_solution(&greedy).unwrap() <= 0);
+    }
+}
+
+#[cfg(not(feature = "hide_verification"))]
+#[test]
+fn test_zero_sota_tardiness() {
+    let mut challenge = generate_challenge(Scenario::FJSP_TARDINESS);
+    // a common due date that sota meets for every job, but greedy does not
+    challenge.job_due_dates = vec![10_100; challenge.num_jobs];
+    let sota = challenge.compute_sota_baseline().unwrap();
+    assert_eq!(challenge.evaluate_objective(&sota).unwrap(), 0);
+
+    // scaled by the greedy baseline instead of hitting the floor
+    let greedy = challenge.compute_greedy_baseline().unwrap();
+    assert!(challenge.evaluate_objective(&greedy).unwrap() > 0);
+    assert_eq!(challenge.evaluate_solution(&greedy).unwrap(), -1_000_000);
+    assert_eq!(challenge.evaluate_solution(&sota).unwrap(), 0);
+}
+
+#[test]
+fn test_side_constraint_violations() {
+    let challenge = generate_challenge(Scenario::FJSP_TARDINESS);
+    let mut solution = build_schedule(&challenge).to_solution().unwrap();
+    let job = (0..challenge.num_jobs)
+        .find(|&j| challenge.release_time(j) > 0)
+        .unwrap();
+    assert!(solution.job_schedule[job][0].1 >= challenge.release_time(job));
+    solution.job_schedule[job][0].1 = challenge.release_time(job) - 1;
+    assert!(challenge.evaluate_objective(&solution).is_err());
+
+    let challenge = generate_challenge(Scenario::FJSP_BREAKDOWNS);
+    let machine = 0;
+    let (window_start, _) = challenge.machine_unavailability[machine][0];
+    let mut builder = ScheduleBuilder::new(&challenge);
+    let job = (0..builder.num_jobs())
+        .find(|&j| builder.processing_times(j, 0).contains_key(&machine))
+        .unwrap();
+    assert!(builder.insert_at(job, machine, window_start).is_err());
+    let start = builder.insert(job, machine).unwrap();
+    let end = start + builder.processing_times(job, 0)[&machine];
+    assert!(challenge.machine_unavailability[machine]
+        .iter()
+        .all(|&(s, e)| end <= s || start >= e));
+
+    let challenge = generate_challenge(Scenario::FJSP_SETUP);
+    let builder = build_schedule(&challenge);
+    let solution = builder.to_solution().unwrap();
+    // pull an operation back to the end of the previous one on its machine, dropping the setup
+    let (prev, next) = (0..challenge.num_machines)
+        .flat_map(|m| builder.machine_timeline(m).windows(2))
+        .map(|w| (w[0], w[1]))
+        .find(|(prev, next)| {
+            challenge.setup_time(builder.job_product(prev.job), builder.job_product(next.job)) > 0
+                && (next.op_idx == 0 || {
+                    let (machine, start) = solution.job_schedule[next.job][next.op_idx - 1];
+                    start + builder.processing_times(next.job, next.op_idx - 1)[&machine]
+                        <= prev.end
+                })
+        })
+        .unwrap();
+    let mut invalid = solution.clone();
+    invalid.job_schedule[next.job][next.op_idx].1 = prev.end;
+    let err = challenge.evaluate_objective(&invalid).unwrap_err();
+    assert!(err.to_string().contains("setup"));
+}
@@ -0,0 +1,76 @@
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use tig_challenges::knapsack::{Challenge, KnapsackState, Solution, Track};
+
+fn generate_challenge() -> Challenge {
+    let track = Track {
+        n_items: 200,
+        budget: 10,
+    };
+    Challenge::generate_instance(&[7u8; 32], &track).unwrap()
+}
+
+#[test]
+fn test_knapsack_state_add_remove_swap() {
+    let challenge = generate_challenge();
+    let mut state = KnapsackState::new(&challenge);
+    let mut rng = SmallRng::seed_from_u64(0);
+
+    for _ in 0..500 {
+        let item = rng.gen_range(0..challenge.num_items);
+        let before = state.total_value();
+        if state.is_selected(item) {
+            let delta = state.remove_delta(item);
+            state.remove(item).unwrap();
+            assert_eq!(state.total_value(), before + delta);
+        } else if let Some(&remove_item) = state
+            .selected()
+            .get(rng.gen_range(0..state.selected().len().max(1)))
+            .filter(|_| rng.gen_bool(0.5))
+        {
+            let delta = state.swap_delta(remove_item, item);
+            state.swap(remove_item, item).unwrap();
+            assert_eq!(state.total_value(), before + delta);
+        } else if state.remaining_capacity() >= challenge.weights[item] as i64 {
+            let delta = state.add_delta(item);
+            state.add(item).unwrap();
+            assert_eq!(state.total_value(), before + delta);
+        }
+        state.check_consistency().unwrap();
+    }
+}
+
+#[test]
+fn test_knapsack_state_from_solution() {
+    let challenge = generate_challenge();
+    let mut items = Vec::new();
+    let mut weight = 0;
+    for i in 0..challenge.num_items {
+        if weight + challenge.weights[i] <= challenge.max_weight {
+            weight += challenge.weights[i];
+            items.push(i);
+        }
+    }
+    let solution = Solution { items };
+    let state = KnapsackState::from_solution(&challenge, &solution).unwrap();
+    state.check_consistency().unwrap();
+    assert!(state.is_feasible());
+    assert_eq!(state.total_weight(), weight);
+    assert_eq!(
+        state.total_value().max(0) as u32,
+        challenge.evaluate_total_value(&solution).unwrap()
+    );
+    assert_eq!(state.to_solution().items, solution.items);
+}
+
+#[test]
+fn test_knapsack_state_errors() {
+    let challenge = generate_challenge();
+    let mut state = KnapsackState::new(&challenge);
+    assert!(state.add(challenge.num_items).is_err());
+    assert!(state.remove(0).is_err());
+    state.add(0).unwrap();
+    assert!(state.add(0).is_err());
+    assert!(state.swap(1, 2).is_err());
+    assert!(state.swap(0, 0).is_err());
+    assert!(KnapsackState::from_solution(&challenge, &Solution { items: vec![1, 1] }).is_err());
+}
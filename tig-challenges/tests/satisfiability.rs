@@ -0,0 +1,87 @@
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use tig_challenges::satisfiability::{Challenge, SatState, Solution, Track};
+
+fn generate_challenge() -> Challenge {
+    let track = Track {
+        n_vars: 200,
+        ratio: 4200,
+    };
+    Challenge::generate_instance(&[3u8; 32], &track).unwrap()
+}
+
+#[test]
+fn test_sat_state_random_flips() {
+    let challenge = generate_challenge();
+    let mut rng = SmallRng::seed_from_u64(0);
+    let variables = (0..challenge.num_variables).map(|_| rng.gen()).collect();
+    let mut state = SatState::new(&challenge, variables).unwrap();
+    state.check_consistency(&challenge).unwrap();
+
+    for _ in 0..1000 {
+        let var = rng.gen_range(0..challenge.num_variables);
+        let num_unsat = state.num_unsat() as i64;
+        let score = state.score(var);
+        state.flip(var);
+        assert_eq!(state.num_unsat() as i64, num_unsat - score);
+        state.check_consistency(&challenge).unwrap();
+    }
+}
+
+#[test]
+fn test_sat_state_walk_sat() {
+    let challenge = generate_challenge();
+    let mut rng = SmallRng::seed_from_u64(1);
+    let mut state = SatState::new(&challenge, vec![false; challenge.num_variables]).unwrap();
+
+    for _ in 0..10000 {
+        if state.is_satisfied() {
+            break;
+        }
+        let c = state.unsat_clauses()[rng.gen_range(0..state.num_unsat())];
+        let var = state
+            .clause(c)
+            .iter()
+            .map(|l| l.unsigned_abs() as usize - 1)
+            .min_by_key(|&v| state.break_score(v))
+            .unwrap();
+        state.flip(var);
+    }
+    state.check_consistency(&challenge).unwrap();
+    for &c in state.unsat_clauses() {
+        assert!(state.clause(c).iter().all(|&l| !state.is_literal_true(l)));
+    }
+}
+
+#[test]
+fn test_sat_state_normalises_clauses() {
+    let challenge = Challenge {
+        seed: [0u8; 32],
+        num_variables: 3,
+        clauses: vec![vec![1, 1, -2], vec![2, -2, 3], vec![-1, -3, -3]],
+    };
+    let mut state = SatState::new(&challenge, vec![false, true, false]).unwrap();
+    assert_eq!(state.clauses(), &[vec![-2, 1], vec![-3, -1]]);
+    assert_eq!(state.unsat_clauses(), &[0]);
+    assert_eq!(state.make_score(0), 1);
+    assert_eq!(state.break_score(0), 0);
+    assert_eq!(state.critical_var(1), None);
+
+    state.flip(0);
+    state.check_consistency(&challenge).unwrap();
+    assert!(state.is_satisfied());
+    assert_eq!(state.critical_var(0), Some(0));
+    assert_eq!(state.critical_var(1), Some(2));
+    assert_eq!(state.break_score(0), 1);
+
+    assert!(SatState::new(&challenge, vec![false; 2]).is_err());
+    assert!(SatState::from_solution(
+        &Challenge {
+            clauses: vec![vec![4]],
+            ..challenge
+        },
+        &Solution {
+            variables: vec![false; 3]
+        }
+    )
+    .is_err());
+}
@@ -0,0 +1,41 @@
+use anyhow::{anyhow, Result};
+use clap::{arg, Command};
+use std::{fs, path::PathBuf};
+use tig_protocol::replay::{replay, BlockSnapshot};
+use tig_utils::{dejsonify, jsonify};
+
+fn cli() -> Command {
+    Command::new("tig-replay")
+        .about("Replays add_block over block snapshots and diffs the reward data of each block")
+        .arg_required_else_help(true)
+        .arg(
+            arg!(<SNAPSHOTS> "Path to a json file with a list of block snapshots")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(arg!(--all "Also output blocks without differences").action(clap::ArgAction::SetTrue))
+}
+
+fn main() {
+    let matches = cli().get_matches();
+
+    if let Err(e) = replay_snapshots(
+        matches.get_one::<PathBuf>("SNAPSHOTS").unwrap().clone(),
+        matches.get_one::<bool>("all").cloned().unwrap_or(false),
+    ) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn replay_snapshots(path: PathBuf, all: bool) -> Result<()> {
+    let snapshots = fs::read_to_string(&path)
+        .map_err(|e| anyhow!("Failed to read snapshots file {:?}: {}", path, e))?;
+    let snapshots = dejsonify::<Vec<BlockSnapshot>>(&snapshots)
+        .map_err(|e| anyhow!("Failed to parse snapshots: {}", e))?;
+    let block_diffs = futures::executor::block_on(replay(&snapshots))?
+        .into_iter()
+        .filter(|d| all || !d.diffs.is_empty())
+        .collect::<Vec<_>>();
+    println!("{}", jsonify(&block_diffs));
+    Ok(())
+}
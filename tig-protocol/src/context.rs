@@ -1,16 +1,25 @@
+pub use crate::events::BlockEvent;
 pub use anyhow::Result;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use tig_structs::{config::*, core::*};
 
 #[allow(async_fn_in_trait)]
 pub trait Context {
     async fn get_advance_state(&self, advance_id: &String) -> Option<AdvanceState>;
+    // advances of the player submitted from `min_round_submitted`, including those in the mempool
+    async fn get_recent_advance_ids(
+        &self,
+        player_id: &String,
+        min_round_submitted: u32,
+    ) -> HashSet<String>;
     async fn add_advance_to_mempool(
         &self,
         details: AdvanceDetails,
         evidence: String,
     ) -> Result<String>;
     async fn get_benchmark_details(&self, benchmark_id: &String) -> Option<BenchmarkDetails>;
+    async fn get_benchmark_solution_quality(&self, benchmark_id: &String) -> Option<Vec<i32>>;
     async fn add_benchmark_to_mempool(
         &self,
         benchmark_id: String,
@@ -20,9 +29,21 @@ pub trait Context {
     async fn get_binary_details(&self, code_id: &String) -> Option<BinaryDetails>;
     async fn add_binary_to_mempool(&self, code_id: String, details: BinaryDetails) -> Result<()>;
     async fn get_latest_block_id(&self) -> String;
+    async fn get_active_ids(
+        &self,
+        block_id: &String,
+        active_type: ActiveType,
+    ) -> Option<HashSet<String>>;
     async fn get_block_details(&self, block_id: &String) -> Option<BlockDetails>;
     async fn get_challenge_state(&self, challenge_id: &String) -> Option<ChallengeState>;
+    async fn get_code_details(&self, code_id: &String) -> Option<CodeDetails>;
     async fn get_code_state(&self, code_id: &String) -> Option<CodeState>;
+    // codes of the player submitted from `min_round_submitted`, including those in the mempool
+    async fn get_recent_code_ids(
+        &self,
+        player_id: &String,
+        min_round_submitted: u32,
+    ) -> HashSet<String>;
     async fn add_code_to_mempool(
         &self,
         details: CodeDetails,
@@ -30,6 +51,8 @@ pub trait Context {
     ) -> Result<String>;
     async fn get_config(&self) -> ProtocolConfig;
     async fn add_deposit_to_mempool(&self, details: DepositDetails) -> Result<String>;
+    async fn get_fraud_state(&self, benchmark_id: &String) -> Option<FraudState>;
+    async fn add_fraud_to_mempool(&self, benchmark_id: String, allegation: String) -> Result<()>;
     async fn get_player_details(&self, player_id: &String) -> Option<PlayerDetails>;
     async fn get_player_state(&self, player_id: &String) -> Option<PlayerState>;
     async fn get_player_block_data(
@@ -41,17 +64,39 @@ pub trait Context {
         &self,
         player_id: String,
         delegatees: HashMap<String, f64>,
+        block_effective: u32,
+    ) -> Result<()>;
+    async fn set_player_reward_share(
+        &self,
+        player_id: String,
+        reward_share: f64,
+        block_effective: u32,
     ) -> Result<()>;
-    async fn set_player_reward_share(&self, player_id: String, reward_share: f64) -> Result<()>;
     async fn set_player_coinbase(
         &self,
         player_id: String,
         coinbase: HashMap<String, f64>,
+        block_effective: u32,
     ) -> Result<()>;
     async fn set_player_vote(&self, player_id: String, advance_id: String, yes: bool)
         -> Result<()>;
+    async fn set_algorithm_ban(&self, algorithm_id: String, change: BanChange) -> Result<()>;
+    async fn set_player_vote_delegatee(
+        &self,
+        player_id: String,
+        vote_delegatee: Option<String>,
+    ) -> Result<()>;
+    // players whose vote delegatee is the player
+    async fn get_vote_delegator_ids(&self, player_id: &String) -> HashSet<String>;
     async fn get_precommit_settings(&self, benchmark_id: &String) -> Option<BenchmarkSettings>;
     async fn get_precommit_details(&self, benchmark_id: &String) -> Option<PrecommitDetails>;
+    // precommits of the player started from `min_block_started`, including those in the mempool,
+    // without a submitted benchmark
+    async fn get_pending_precommit_ids(
+        &self,
+        player_id: &String,
+        min_block_started: u32,
+    ) -> HashSet<String>;
     async fn add_precommit_to_mempool(
         &self,
         settings: BenchmarkSettings,
@@ -67,25 +112,79 @@ pub trait Context {
     ) -> Result<()>;
     async fn add_topup_to_mempool(&self, details: TopUpDetails) -> Result<String>;
 
+    // builds the cache of the next block. The context only sets `block_data.active_ids` of
+    // deposits and players, and puts candidates in the cache for the other types: challenges in
+    // `active_challenges_block_data`, codes and advances in their state maps and benchmarks in
+    // `recent_benchmarks`. `update_block_cache` selects the challenges, codes, advances,
+    // benchmarks and OPoW players active in the block, applying retirement, bans and lifespans
     async fn build_block_cache(&self) -> AddBlockCache;
+    // stores the cache once updated, with the active ids of every type
     async fn commit_block_cache(&self, cache: AddBlockCache);
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct AddBlockCache {
     pub config: ProtocolConfig,
     pub block_details: BlockDetails,
     pub block_data: BlockData,
     pub active_deposit_details: HashMap<String, DepositDetails>,
+    // includes every player with a benchmark in `recent_benchmarks`, as the contracts deduct
+    // their fee balance for frauds and missing proofs
     pub active_players_state: HashMap<String, PlayerState>,
     pub active_players_block_data: HashMap<String, PlayerBlockData>,
+    // OPoW data of the previous block, the contracts replace it with that of the players taking
+    // part in OPoW
     pub active_opow_block_data: HashMap<String, OPoWBlockData>,
+    // challenges that became active, the contracts drop the retired ones
     pub active_challenges_block_data: HashMap<String, ChallengeBlockData>,
+    // codes with a binary or a ban, the contracts select the active ones
     pub active_codes_state: HashMap<String, CodeState>,
     pub active_codes_details: HashMap<String, CodeDetails>,
     pub active_codes_block_data: HashMap<String, CodeBlockData>,
     pub voting_advances_state: HashMap<String, AdvanceState>,
+    // advances not being voted on that were activated or have a ban, the contracts select the
+    // active ones
     pub active_advances_state: HashMap<String, AdvanceState>,
     pub active_advances_details: HashMap<String, AdvanceDetails>,
     pub active_advances_block_data: HashMap<String, AdvanceBlockData>,
+    // benchmarks whose precommit's lifespan had not ended by the previous block, the contracts
+    // select the active ones into `active_benchmarks`
+    #[serde(default)]
+    pub recent_benchmarks: HashMap<String, RecentBenchmark>,
     pub active_benchmarks: Vec<(BenchmarkSettings, Vec<i32>)>,
+    // details of the binaries confirmed in this block
+    #[serde(default)]
+    pub confirmed_binaries_details: HashMap<String, BinaryDetails>,
+    // emitted by the contracts during the update
+    #[serde(default)]
+    pub events: Vec<BlockEvent>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecentBenchmark {
+    pub settings: BenchmarkSettings,
+    pub precommit_details: PrecommitDetails,
+    pub details: BenchmarkDetails,
+    pub proof_details: Option<ProofDetails>,
+    #[serde(default)]
+    pub fraud_state: Option<FraudState>,
+}
+
+// opow block data of a player before `opow::update`
+pub(crate) fn new_opow_block_data() -> OPoWBlockData {
+    let zero = PreciseNumber::from(0);
+    OPoWBlockData {
+        num_qualifiers_by_challenge_by_track: HashMap::new(),
+        num_potential_qualifiers_by_challenge_by_track: HashMap::new(),
+        legacy_multiplier_by_challenge_by_track: HashMap::new(),
+        cutoff: 0,
+        weighted_self_deposit: zero,
+        weighted_delegated_deposit: zero,
+        delegators: HashSet::new(),
+        reward_share: zero,
+        coinbase: HashMap::new(),
+        imbalance: zero,
+        influence: zero,
+        reward: zero,
+    }
 }
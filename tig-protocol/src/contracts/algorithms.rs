@@ -1,10 +1,69 @@
+use super::{
+    bans::{apply_bans, banned_in_round},
+    players::tally_votes,
+};
 use crate::context::*;
 use anyhow::{anyhow, Result};
 use logging_timer::time;
-use std::collections::HashMap;
-use tig_structs::core::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tig_structs::{config::*, core::*};
 use tig_utils::*;
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AlgorithmLifecycle {
+    pub algorithm_id: String,
+    /// stage in the latest round
+    pub stage: LifecycleStage,
+    /// every transition, including those scheduled for a later round
+    pub lifecycle: Lifecycle,
+}
+
+/// Lifecycle of a code or advance. Lifecycles of algorithms submitted before they were recorded
+/// are rebuilt from the rounds of their state
+pub async fn get_lifecycle<T: Context>(
+    ctx: &T,
+    algorithm_id: String,
+) -> Result<AlgorithmLifecycle> {
+    let latest_block_id = ctx.get_latest_block_id().await;
+    let latest_block_details = ctx.get_block_details(&latest_block_id).await.unwrap();
+    let mut events = Vec::new();
+    let lifecycle = match ctx.get_code_state(&algorithm_id).await {
+        Some(mut code_state) => {
+            backfill_code_lifecycle(&algorithm_id, &mut code_state, &mut events);
+            code_state.lifecycle
+        }
+        None => {
+            let mut advance_state = ctx
+                .get_advance_state(&algorithm_id)
+                .await
+                .ok_or_else(|| anyhow!("Invalid algorithm '{}'", algorithm_id))?;
+            backfill_advance_lifecycle(
+                &algorithm_id,
+                &mut advance_state,
+                latest_block_details.round,
+                &mut events,
+            );
+            advance_state.lifecycle
+        }
+    };
+    if let Some(BlockEvent::TransitionRejected { reason, .. }) = events.first() {
+        return Err(anyhow!(
+            "Invalid lifecycle for '{}': {}",
+            algorithm_id,
+            reason
+        ));
+    }
+    let stage = lifecycle
+        .stage_at(latest_block_details.round)
+        .ok_or_else(|| anyhow!("No lifecycle recorded for '{}'", algorithm_id))?;
+    Ok(AlgorithmLifecycle {
+        algorithm_id,
+        stage,
+        lifecycle,
+    })
+}
+
 #[time]
 pub async fn submit_advance<T: Context>(
     ctx: &T,
@@ -20,10 +79,31 @@ pub async fn submit_advance<T: Context>(
         .get_challenge_state(&challenge_id)
         .await
         .is_some_and(|s| s.round_active <= latest_block_details.round)
+        // the challenge must outlast the vote
+        || config.challenges.get(&challenge_id).is_some_and(|c| {
+            c.is_retired_at(
+                latest_block_details.round
+                    + config.advances.vote_start_delay
+                    + config.advances.vote_period,
+            )
+        })
     {
         return Err(anyhow!("Invalid challenge '{}'", challenge_id));
     }
 
+    if let Some(limits) = &config.submission_limits {
+        let num_submitted = ctx
+            .get_recent_advance_ids(&player_id, latest_block_details.round)
+            .await
+            .len();
+        if num_submitted >= limits.max_advances_per_round {
+            return Err(anyhow!(
+                "Too many advances submitted this round (max {})",
+                limits.max_advances_per_round
+            ));
+        }
+    }
+
     if !ctx
         .get_player_state(&player_id)
         .await
@@ -68,6 +148,10 @@ pub async fn submit_code<T: Context>(
                     + config.advances.vote_period
                     + config.codes.push_delay_period
         })
+        // the challenge must outlast the push delay
+        || config.challenges.get(&challenge_id).is_some_and(|c| {
+            c.is_retired_at(latest_block_details.round + config.codes.push_delay_period)
+        })
     {
         return Err(anyhow!("Invalid challenge '{}'", challenge_id));
     }
@@ -78,6 +162,19 @@ pub async fn submit_code<T: Context>(
         }
     }
 
+    if let Some(limits) = &config.submission_limits {
+        let num_submitted = ctx
+            .get_recent_code_ids(&player_id, latest_block_details.round)
+            .await
+            .len();
+        if num_submitted >= limits.max_codes_per_round {
+            return Err(anyhow!(
+                "Too many codes submitted this round (max {})",
+                limits.max_codes_per_round
+            ));
+        }
+    }
+
     if !ctx
         .get_player_state(&player_id)
         .await
@@ -101,37 +198,199 @@ pub async fn submit_code<T: Context>(
     Ok(algorithm_id)
 }
 
+/// Records the outcome of compiling a code for each architecture, and for GPU challenges its ptx.
+/// A compiled artifact must come with its blake3 hash. The code compiles successfully if at least
+/// one architecture (and the ptx, if any) compiled, in which case the download URL of the
+/// artifacts and the toolchain version used are required. A code that fails to compile is banned
+/// from the current round
 #[time]
 pub async fn submit_binary<T: Context>(
     ctx: &T,
     algorithm_id: String,
-    compile_success: bool,
     download_url: Option<String>,
+    so_by_arch: HashMap<CPUArchitecture, BinaryArtifact>,
+    ptx: Option<BinaryArtifact>,
+    toolchain_version: Option<String>,
 ) -> Result<()> {
-    if ctx.get_code_state(&algorithm_id).await.is_none() {
-        return Err(anyhow!("Invalid algorithm: {}", algorithm_id));
-    }
+    let code_state = match ctx.get_code_state(&algorithm_id).await {
+        None => return Err(anyhow!("Invalid algorithm: {}", algorithm_id)),
+        Some(code_state) if code_state.banned => {
+            return Err(anyhow!("Algorithm is banned: {}", algorithm_id))
+        }
+        Some(code_state) => code_state,
+    };
+    let code_details = ctx.get_code_details(&algorithm_id).await.unwrap();
     if ctx.get_binary_details(&algorithm_id).await.is_some() {
         return Err(anyhow!(
             "Binary already submitted for algorithm: {}",
             algorithm_id
         ));
     }
-    if compile_success && download_url.is_none() {
-        return Err(anyhow!("Missing download URL"));
+
+    let check_artifact = |artifact: &BinaryArtifact, target: String| match &artifact.hash {
+        None if artifact.compile_success => Err(anyhow!("Missing {} hash", target)),
+        Some(_) if !artifact.compile_success => {
+            Err(anyhow!("A failed {} compilation has no hash", target))
+        }
+        Some(h) if !is_blake3_hex(h) => Err(anyhow!(
+            "Invalid {} hash. Must be a blake3 hex digest",
+            target
+        )),
+        _ => Ok(()),
+    };
+    for (arch, artifact) in so_by_arch.iter() {
+        check_artifact(artifact, format!("{:?} so", arch))?;
+    }
+    let config = ctx.get_config().await;
+    let is_gpu = config.challenges[&code_details.challenge_id].r#type == ChallengeType::GPU;
+    let ptx_success = match (&ptx, is_gpu) {
+        (Some(_), false) => return Err(anyhow!("Unexpected ptx for a CPU challenge")),
+        (None, true) => return Err(anyhow!("Missing ptx for a GPU challenge")),
+        (Some(artifact), true) => {
+            check_artifact(artifact, "ptx".to_string())?;
+            artifact.compile_success
+        }
+        (None, false) => true,
+    };
+
+    let compile_success = ptx_success && so_by_arch.values().any(|a| a.compile_success);
+    if compile_success {
+        if download_url.is_none() {
+            return Err(anyhow!("Missing download URL"));
+        }
+        if toolchain_version
+            .as_ref()
+            .is_none_or(|v| v.trim().is_empty())
+        {
+            return Err(anyhow!("Missing toolchain version"));
+        }
+    } else if download_url.is_some() {
+        return Err(anyhow!("A failed compilation has no download URL"));
     }
 
     ctx.add_binary_to_mempool(
-        algorithm_id,
+        algorithm_id.clone(),
         BinaryDetails {
             compile_success,
             download_url,
+            so_by_arch,
+            ptx,
+            toolchain_version,
         },
     )
     .await?;
+
+    // a code that fails to compile is banned right away
+    if !compile_success {
+        let latest_block_id = ctx.get_latest_block_id().await;
+        let latest_block_details = ctx.get_block_details(&latest_block_id).await.unwrap();
+        let round = latest_block_details.round;
+        ctx.set_algorithm_ban(
+            algorithm_id,
+            BanChange {
+                banned: true,
+                reason: "Failed to compile".to_string(),
+                block_set: latest_block_details.height,
+                round_effective: code_state
+                    .bans
+                    .last()
+                    .map_or(round, |b| round.max(b.round_effective + 1)),
+            },
+        )
+        .await?;
+    }
     Ok(())
 }
 
+// codes become active from the round they are pushed in once their binary compiles, banned
+// codes when they are unbanned. Selects the active codes and advances among those of the cache
+#[time]
+pub(crate) async fn select_active(cache: &mut AddBlockCache) {
+    let AddBlockCache {
+        block_details,
+        block_data,
+        active_codes_state,
+        active_codes_details,
+        active_codes_block_data,
+        voting_advances_state,
+        active_advances_state,
+        active_advances_details,
+        active_advances_block_data,
+        confirmed_binaries_details,
+        events,
+        ..
+    } = cache;
+
+    for (algorithm_id, code_state) in active_codes_state.iter_mut() {
+        backfill_code_lifecycle(algorithm_id, code_state, events);
+    }
+    for (algorithm_id, advance_state) in voting_advances_state
+        .iter_mut()
+        .chain(active_advances_state.iter_mut())
+    {
+        backfill_advance_lifecycle(algorithm_id, advance_state, block_details.round, events);
+    }
+
+    for (algorithm_id, binary_details) in confirmed_binaries_details.iter() {
+        let code_state = match active_codes_state.get_mut(algorithm_id) {
+            Some(code_state) if binary_details.compile_success => code_state,
+            _ => continue,
+        };
+        // a banned code enters Active when it is unbanned
+        if !code_state.banned {
+            // an unbanned code re-entered its stage after it was pushed
+            let round = code_state
+                .lifecycle
+                .0
+                .last()
+                .map_or(code_state.round_pushed, |t| {
+                    t.round.max(code_state.round_pushed)
+                });
+            if !try_transition(
+                algorithm_id,
+                &mut code_state.lifecycle,
+                LifecycleStage::Active,
+                round,
+                events,
+            ) {
+                continue;
+            }
+        }
+        code_state.round_active = Some(code_state.round_pushed);
+    }
+
+    // bans taking effect this round apply to the block, before `update` records them
+    let round = block_details.round;
+    let active_challenge_ids = &block_data.active_ids[&ActiveType::Challenge];
+    let active_code_ids = active_codes_state
+        .iter()
+        .filter(|(algorithm_id, code_state)| {
+            !banned_in_round(code_state.banned, &code_state.bans, round)
+                && code_state.round_active.is_some_and(|r| r <= round)
+                && active_challenge_ids.contains(&active_codes_details[*algorithm_id].challenge_id)
+        })
+        .map(|(algorithm_id, _)| algorithm_id.clone())
+        .collect::<HashSet<String>>();
+    let active_advance_ids = active_advances_state
+        .iter()
+        .filter(|(algorithm_id, advance_state)| {
+            !banned_in_round(advance_state.banned, &advance_state.bans, round)
+                && advance_state.round_active.is_some_and(|r| r <= round)
+                && active_challenge_ids
+                    .contains(&active_advances_details[*algorithm_id].challenge_id)
+        })
+        .map(|(algorithm_id, _)| algorithm_id.clone())
+        .collect::<HashSet<String>>();
+    active_codes_block_data.retain(|algorithm_id, _| active_code_ids.contains(algorithm_id));
+    active_advances_block_data.retain(|algorithm_id, _| active_advance_ids.contains(algorithm_id));
+    block_data
+        .active_ids
+        .insert(ActiveType::Code, active_code_ids);
+    block_data
+        .active_ids
+        .insert(ActiveType::Advance, active_advance_ids);
+}
+
 #[time]
 pub(crate) async fn update(cache: &mut AddBlockCache) {
     let AddBlockCache {
@@ -147,6 +406,7 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
         voting_advances_state,
         active_players_state,
         active_players_block_data,
+        events,
         ..
     } = cache;
 
@@ -155,28 +415,54 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
     let active_challenge_ids = &block_data.active_ids[&ActiveType::Challenge];
     let active_player_ids = &block_data.active_ids[&ActiveType::Player];
 
-    // update votes
-    for advance_state in voting_advances_state.values_mut() {
-        advance_state.votes_tally = HashMap::from([
-            (true, PreciseNumber::from(0)),
-            (false, PreciseNumber::from(0)),
-        ]);
+    // bans and unbans taking effect. Unbanned algorithms move on to the stage they reached while
+    // banned: codes whose binary was confirmed are active, advances whose votes were tallied are
+    // active or expired
+    for (algorithm_id, code_state) in active_codes_state.iter_mut() {
+        let resume_stage = code_state.round_active.map(|_| LifecycleStage::Active);
+        apply_bans(
+            algorithm_id,
+            &mut code_state.banned,
+            &mut code_state.lifecycle,
+            &code_state.bans,
+            resume_stage,
+            block_details.round,
+            events,
+        );
     }
-    for player_id in active_player_ids.iter() {
-        let player_state = &active_players_state[player_id];
-        let player_data = &active_players_block_data[player_id];
-        for (algorithm_id, vote) in player_state.votes.iter() {
-            let yes = vote.value;
-            if let Some(advance_state) = voting_advances_state.get_mut(algorithm_id) {
-                let n = advance_state.round_votes_tallied - block_details.round;
-                let votes: PreciseNumber = player_data
-                    .deposit_by_locked_period
-                    .iter()
-                    .skip(n as usize)
-                    .sum();
-                *advance_state.votes_tally.get_mut(&yes).unwrap() += votes;
+    for (algorithm_id, advance_state) in voting_advances_state
+        .iter_mut()
+        .chain(active_advances_state.iter_mut())
+    {
+        let resume_stage = match advance_state.round_active {
+            Some(_) => Some(LifecycleStage::Active),
+            None if advance_state.round_votes_tallied <= block_details.round => {
+                Some(LifecycleStage::Expired)
             }
-        }
+            None => None,
+        };
+        apply_bans(
+            algorithm_id,
+            &mut advance_state.banned,
+            &mut advance_state.lifecycle,
+            &advance_state.bans,
+            resume_stage,
+            block_details.round,
+            events,
+        );
+    }
+
+    // update votes
+    for (algorithm_id, advance_state) in voting_advances_state.iter_mut() {
+        let tally = tally_votes(
+            algorithm_id,
+            advance_state,
+            block_details.round,
+            active_player_ids,
+            active_players_state,
+            active_players_block_data,
+        );
+        advance_state.votes_tally = HashMap::from([(true, tally.yes), (false, tally.no)]);
     }
 
     // update adoption
@@ -277,37 +563,66 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
         }
     }
 
-    // update merges at last block of the round
+    // update merges at last block of the round, in order of id so that events are deterministic
     if (block_details.height + 1) % config.rounds.blocks_per_round == 0 {
-        for algorithm_ids in codes_by_challenge.values() {
+        let mut challenge_ids = codes_by_challenge.keys().collect::<Vec<_>>();
+        challenge_ids.sort();
+        for challenge_id in challenge_ids {
+            let algorithm_ids = &codes_by_challenge[challenge_id];
             let algorithm_id = algorithm_ids
                 .iter()
                 .max_by_key(|&id| active_codes_block_data[id].merge_points)
                 .unwrap();
 
-            if active_codes_block_data[algorithm_id].merge_points
-                < config.codes.merge_points_threshold
+            let code_state = active_codes_state.get_mut(algorithm_id).unwrap();
+            if code_state.round_merged.is_some()
+                || active_codes_block_data[algorithm_id].merge_points
+                    < config.codes.merge_points_threshold
             {
                 continue;
             }
 
-            active_codes_state
-                .get_mut(algorithm_id)
-                .unwrap()
-                .round_merged = Some(block_details.round + 1);
+            if !try_transition(
+                algorithm_id,
+                &mut code_state.lifecycle,
+                LifecycleStage::Merged,
+                block_details.round + 1,
+                events,
+            ) {
+                continue;
+            }
+            code_state.round_merged = Some(block_details.round + 1);
+            events.push(BlockEvent::CodeMerged {
+                code_id: algorithm_id.clone(),
+                round_merged: block_details.round + 1,
+            });
         }
 
-        for algorithm_id in active_advance_ids.iter() {
-            if active_advances_block_data[algorithm_id].merge_points
-                < config.advances.merge_points_threshold
+        let mut advance_ids = active_advance_ids.iter().collect::<Vec<_>>();
+        advance_ids.sort();
+        for algorithm_id in advance_ids {
+            let advance_state = active_advances_state.get_mut(algorithm_id).unwrap();
+            if advance_state.round_merged.is_some()
+                || active_advances_block_data[algorithm_id].merge_points
+                    < config.advances.merge_points_threshold
             {
                 continue;
             }
 
-            active_advances_state
-                .get_mut(algorithm_id)
-                .unwrap()
-                .round_merged = Some(block_details.round + 1);
+            if !try_transition(
+                algorithm_id,
+                &mut advance_state.lifecycle,
+                LifecycleStage::Merged,
+                block_details.round + 1,
+                events,
+            ) {
+                continue;
+            }
+            advance_state.round_merged = Some(block_details.round + 1);
+            events.push(BlockEvent::AdvanceMerged {
+                advance_id: algorithm_id.clone(),
+                round_merged: block_details.round + 1,
+            });
         }
     }
 
@@ -315,15 +630,160 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
     if (block_details.height + 1) % config.rounds.blocks_per_round == 0 {
         let yes_threshold = PreciseNumber::from_f64(config.advances.min_percent_yes_votes);
         let zero = PreciseNumber::from(0);
-        for advance in voting_advances_state.values_mut() {
-            if advance.round_votes_tallied == block_details.round + 1 {
+        let mut advance_ids = voting_advances_state.keys().cloned().collect::<Vec<_>>();
+        advance_ids.sort();
+        for algorithm_id in advance_ids.iter() {
+            let advance = voting_advances_state.get_mut(algorithm_id).unwrap();
+            // advances banned by the round their votes are tallied are not activated by their vote
+            if advance.round_votes_tallied == block_details.round + 1
+                && !banned_in_round(advance.banned, &advance.bans, block_details.round + 1)
+            {
                 let yes = &advance.votes_tally[&true];
                 let no = &advance.votes_tally[&false];
                 let total = yes + no;
-                if total != zero && yes / total >= yes_threshold {
-                    advance.round_active = Some(block_details.round + 1);
+                let passed = total != zero && yes / total >= yes_threshold;
+                events.push(BlockEvent::VoteTallied {
+                    advance_id: algorithm_id.clone(),
+                    yes: *yes,
+                    no: *no,
+                    passed,
+                });
+                // an advance still banned is unbanned next round, entering the stage of its vote
+                // then
+                let banned = advance.lifecycle.latest() == Some(LifecycleStage::Banned);
+                if passed {
+                    if banned
+                        || try_transition(
+                            algorithm_id,
+                            &mut advance.lifecycle,
+                            LifecycleStage::Active,
+                            block_details.round + 1,
+                            events,
+                        )
+                    {
+                        advance.round_active = Some(block_details.round + 1);
+                        events.push(BlockEvent::AdvanceActivated {
+                            advance_id: algorithm_id.clone(),
+                            round_active: block_details.round + 1,
+                        });
+                    }
+                } else if !banned {
+                    try_transition(
+                        algorithm_id,
+                        &mut advance.lifecycle,
+                        LifecycleStage::Expired,
+                        block_details.round + 1,
+                        events,
+                    );
                 }
             }
         }
     }
 }
+
+// lifecycles move before the rounds of the state are updated, which only happens if the transition
+// succeeds. Every transition the contracts make is allowed, so a rejected one is a bug that leaves
+// the state unchanged and is reported as an event rather than failing the block
+pub(crate) fn try_transition(
+    algorithm_id: &str,
+    lifecycle: &mut Lifecycle,
+    stage: LifecycleStage,
+    round: u32,
+    events: &mut Vec<BlockEvent>,
+) -> bool {
+    let result = lifecycle.transition(stage, round);
+    debug_assert!(result.is_ok(), "'{}': {:?}", algorithm_id, result);
+    match result {
+        Ok(()) => true,
+        Err(reason) => {
+            events.push(BlockEvent::TransitionRejected {
+                algorithm_id: algorithm_id.to_string(),
+                stage,
+                round,
+                reason,
+            });
+            false
+        }
+    }
+}
+
+// lifecycles were not recorded for algorithms submitted before them, so an empty one is rebuilt
+// from the rounds of the state
+fn backfill_code_lifecycle(
+    algorithm_id: &str,
+    code_state: &mut CodeState,
+    events: &mut Vec<BlockEvent>,
+) {
+    if code_state.lifecycle.0.is_empty() {
+        backfill_lifecycle(
+            algorithm_id,
+            &mut code_state.lifecycle,
+            [
+                (LifecycleStage::Submitted, Some(code_state.round_submitted)),
+                (LifecycleStage::Pushed, Some(code_state.round_pushed)),
+                (LifecycleStage::Active, code_state.round_active),
+                (LifecycleStage::Merged, code_state.round_merged),
+            ],
+            code_state.banned,
+            events,
+        );
+    }
+}
+
+// an advance that was not activated by its tallied vote expired
+fn backfill_advance_lifecycle(
+    algorithm_id: &str,
+    advance_state: &mut AdvanceState,
+    round: u32,
+    events: &mut Vec<BlockEvent>,
+) {
+    if advance_state.lifecycle.0.is_empty() {
+        let round_expired = match advance_state.round_active {
+            None if advance_state.round_votes_tallied <= round => {
+                Some(advance_state.round_votes_tallied)
+            }
+            _ => None,
+        };
+        backfill_lifecycle(
+            algorithm_id,
+            &mut advance_state.lifecycle,
+            [
+                (
+                    LifecycleStage::Submitted,
+                    Some(advance_state.round_submitted),
+                ),
+                (LifecycleStage::Pushed, Some(advance_state.round_pushed)),
+                (LifecycleStage::Active, advance_state.round_active),
+                (LifecycleStage::Merged, advance_state.round_merged),
+                (LifecycleStage::Expired, round_expired),
+            ],
+            advance_state.banned,
+            events,
+        );
+    }
+}
+
+fn backfill_lifecycle<const N: usize>(
+    algorithm_id: &str,
+    lifecycle: &mut Lifecycle,
+    rounds: [(LifecycleStage, Option<u32>); N],
+    banned: bool,
+    events: &mut Vec<BlockEvent>,
+) {
+    for (stage, round) in rounds {
+        if let Some(round) = round {
+            try_transition(algorithm_id, lifecycle, stage, round, events);
+        }
+    }
+    // an algorithm that expired before its ban took effect stays expired
+    if banned && lifecycle.latest() != Some(LifecycleStage::Expired) {
+        let round = lifecycle.0.last().map_or(0, |t| t.round);
+        try_transition(
+            algorithm_id,
+            lifecycle,
+            LifecycleStage::Banned,
+            round,
+            events,
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use super::algorithms::try_transition;
+use crate::context::*;
+use anyhow::{anyhow, Result};
+use logging_timer::time;
+use tig_structs::core::*;
+
+/// Bans or unbans a code or advance from `round_effective`, by default the next round. Only the
+/// governance player can do so. Banned codes earn no rewards and their benchmarks stop being
+/// active, banned advances earn no rewards and are not activated by their vote. Unbanning returns
+/// the lifecycle to the stage it was banned in, and codes that failed to compile cannot be unbanned
+#[time]
+pub async fn set_ban<T: Context>(
+    ctx: &T,
+    player_id: String,
+    algorithm_id: String,
+    banned: bool,
+    reason: String,
+    round_effective: Option<u32>,
+) -> Result<()> {
+    let config = ctx.get_config().await;
+    let governance_address = match &config.bans {
+        Some(bans_config) => &bans_config.governance_address,
+        None => return Err(anyhow!("Banning is disabled")),
+    };
+    if player_id != *governance_address {
+        return Err(anyhow!(
+            "Only '{}' can ban or unban algorithms",
+            governance_address
+        ));
+    }
+    if reason.trim().is_empty() {
+        return Err(anyhow!("Missing reason"));
+    }
+
+    let (is_banned, lifecycle, bans) = match ctx.get_code_state(&algorithm_id).await {
+        Some(code_state) => (code_state.banned, code_state.lifecycle, code_state.bans),
+        None => {
+            let advance_state = ctx
+                .get_advance_state(&algorithm_id)
+                .await
+                .ok_or_else(|| anyhow!("Invalid algorithm '{}'", algorithm_id))?;
+            (
+                advance_state.banned,
+                advance_state.lifecycle,
+                advance_state.bans,
+            )
+        }
+    };
+    // compared against the status once every queued change is in effect
+    if bans.last().map_or(is_banned, |b| b.banned) == banned {
+        return Err(anyhow!(
+            "'{}' is already {}",
+            algorithm_id,
+            if banned { "banned" } else { "unbanned" }
+        ));
+    }
+    if lifecycle.latest() == Some(LifecycleStage::Expired) {
+        return Err(anyhow!("'{}' has expired", algorithm_id));
+    }
+    if !banned
+        && ctx
+            .get_binary_details(&algorithm_id)
+            .await
+            .is_some_and(|b| !b.compile_success)
+    {
+        return Err(anyhow!(
+            "Cannot unban '{}' as it failed to compile",
+            algorithm_id
+        ));
+    }
+
+    let latest_block_id = ctx.get_latest_block_id().await;
+    let latest_block_details = ctx.get_block_details(&latest_block_id).await.unwrap();
+    let earliest = bans
+        .last()
+        .map_or(0, |b| b.round_effective + 1)
+        .max(latest_block_details.round + 1);
+    let round_effective = round_effective.unwrap_or(earliest);
+    if round_effective < earliest {
+        return Err(anyhow!(
+            "Invalid round_effective {}. Must be at least {}",
+            round_effective,
+            earliest
+        ));
+    }
+
+    ctx.set_algorithm_ban(
+        algorithm_id,
+        BanChange {
+            banned,
+            reason,
+            block_set: latest_block_details.height,
+            round_effective,
+        },
+    )
+    .await?;
+    Ok(())
+}
+
+// whether an algorithm is banned in `round`, including changes that are queued but not yet applied
+pub(crate) fn banned_in_round(banned: bool, bans: &[BanChange], round: u32) -> bool {
+    bans.iter()
+        .rev()
+        .find(|b| b.round_effective <= round)
+        .map_or(banned, |b| b.banned)
+}
+
+// applies the latest ban or unban in effect by `round`, moving the lifecycle in or out of Banned.
+// Unbanned algorithms return to the stage they were banned in, then move on to `resume_stage` if
+// they reached it while banned
+pub(crate) fn apply_bans(
+    algorithm_id: &str,
+    banned: &mut bool,
+    lifecycle: &mut Lifecycle,
+    bans: &[BanChange],
+    resume_stage: Option<LifecycleStage>,
+    round: u32,
+    events: &mut Vec<BlockEvent>,
+) {
+    let change = match bans.iter().rev().find(|b| b.round_effective <= round) {
+        Some(change) if change.banned != *banned => change,
+        _ => return,
+    };
+    *banned = change.banned;
+    let round = lifecycle.0.last().map_or(round, |t| t.round.max(round));
+    // an algorithm that expired before its ban took effect stays expired
+    if lifecycle.latest() == Some(LifecycleStage::Expired) {
+        return;
+    }
+    if change.banned {
+        try_transition(
+            algorithm_id,
+            lifecycle,
+            LifecycleStage::Banned,
+            round,
+            events,
+        );
+        return;
+    }
+    let unbanned = lifecycle.unban(round);
+    debug_assert!(unbanned.is_ok(), "'{}': {:?}", algorithm_id, unbanned);
+    if let (Ok(()), Some(stage), Some(latest)) = (unbanned, resume_stage, lifecycle.latest()) {
+        // stages the algorithm already passed are not re-entered
+        if latest != stage && latest.can_transition_to(stage) {
+            try_transition(algorithm_id, lifecycle, stage, round, events);
+        }
+    }
+}
@@ -2,41 +2,75 @@ use crate::context::*;
 use anyhow::{anyhow, Result};
 use logging_timer::time;
 use rand::{prelude::IteratorRandom, rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::collections::{HashMap, HashSet};
 use tig_structs::{config::*, core::*};
+use tig_utils::{blake3_hex, jsonify};
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PrecommitQuote {
+    /// tracks one of which is randomly selected on submission, sorted by track id
+    pub tracks: Vec<TrackQuote>,
+    /// errors that fail the submission whichever track is selected
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TrackQuote {
+    pub track_id: String,
+    pub probability: f64,
+    pub num_nonces: u64,
+    pub submission_fee: PreciseNumber,
+    /// errors that fail the submission if this track is selected
+    pub errors: Vec<String>,
+}
+
+impl PrecommitQuote {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty() && self.tracks.iter().all(|t| t.errors.is_empty())
+    }
+}
+
+/// Validates a precommit without submitting it, returning every validation error along with the
+/// fee and number of nonces of each track that could be selected
 #[time]
-pub async fn submit_precommit<T: Context>(
+pub async fn quote_precommit<T: Context>(
     ctx: &T,
     player_id: String,
-    mut settings: BenchmarkSettings,
-    mut track_settings: HashMap<String, TrackSettings>,
-    seed: u64,
-) -> Result<String> {
+    settings: BenchmarkSettings,
+    track_settings: HashMap<String, TrackSettings>,
+) -> PrecommitQuote {
+    let mut errors = Vec::new();
     if player_id != settings.player_id {
-        return Err(anyhow!("Invalid settings.player_id. Must be {}", player_id));
+        errors.push(format!("Invalid settings.player_id. Must be {}", player_id));
     }
 
     let config = ctx.get_config().await;
 
     let latest_block_id = ctx.get_latest_block_id().await;
     let latest_block_details = ctx.get_block_details(&latest_block_id).await.unwrap();
-    if settings.block_id != latest_block_id
+    let latest_height = latest_block_details.height;
+    let block_details = if settings.block_id != latest_block_id
         && settings.block_id != latest_block_details.prev_block_id
     {
-        return Err(anyhow!(
-            "Invalid block_id. Must reference latest or second latest block"
-        ));
-    }
-    let block_details = ctx.get_block_details(&settings.block_id).await.unwrap();
+        errors.push("Invalid block_id. Must reference latest or second latest block".to_string());
+        latest_block_details
+    } else {
+        ctx.get_block_details(&settings.block_id).await.unwrap()
+    };
 
-    // verify challenge is active
+    // verify challenge is active and not retired
     if !ctx
         .get_challenge_state(&settings.challenge_id)
         .await
         .is_some_and(|s| s.round_active <= block_details.round)
+        || config
+            .challenges
+            .get(&settings.challenge_id)
+            .is_some_and(|c| c.is_retired_at(block_details.round))
     {
-        return Err(anyhow!("Invalid challenge '{}'", settings.challenge_id));
+        errors.push(format!("Invalid challenge '{}'", settings.challenge_id));
     }
 
     // verify algorithm is active
@@ -45,31 +79,130 @@ pub async fn submit_precommit<T: Context>(
         .await
         .is_some_and(|s| !s.banned && s.round_active.is_some_and(|r| r <= block_details.round))
     {
-        return Err(anyhow!("Invalid algorithm '{}'", settings.algorithm_id));
+        errors.push(format!("Invalid algorithm '{}'", settings.algorithm_id));
     }
 
+    // verify player has room for another outstanding precommit
+    if let Some(limits) = &config.submission_limits {
+        // precommits started before this have expired
+        let min_block_started = (latest_height + 1).saturating_sub(limits.precommit_expiry_period);
+        let num_outstanding = ctx
+            .get_pending_precommit_ids(&player_id, min_block_started)
+            .await
+            .len();
+        if num_outstanding >= limits.max_outstanding_precommits {
+            errors.push(format!(
+                "Too many outstanding precommits. Submit their benchmarks or wait for them to expire (max {})",
+                limits.max_outstanding_precommits
+            ));
+        }
+    }
+
+    let challenge_config = match config.challenges.get(&settings.challenge_id) {
+        Some(c) => c,
+        None => {
+            return PrecommitQuote {
+                tracks: Vec::new(),
+                errors,
+            }
+        }
+    };
+
     // verify size
-    let challenge_config = &config.challenges[&settings.challenge_id];
-    if challenge_config.active_tracks.len() != track_settings.len()
-        || !track_settings
-            .keys()
-            .all(|k| challenge_config.active_tracks.contains_key(k))
+    let active_tracks = challenge_config.active_tracks_at(block_details.round);
+    if active_tracks.len() != track_settings.len()
+        || !track_settings.keys().all(|k| active_tracks.contains_key(k))
     {
-        return Err(anyhow!(
+        errors.push(format!(
             "Must submit settings for all active tracks: {:?}",
-            challenge_config.active_tracks.keys().collect::<Vec<_>>(),
+            active_tracks.keys().collect::<Vec<_>>(),
         ));
     }
 
+    // each active track is equally likely to be selected
+    let available_fee_balance = ctx
+        .get_player_state(&player_id)
+        .await
+        .map(|s| s.available_fee_balance);
+    let probability = 1.0 / active_tracks.len() as f64;
+    let mut tracks = Vec::new();
+    for (track_id, track_config) in active_tracks.iter() {
+        let TrackSettings {
+            fuel_budget,
+            num_bundles,
+            ..
+        } = match track_settings.get(track_id) {
+            Some(s) => s,
+            None => continue,
+        };
+
+        let mut track_errors = Vec::new();
+        if *num_bundles < challenge_config.min_num_bundles {
+            track_errors.push(format!(
+                "Invalid num_bundles '{}'. Must be at least {}",
+                num_bundles, challenge_config.min_num_bundles,
+            ));
+        }
+
+        if *fuel_budget > challenge_config.max_fuel_budget {
+            track_errors.push(format!(
+                "Invalid fuel_budget '{}'. Must be <= {}",
+                fuel_budget, challenge_config.max_fuel_budget
+            ));
+        }
+
+        // verify player has sufficient balance
+        let submission_fee = challenge_config.base_fee
+            + challenge_config.per_nonce_fee * PreciseNumber::from(*num_bundles);
+        if available_fee_balance.is_none_or(|b| b < submission_fee) {
+            track_errors.push("Insufficient balance".to_string());
+        }
+
+        tracks.push(TrackQuote {
+            track_id: track_id.clone(),
+            probability,
+            num_nonces: num_bundles * track_config.num_nonces_per_bundle,
+            submission_fee,
+            errors: track_errors,
+        });
+    }
+    tracks.sort_by(|a, b| a.track_id.cmp(&b.track_id));
+    PrecommitQuote { tracks, errors }
+}
+
+#[time]
+pub async fn submit_precommit<T: Context>(
+    ctx: &T,
+    player_id: String,
+    mut settings: BenchmarkSettings,
+    mut track_settings: HashMap<String, TrackSettings>,
+    seed: u64,
+) -> Result<String> {
+    let quote = quote_precommit(ctx, player_id, settings.clone(), track_settings.clone()).await;
+    if let Some(e) = quote.errors.first() {
+        return Err(anyhow!("{}", e));
+    }
+
+    let config = ctx.get_config().await;
+    let block_details = ctx.get_block_details(&settings.block_id).await.unwrap();
+    let challenge_config = &config.challenges[&settings.challenge_id];
+
     // randomly select a track
     let mut rng = StdRng::seed_from_u64(seed);
     settings.track_id = challenge_config
-        .active_tracks
+        .active_tracks_at(block_details.round)
         .keys()
         .choose(&mut rng)
         .unwrap()
         .clone();
-    let track_config = &challenge_config.active_tracks[&settings.track_id];
+    let track_quote = quote
+        .tracks
+        .into_iter()
+        .find(|t| t.track_id == settings.track_id)
+        .unwrap();
+    if let Some(e) = track_quote.errors.first() {
+        return Err(anyhow!("{}", e));
+    }
 
     let TrackSettings {
         hyperparameters,
@@ -77,42 +210,15 @@ pub async fn submit_precommit<T: Context>(
         num_bundles,
     } = track_settings.remove(&settings.track_id).unwrap();
 
-    if num_bundles < challenge_config.min_num_bundles {
-        return Err(anyhow!(
-            "Invalid num_bundles '{}'. Must be at least {}",
-            num_bundles,
-            challenge_config.min_num_bundles,
-        ));
-    }
-
-    if fuel_budget > challenge_config.max_fuel_budget {
-        return Err(anyhow!(
-            "Invalid fuel_budget '{}'. Must be <= {}",
-            fuel_budget,
-            challenge_config.max_fuel_budget
-        ));
-    }
-
-    // verify player has sufficient balance
-    let submission_fee = challenge_config.base_fee
-        + challenge_config.per_nonce_fee * PreciseNumber::from(num_bundles);
-    if !ctx
-        .get_player_state(&player_id)
-        .await
-        .is_some_and(|s| s.available_fee_balance >= submission_fee)
-    {
-        return Err(anyhow!("Insufficient balance"));
-    }
-
     let benchmark_id = ctx
         .add_precommit_to_mempool(
             settings,
             PrecommitDetails {
                 block_started: block_details.height,
-                num_nonces: num_bundles * track_config.num_nonces_per_bundle,
+                num_nonces: track_quote.num_nonces,
                 num_bundles,
                 rand_hash: hex::encode(rng.r#gen::<[u8; 16]>()),
-                fee_paid: submission_fee,
+                fee_paid: track_quote.submission_fee,
                 hyperparameters,
                 fuel_budget,
             },
@@ -149,6 +255,17 @@ pub async fn submit_benchmark<T: Context>(
         ));
     }
 
+    // check precommit has not expired
+    let config = ctx.get_config().await;
+    let precommit_details = ctx.get_precommit_details(&benchmark_id).await.unwrap();
+    let latest_block_id = ctx.get_latest_block_id().await;
+    let latest_block_details = ctx.get_block_details(&latest_block_id).await.unwrap();
+    if config.submission_limits.as_ref().is_some_and(|l| {
+        latest_block_details.height >= precommit_details.block_started + l.precommit_expiry_period
+    }) {
+        return Err(anyhow!("Precommit has expired: {}", benchmark_id));
+    }
+
     if stopped {
         ctx.add_benchmark_to_mempool(
             benchmark_id,
@@ -178,7 +295,7 @@ pub async fn submit_benchmark<T: Context>(
         num_nonces,
         num_bundles,
         ..
-    } = ctx.get_precommit_details(&benchmark_id).await.unwrap();
+    } = precommit_details;
     let num_nonces = num_nonces as usize;
     let num_bundles = num_bundles as usize;
     if solution_quality.len() != num_nonces {
@@ -188,9 +305,106 @@ pub async fn submit_benchmark<T: Context>(
         ));
     }
 
-    // random sample nonces
-    let challenge_config = &ctx.get_config().await.challenges[&settings.challenge_id];
-    let track_config = &challenge_config.active_tracks[&settings.track_id];
+    // bundles on a track removed since the precommit would never qualify
+    let challenge_config = &config.challenges[&settings.challenge_id];
+    if !challenge_config
+        .active_tracks_at(latest_block_details.round)
+        .contains_key(&settings.track_id)
+    {
+        return Err(anyhow!(
+            "Track '{}' is no longer active. Submit the benchmark as stopped",
+            settings.track_id
+        ));
+    }
+
+    // random sample nonces, using the track config of the precommit's round
+    let round = ctx
+        .get_block_details(&settings.block_id)
+        .await
+        .unwrap()
+        .round;
+    let sample = sample_benchmark(
+        challenge_config,
+        &settings.track_id,
+        round,
+        num_bundles,
+        &solution_quality,
+        seed,
+    )?;
+    let num_active_bundles = sample.num_active_bundles();
+
+    ctx.add_benchmark_to_mempool(
+        benchmark_id,
+        BenchmarkDetails {
+            stopped: num_active_bundles == 0,
+            num_active_bundles,
+            average_quality_by_bundle: (num_active_bundles > 0)
+                .then(|| sample.average_quality_by_bundle()),
+            merkle_root: Some(merkle_root),
+            sampled_nonces: (num_active_bundles > 0).then_some(sample.sampled_nonces),
+        },
+        Some(solution_quality),
+    )
+    .await?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BundleSample {
+    /// nonces of the bundle, sorted by quality
+    pub nonces: Vec<u64>,
+    /// median quality of the bundle for continuous challenges, mean quality for binary challenges
+    pub average_quality: i32,
+    /// whether the average quality meets the track's `min_active_quality`
+    pub active: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BenchmarkSample {
+    pub bundles: Vec<BundleSample>,
+    /// nonces whose merkle proofs must be submitted to prove the benchmark
+    pub sampled_nonces: HashSet<u64>,
+}
+
+impl BenchmarkSample {
+    pub fn num_active_bundles(&self) -> u64 {
+        self.bundles.iter().filter(|b| b.active).count() as u64
+    }
+
+    /// average quality of each active bundle, as recorded in `BenchmarkDetails`
+    pub fn average_quality_by_bundle(&self) -> Vec<i32> {
+        self.bundles
+            .iter()
+            .filter(|b| b.active)
+            .map(|b| b.average_quality)
+            .collect()
+    }
+}
+
+/// Shuffles nonces into bundles, averages their quality and samples the nonces to be proven,
+/// exactly as `submit_benchmark` does for the same `seed`. Benchmarkers can use it to preview which
+/// bundles will be active and how many proofs they will need to submit. `round` is the round of
+/// the precommit's block, which selects the track config
+pub fn sample_benchmark(
+    challenge_config: &ChallengeConfig,
+    track_id: &String,
+    round: u32,
+    num_bundles: usize,
+    solution_quality: &[i32],
+    seed: u64,
+) -> Result<BenchmarkSample> {
+    let track_config = challenge_config
+        .active_tracks_at(round)
+        .get(track_id)
+        .ok_or_else(|| anyhow!("Invalid track '{}'", track_id))?;
+    let num_nonces = solution_quality.len();
+    if num_bundles == 0 || num_nonces == 0 || !num_nonces.is_multiple_of(num_bundles) {
+        return Err(anyhow!(
+            "Invalid number of nonces {}. Must be a non-zero multiple of the number of bundles {}",
+            num_nonces,
+            num_bundles
+        ));
+    }
     let num_nonces_per_bundle = num_nonces / num_bundles;
     let mut rng = StdRng::seed_from_u64(seed);
     let mut nonce_quality = solution_quality
@@ -206,7 +420,7 @@ pub async fn submit_benchmark<T: Context>(
             chunk.to_vec()
         })
         .collect::<Vec<_>>();
-    let active_bundles = bundles
+    let average_quality_by_bundle = bundles
         .iter()
         .map(|bundle| {
             (
@@ -230,71 +444,53 @@ pub async fn submit_benchmark<T: Context>(
                 bundle,
             )
         })
+        .collect::<Vec<_>>();
+    let active_bundles = average_quality_by_bundle
+        .iter()
         .filter(|(q, _)| *q >= track_config.min_active_quality)
         .collect::<Vec<_>>();
 
-    let mut sampled_nonces = HashSet::new();
-    if !active_bundles.is_empty() {
-        let mut gte_average_samples = HashSet::new();
-        for _ in 0..25 {
-            if gte_average_samples.len() == challenge_config.num_samples_gte_average {
-                break;
-            }
-            let bundle = active_bundles[rng.gen_range(0..active_bundles.len())].1;
-            let start = match challenge_config.quality_type {
-                QualityType::Continuous => num_nonces_per_bundle / 2,
-                QualityType::Binary => {
-                    if let Some(idx) = bundle.iter().position(|&(_, q)| q != 0) {
-                        idx
-                    } else {
-                        continue;
-                    }
-                }
-            };
-            let idx = rng.gen_range(start..num_nonces_per_bundle);
-            gte_average_samples.insert(bundle[idx].0 as u64);
-        }
-        sampled_nonces.extend(gte_average_samples);
-
-        let mut lt_average_samples = HashSet::new();
-        for _ in 0..25 {
-            if lt_average_samples.len() == challenge_config.num_samples_lt_average {
-                break;
-            }
-            let bundle = active_bundles[rng.gen_range(0..active_bundles.len())].1;
-            let end = match challenge_config.quality_type {
-                QualityType::Continuous => num_nonces_per_bundle / 2,
-                QualityType::Binary => {
-                    if let Some(idx) = bundle.iter().position(|&(_, q)| q != 0) {
-                        idx
-                    } else {
-                        continue;
-                    }
-                }
-            };
-            if end == 0 {
-                continue;
-            }
-            let idx = rng.gen_range(0..end);
-            lt_average_samples.insert(bundle[idx].0 as u64);
-        }
-        sampled_nonces.extend(lt_average_samples);
+    // nonces at or above the average quality of their bundle, and nonces below it. For binary
+    // challenges, bundles without any nonzero quality are not sampled
+    let mut gte_average_pool = Vec::new();
+    let mut lt_average_pool = Vec::new();
+    for (_, bundle) in active_bundles.iter() {
+        let split = match challenge_config.quality_type {
+            QualityType::Continuous => num_nonces_per_bundle / 2,
+            QualityType::Binary => match bundle.iter().position(|&(_, q)| q != 0) {
+                Some(idx) => idx,
+                None => continue,
+            },
+        };
+        lt_average_pool.extend(bundle[..split].iter().map(|&(nonce, _)| nonce as u64));
+        gte_average_pool.extend(bundle[split..].iter().map(|&(nonce, _)| nonce as u64));
     }
 
-    ctx.add_benchmark_to_mempool(
-        benchmark_id,
-        BenchmarkDetails {
-            stopped: active_bundles.is_empty(),
-            num_active_bundles: active_bundles.len() as u64,
-            average_quality_by_bundle: (!active_bundles.is_empty())
-                .then_some(active_bundles.iter().map(|(q, _)| *q).collect::<Vec<_>>()),
-            merkle_root: Some(merkle_root),
-            sampled_nonces: (!active_bundles.is_empty()).then_some(sampled_nonces),
-        },
-        Some(solution_quality),
-    )
-    .await?;
-    Ok(())
+    // sampled uniformly without replacement, so exactly min(num_samples, pool size) nonces are
+    // sampled from each pool
+    let mut sampled_nonces = HashSet::new();
+    sampled_nonces.extend(
+        gte_average_pool
+            .choose_multiple(&mut rng, challenge_config.num_samples_gte_average)
+            .cloned(),
+    );
+    sampled_nonces.extend(
+        lt_average_pool
+            .choose_multiple(&mut rng, challenge_config.num_samples_lt_average)
+            .cloned(),
+    );
+
+    Ok(BenchmarkSample {
+        bundles: average_quality_by_bundle
+            .iter()
+            .map(|(q, bundle)| BundleSample {
+                nonces: bundle.iter().map(|&(nonce, _)| nonce as u64).collect(),
+                average_quality: *q,
+                active: *q >= track_config.min_active_quality,
+            })
+            .collect(),
+        sampled_nonces,
+    })
 }
 
 #[time]
@@ -330,8 +526,19 @@ pub async fn submit_proof<T: Context>(
         ));
     }
 
-    // verify
+    // check the benchmark's lifespan has not ended, after which its proof is missing
     let precommit_details = ctx.get_precommit_details(&benchmark_id).await.unwrap();
+    let config = ctx.get_config().await;
+    if config.submission_limits.is_some() {
+        let lifespan_period = config.challenges[&settings.challenge_id].lifespan_period;
+        let latest_block_id = ctx.get_latest_block_id().await;
+        let latest_block_details = ctx.get_block_details(&latest_block_id).await.unwrap();
+        if latest_block_details.height >= precommit_details.block_started + lifespan_period {
+            return Err(anyhow!("Benchmark has expired: {}", benchmark_id));
+        }
+    }
+
+    // verify
     let proof_nonces: HashSet<u64> = merkle_proofs.iter().map(|p| p.leaf.nonce).collect();
     let sampled_nonces = benchmark_details.sampled_nonces.unwrap();
     let num_nonces = precommit_details.num_nonces;
@@ -341,34 +548,28 @@ pub async fn submit_proof<T: Context>(
         ));
     }
 
+    // check nonces were computed on an architecture with an approved binary
+    let binary_details = ctx
+        .get_binary_details(&settings.algorithm_id)
+        .await
+        .unwrap();
+    if let Some(merkle_proof) = merkle_proofs
+        .iter()
+        .find(|p| !binary_details.has_approved_binary(p.leaf.cpu_arch))
+    {
+        return Err(anyhow!(
+            "Invalid merkle proof for nonce {}. No approved {:?} binary",
+            merkle_proof.leaf.nonce,
+            merkle_proof.leaf.cpu_arch
+        ));
+    }
+
     // verify merkle_proofs
     let mut verification_result = Ok(());
-    let max_branch_len = (64 - (num_nonces - 1).leading_zeros()) as usize;
     let merkle_root = benchmark_details.merkle_root.unwrap();
     for merkle_proof in merkle_proofs.iter() {
-        if merkle_proof.branch.0.len() > max_branch_len
-            || merkle_proof
-                .branch
-                .0
-                .iter()
-                .any(|(d, _)| *d as usize > max_branch_len)
-        {
-            verification_result = Err(anyhow!(
-                "Invalid merkle proof for nonce {}. Branch too long",
-                merkle_proof.leaf.nonce
-            ));
-            break;
-        }
-        let output_meta_data = OutputMetaData::from(merkle_proof.leaf.clone());
-        let hash = MerkleHash::from(output_meta_data);
-        let result = merkle_proof
-            .branch
-            .calc_merkle_root(&hash, merkle_proof.leaf.nonce as usize);
-        if !result.is_ok_and(|actual_merkle_root| actual_merkle_root == merkle_root) {
-            verification_result = Err(anyhow!(
-                "Invalid merkle proof for nonce {}. Merkle root does not match",
-                merkle_proof.leaf.nonce
-            ));
+        verification_result = verify_merkle_proof(merkle_proof, &merkle_root, num_nonces);
+        if verification_result.is_err() {
             break;
         }
     }
@@ -381,3 +582,231 @@ pub async fn submit_proof<T: Context>(
         .await?;
     Ok(verification_result)
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SettingsPerformance {
+    pub algorithm_id: String,
+    pub track_id: String,
+    pub fuel_budget: u64,
+    /// blake3 hex digest of the hyperparameters with their keys sorted. None without
+    /// hyperparameters
+    pub hyperparameters_hash: Option<String>,
+    pub hyperparameters: Option<Map<String, Value>>,
+    pub num_benchmarks: u64,
+    /// number of active bundles across the benchmarks
+    pub num_bundles: u64,
+    /// mean of `BenchmarkDetails.average_quality_by_bundle` across the benchmarks
+    pub average_quality: f64,
+    pub best_quality: i32,
+}
+
+/// Aggregates the average quality of the active bundles of the benchmarks active at `block_id`
+/// by algorithm, track, fuel budget and hyperparameters, so benchmarkers can compare settings.
+/// Sorted by algorithm, track, fuel budget and hyperparameters hash
+pub async fn get_settings_performance<T: Context>(
+    ctx: &T,
+    block_id: String,
+) -> Result<Vec<SettingsPerformance>> {
+    let benchmark_ids = ctx
+        .get_active_ids(&block_id, ActiveType::Benchmark)
+        .await
+        .ok_or_else(|| anyhow!("Invalid block '{}'", block_id))?;
+
+    let mut performances = HashMap::<_, SettingsPerformance>::new();
+    let mut total_quality = HashMap::<_, i64>::new();
+    for benchmark_id in benchmark_ids.iter() {
+        let average_quality_by_bundle = match ctx.get_benchmark_details(benchmark_id).await {
+            Some(BenchmarkDetails {
+                average_quality_by_bundle: Some(q),
+                ..
+            }) if !q.is_empty() => q,
+            _ => continue,
+        };
+        let settings = ctx.get_precommit_settings(benchmark_id).await.unwrap();
+        let precommit_details = ctx.get_precommit_details(benchmark_id).await.unwrap();
+        let hyperparameters_hash = precommit_details
+            .hyperparameters
+            .as_ref()
+            .map(|h| blake3_hex(jsonify(&sort_keys(&Value::Object(h.clone()))).as_bytes()));
+        let key = (
+            settings.algorithm_id.clone(),
+            settings.track_id.clone(),
+            precommit_details.fuel_budget,
+            hyperparameters_hash.clone(),
+        );
+        let performance = performances
+            .entry(key.clone())
+            .or_insert_with(|| SettingsPerformance {
+                algorithm_id: settings.algorithm_id,
+                track_id: settings.track_id,
+                fuel_budget: precommit_details.fuel_budget,
+                hyperparameters_hash,
+                hyperparameters: precommit_details.hyperparameters,
+                num_benchmarks: 0,
+                num_bundles: 0,
+                average_quality: 0.0,
+                best_quality: i32::MIN,
+            });
+        performance.num_benchmarks += 1;
+        performance.num_bundles += average_quality_by_bundle.len() as u64;
+        performance.best_quality = average_quality_by_bundle
+            .iter()
+            .fold(performance.best_quality, |best, &q| best.max(q));
+        *total_quality.entry(key).or_default() += average_quality_by_bundle
+            .iter()
+            .map(|&q| q as i64)
+            .sum::<i64>();
+    }
+
+    let mut performances = performances
+        .into_iter()
+        .map(|(key, mut performance)| {
+            performance.average_quality =
+                total_quality[&key] as f64 / performance.num_bundles as f64;
+            (key, performance)
+        })
+        .collect::<Vec<_>>();
+    performances.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(performances.into_iter().map(|(_, p)| p).collect())
+}
+
+// json objects with their keys sorted, so equal hyperparameters hash the same
+fn sort_keys(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys = map.keys().collect::<Vec<_>>();
+            keys.sort();
+            Value::Object(
+                keys.into_iter()
+                    .map(|k| (k.clone(), sort_keys(&map[k])))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.iter().map(sort_keys).collect()),
+        _ => value.clone(),
+    }
+}
+
+// benchmarks are active from the block their proof becomes active until their precommit's
+// lifespan ends, unless they were stopped or a fraud was confirmed. Their players take part in
+// OPoW
+#[time]
+pub(crate) async fn select_active(cache: &mut AddBlockCache) {
+    let AddBlockCache {
+        config,
+        block_details,
+        block_data,
+        recent_benchmarks,
+        active_benchmarks,
+        ..
+    } = cache;
+
+    let height = block_details.height;
+    let active_challenge_ids = &block_data.active_ids[&ActiveType::Challenge];
+    let active_code_ids = &block_data.active_ids[&ActiveType::Code];
+    let mut active_benchmark_ids = recent_benchmarks
+        .iter()
+        .filter(|(_, benchmark)| {
+            let settings = &benchmark.settings;
+            let lifespan_period = config.challenges[&settings.challenge_id].lifespan_period;
+            !benchmark.details.stopped
+                && benchmark.fraud_state.is_none()
+                && benchmark
+                    .proof_details
+                    .as_ref()
+                    .is_some_and(|p| p.block_active <= height)
+                && height < benchmark.precommit_details.block_started + lifespan_period
+                && active_challenge_ids.contains(&settings.challenge_id)
+                && active_code_ids.contains(&settings.algorithm_id)
+        })
+        .map(|(benchmark_id, _)| benchmark_id.clone())
+        .collect::<Vec<String>>();
+    active_benchmark_ids.sort();
+
+    let mut active_opow_ids = HashSet::new();
+    for benchmark_id in active_benchmark_ids.iter() {
+        let benchmark = &recent_benchmarks[benchmark_id];
+        active_benchmarks.push((
+            benchmark.settings.clone(),
+            benchmark
+                .details
+                .average_quality_by_bundle
+                .clone()
+                .unwrap_or_default(),
+        ));
+        active_opow_ids.insert(benchmark.settings.player_id.clone());
+    }
+    block_data
+        .active_ids
+        .entry(ActiveType::OPoW)
+        .or_default()
+        .extend(active_opow_ids);
+    block_data
+        .active_ids
+        .entry(ActiveType::Benchmark)
+        .or_default()
+        .extend(active_benchmark_ids);
+}
+
+#[time]
+pub(crate) async fn update(cache: &mut AddBlockCache) {
+    let AddBlockCache {
+        config,
+        block_details,
+        recent_benchmarks,
+        active_players_state,
+        ..
+    } = cache;
+
+    // penalise players who did not prove their benchmark within its lifespan
+    let penalty = match &config.submission_limits {
+        Some(limits) => limits.missing_proof_penalty,
+        None => return,
+    };
+    for benchmark in recent_benchmarks.values() {
+        let settings = &benchmark.settings;
+        let lifespan_period = config.challenges[&settings.challenge_id].lifespan_period;
+        if benchmark.details.stopped
+            || benchmark.proof_details.is_some()
+            || block_details.height != benchmark.precommit_details.block_started + lifespan_period
+        {
+            continue;
+        }
+        let player_state = active_players_state.get_mut(&settings.player_id).unwrap();
+        let deducted = player_state.available_fee_balance.min(penalty);
+        player_state.available_fee_balance -= deducted;
+        player_state.total_fees_paid += deducted;
+    }
+}
+
+pub(crate) fn verify_merkle_proof(
+    merkle_proof: &MerkleProof,
+    merkle_root: &MerkleHash,
+    num_nonces: u64,
+) -> Result<()> {
+    let max_branch_len = (64 - (num_nonces - 1).leading_zeros()) as usize;
+    if merkle_proof.branch.0.len() > max_branch_len
+        || merkle_proof
+            .branch
+            .0
+            .iter()
+            .any(|(d, _)| *d as usize > max_branch_len)
+    {
+        return Err(anyhow!(
+            "Invalid merkle proof for nonce {}. Branch too long",
+            merkle_proof.leaf.nonce
+        ));
+    }
+    let output_meta_data = OutputMetaData::from(merkle_proof.leaf.clone());
+    let hash = MerkleHash::from(output_meta_data);
+    let result = merkle_proof
+        .branch
+        .calc_merkle_root(&hash, merkle_proof.leaf.nonce as usize);
+    if !result.is_ok_and(|actual_merkle_root| actual_merkle_root == *merkle_root) {
+        return Err(anyhow!(
+            "Invalid merkle proof for nonce {}. Merkle root does not match",
+            merkle_proof.leaf.nonce
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use crate::context::*;
+use logging_timer::time;
+use std::collections::HashSet;
+use tig_structs::core::*;
+
+// challenges are active until the round they retire in. Selects the active challenges among
+// those of the cache
+#[time]
+pub(crate) async fn select_active(cache: &mut AddBlockCache) {
+    let AddBlockCache {
+        config,
+        block_details,
+        block_data,
+        active_challenges_block_data,
+        ..
+    } = cache;
+
+    active_challenges_block_data.retain(|challenge_id, _| {
+        config
+            .challenges
+            .get(challenge_id)
+            .is_some_and(|c| !c.is_retired_at(block_details.round))
+    });
+    block_data.active_ids.insert(
+        ActiveType::Challenge,
+        active_challenges_block_data
+            .keys()
+            .cloned()
+            .collect::<HashSet<String>>(),
+    );
+}
@@ -0,0 +1,147 @@
+use super::benchmarks::verify_merkle_proof;
+use crate::context::*;
+use anyhow::{anyhow, Result};
+use logging_timer::time;
+use tig_challenges::{c001, c002, c003, c007};
+use tig_structs::core::*;
+
+/// Alleges fraud on a proven benchmark that has not expired, with an output it committed to. The
+/// protocol adjudicates the allegation by regenerating the nonce's instance and re-verifying the
+/// output, which is only possible for CPU challenges. A confirmed fraud excludes the benchmark and
+/// slashes `frauds.slash_fraction` of the player's fee balance, counted in its `total_fees_paid`
+#[time]
+pub async fn submit_fraud<T: Context>(
+    ctx: &T,
+    player_id: String,
+    benchmark_id: String,
+    merkle_proof: MerkleProof,
+) -> Result<String> {
+    if ctx.get_player_details(&player_id).await.is_none() {
+        return Err(anyhow!(
+            "Player '{}' is invalid or not registered",
+            player_id
+        ));
+    }
+
+    // check fraud is not duplicate
+    if ctx.get_fraud_state(&benchmark_id).await.is_some() {
+        return Err(anyhow!("Duplicate fraud: {}", benchmark_id));
+    }
+
+    // check benchmark is proven and not expired
+    if ctx.get_proof_state(&benchmark_id).await.is_none() {
+        return Err(anyhow!("Benchmark needs to be proven first."));
+    }
+    let settings = ctx.get_precommit_settings(&benchmark_id).await.unwrap();
+    let precommit_details = ctx.get_precommit_details(&benchmark_id).await.unwrap();
+    let config = ctx.get_config().await;
+    let latest_block_id = ctx.get_latest_block_id().await;
+    let latest_block_details = ctx.get_block_details(&latest_block_id).await.unwrap();
+    let lifespan_period = config.challenges[&settings.challenge_id].lifespan_period;
+    if latest_block_details.height >= precommit_details.block_started + lifespan_period {
+        return Err(anyhow!("Benchmark has expired: {}", benchmark_id));
+    }
+
+    // evidence must be an output committed to by the benchmark
+    let nonce = merkle_proof.leaf.nonce;
+    if nonce >= precommit_details.num_nonces {
+        return Err(anyhow!("Invalid nonce {}", nonce));
+    }
+    let benchmark_details = ctx.get_benchmark_details(&benchmark_id).await.unwrap();
+    verify_merkle_proof(
+        &merkle_proof,
+        benchmark_details.merkle_root.as_ref().unwrap(),
+        precommit_details.num_nonces,
+    )?;
+
+    // adjudicate by re-verifying the output
+    let solution_quality = ctx
+        .get_benchmark_solution_quality(&benchmark_id)
+        .await
+        .unwrap();
+    let allegation = match verify_solution(
+        &settings,
+        &precommit_details.rand_hash,
+        nonce,
+        &merkle_proof.leaf.solution,
+    )? {
+        Err(e) => format!("Invalid solution for nonce {}: {}", nonce, e),
+        Ok(quality) if quality != solution_quality[nonce as usize] => format!(
+            "Invalid quality for nonce {}. Submitted {}, verified {}",
+            nonce, solution_quality[nonce as usize], quality
+        ),
+        Ok(_) => return Err(anyhow!("No fraud found for nonce {}", nonce)),
+    };
+    let allegation = format!("{} (alleged by {})", allegation, player_id);
+    ctx.add_fraud_to_mempool(benchmark_id, allegation.clone())
+        .await?;
+    Ok(allegation)
+}
+
+// regenerates the instance of the nonce and evaluates the solution. Outer error if the solution
+// cannot be verified by the protocol, inner error if the solution is invalid. Otherwise returns
+// the solution's quality
+pub(crate) fn verify_solution(
+    settings: &BenchmarkSettings,
+    rand_hash: &String,
+    nonce: u64,
+    solution: &str,
+) -> Result<Result<i32>> {
+    let seed = settings.calc_seed(rand_hash, nonce);
+
+    macro_rules! dispatch_challenge {
+        ($c:ident) => {{
+            let track = serde_json::from_str::<$c::Track>(&format!(r#""{}""#, settings.track_id))
+                .map_err(|_| {
+                anyhow!(
+                    "Failed to parse track_id '{}' as {}::Track",
+                    settings.track_id,
+                    stringify!($c)
+                )
+            })?;
+            let challenge = $c::Challenge::generate_instance(&seed, &track)?;
+            Ok(match serde_json::from_str::<$c::Solution>(solution) {
+                Ok(solution) => challenge.evaluate_solution(&solution),
+                Err(_) => Err(anyhow!("Cannot convert to {}::Solution", stringify!($c))),
+            })
+        }};
+    }
+
+    match settings.challenge_id.as_str() {
+        "c001" => dispatch_challenge!(c001),
+        "c002" => dispatch_challenge!(c002),
+        "c003" => dispatch_challenge!(c003),
+        "c007" => dispatch_challenge!(c007),
+        _ => Err(anyhow!(
+            "Solutions of challenge '{}' cannot be verified by the protocol",
+            settings.challenge_id
+        )),
+    }
+}
+
+#[time]
+pub(crate) async fn update(cache: &mut AddBlockCache) {
+    let AddBlockCache {
+        config,
+        block_data,
+        recent_benchmarks,
+        active_players_state,
+        ..
+    } = cache;
+
+    // slash the fee balance of players with a fraud confirmed in this block
+    let slash_fraction = match &config.frauds {
+        Some(frauds_config) => PreciseNumber::from_f64(frauds_config.slash_fraction),
+        None => return,
+    };
+    for benchmark_id in block_data.confirmed_ids[&TxType::Fraud].iter() {
+        let settings = match recent_benchmarks.get(benchmark_id) {
+            Some(benchmark) => &benchmark.settings,
+            None => continue,
+        };
+        let player_state = active_players_state.get_mut(&settings.player_id).unwrap();
+        let slashed = player_state.available_fee_balance * slash_fraction;
+        player_state.available_fee_balance -= slashed;
+        player_state.total_fees_paid += slashed;
+    }
+}
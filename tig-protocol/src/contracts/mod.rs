@@ -1,5 +1,8 @@
 pub mod algorithms;
+pub mod bans;
 pub mod benchmarks;
+pub mod challenges;
+pub mod frauds;
 pub mod opow;
 pub mod players;
 pub mod rewards;
@@ -1,7 +1,7 @@
 use crate::context::*;
 use logging_timer::time;
-use rand::seq::SliceRandom;
-use std::collections::{HashMap, HashSet};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use tig_structs::core::*;
 use tig_utils::*;
 
@@ -21,6 +21,7 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
         active_players_state,
         active_players_block_data,
         active_opow_block_data,
+        events,
         ..
     } = cache;
 
@@ -29,6 +30,12 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
     let active_player_ids = &block_data.active_ids[&ActiveType::Player];
     let active_opow_ids = &block_data.active_ids[&ActiveType::OPoW];
 
+    // the previous block's data is replaced by that of the players taking part in OPoW
+    let prev_opow_block_data = std::mem::take(active_opow_block_data);
+    for player_id in active_opow_ids.iter() {
+        active_opow_block_data.insert(player_id.clone(), new_opow_block_data());
+    }
+
     // update cutoffs
     let mut phase_in_challenge_ids: HashSet<String> = active_challenge_ids.clone();
     for algorithm_id in active_code_ids.iter() {
@@ -44,6 +51,25 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
     let phase_in_period = config.opow.cutoff_phase_in_period;
     let phase_in_end = phase_in_start + phase_in_period;
 
+    // weight out of `phase_in_period` of challenges being phased in, or phased out over the
+    // period before they retire. Other challenges have full weight
+    let mut phase_weight_by_challenge = HashMap::<String, u32>::new();
+    for challenge_id in active_challenge_ids.iter() {
+        let mut weight = phase_in_period;
+        if phase_in_challenge_ids.contains(challenge_id) && block_details.height < phase_in_end {
+            weight = block_details.height - phase_in_start;
+        }
+        if let Some(round_retired) = config.challenges[challenge_id].round_retired {
+            let phase_out_end = round_retired.saturating_sub(1) * config.rounds.blocks_per_round;
+            if block_details.height + phase_in_period > phase_out_end {
+                weight = weight.min(phase_out_end - block_details.height);
+            }
+        }
+        if weight < phase_in_period {
+            phase_weight_by_challenge.insert(challenge_id.clone(), weight);
+        }
+    }
+
     let mut num_bundles_by_player_by_challenge = HashMap::<String, HashMap<String, u64>>::new();
     for (settings, average_quality_by_bundle) in active_benchmarks.iter() {
         *num_bundles_by_player_by_challenge
@@ -65,10 +91,10 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
             .min()
             .unwrap_or_default();
         let mut cutoff = (min_num_bundles as f64 * config.opow.cutoff_multiplier).ceil() as u64;
-        if phase_in_challenge_ids.len() > 0 && phase_in_end > block_details.height {
+        if let Some(&phase_weight) = phase_weight_by_challenge.values().min() {
             let phase_in_min_num_groups = active_challenge_ids
                 .iter()
-                .filter(|&id| !phase_in_challenge_ids.contains(id))
+                .filter(|&id| !phase_weight_by_challenge.contains_key(id))
                 .map(|id| {
                     player_bundles_by_challenge
                         .get(id)
@@ -79,8 +105,7 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
                 .unwrap_or_default();
             let phase_in_cutoff =
                 (phase_in_min_num_groups as f64 * config.opow.cutoff_multiplier).ceil() as u64;
-            let phase_in_weight =
-                (phase_in_end - block_details.height) as f64 / phase_in_period as f64;
+            let phase_in_weight = (phase_in_period - phase_weight) as f64 / phase_in_period as f64;
             cutoff = (phase_in_cutoff as f64 * phase_in_weight
                 + cutoff as f64 * (1.0 - phase_in_weight)) as u64;
         }
@@ -92,7 +117,7 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
         HashMap::<String, HashMap<String, Vec<(&BenchmarkSettings, i32)>>>::new();
     for (settings, average_quality_by_bundle) in active_benchmarks.iter() {
         if config.challenges[&settings.challenge_id]
-            .active_tracks
+            .active_tracks_at(block_details.round)
             .contains_key(&settings.track_id)
         {
             bundles_by_challenge_by_track
@@ -118,11 +143,18 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
         })
         .collect::<HashMap<String, u64>>();
 
-    let mut rng = rand::thread_rng();
     for challenge_id in active_challenge_ids.iter() {
+        // seeded from the parent block so that every node breaks ties identically
+        let mut rng = StdRng::from_seed(u8s_from_str(&format!(
+            "{}_{}",
+            block_details.prev_block_id, challenge_id
+        )));
         let challenge_config = &config.challenges[challenge_id];
         let challenge_data = active_challenges_block_data.get_mut(challenge_id).unwrap();
-        for track_id in challenge_config.active_tracks.keys() {
+        for track_id in challenge_config
+            .active_tracks_at(block_details.round)
+            .keys()
+        {
             challenge_data
                 .qualifier_qualities_by_track
                 .insert(track_id.clone(), HashSet::new());
@@ -135,7 +167,18 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
             let bundles_by_track = bundles_by_track
                 .into_iter()
                 .filter(|(_, x)| !x.is_empty())
+                .collect::<BTreeMap<_, _>>()
+                .into_iter()
                 .map(|(track_id, mut x)| {
+                    // canonical order before shuffling, as benchmarks come in no particular order
+                    x.sort_by(|(a, a_quality), (b, b_quality)| {
+                        (&a.player_id, &a.algorithm_id, &a.block_id, a_quality).cmp(&(
+                            &b.player_id,
+                            &b.algorithm_id,
+                            &b.block_id,
+                            b_quality,
+                        ))
+                    });
                     // shuffle to break ties randomly
                     x.shuffle(&mut rng);
                     x.sort_by_key(|&(_, quality)| -quality);
@@ -149,13 +192,14 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
             let mut num_potential_qualifiers_by_player_by_track =
                 HashMap::<String, HashMap<String, u64>>::new();
             let mut max_qualifiers_by_player = max_qualifiers_by_player.clone();
-            let mut qualifier_age_by_track = HashMap::<String, Vec<(u32, String)>>::new();
+            let mut qualifier_age_by_track = BTreeMap::<String, Vec<(u32, String)>>::new();
 
             let mut track_rank = bundles_by_track
                 .keys()
                 .map(|k| (k.clone(), 0))
                 .collect::<HashMap<_, _>>();
             let mut track_ids = bundles_by_track.keys().cloned().collect::<Vec<_>>();
+            track_ids.sort();
             while !track_ids.is_empty() {
                 track_ids.shuffle(&mut rng);
                 track_ids.retain(|track_id| {
@@ -278,6 +322,64 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
         }
     }
 
+    // events sorted by player, then challenge and track. Players new to OPoW, or that left it,
+    // are diffed against empty data
+    let empty_opow_data = new_opow_block_data();
+    let mut player_ids = active_opow_block_data
+        .keys()
+        .chain(prev_opow_block_data.keys())
+        .collect::<Vec<_>>();
+    player_ids.sort();
+    player_ids.dedup();
+    for player_id in player_ids {
+        let opow_data = active_opow_block_data
+            .get(player_id)
+            .unwrap_or(&empty_opow_data);
+        let prev_opow_data = prev_opow_block_data
+            .get(player_id)
+            .unwrap_or(&empty_opow_data);
+        let prev_cutoff = prev_opow_data.cutoff;
+        if opow_data.cutoff != prev_cutoff {
+            events.push(BlockEvent::CutoffChanged {
+                player_id: player_id.clone(),
+                prev_cutoff,
+                cutoff: opow_data.cutoff,
+            });
+        }
+        let num_qualifiers = |data: &OPoWBlockData, challenge_id: &String, track_id: &String| {
+            data.num_qualifiers_by_challenge_by_track
+                .get(challenge_id)
+                .and_then(|x| x.get(track_id))
+                .copied()
+                .unwrap_or_default()
+        };
+        let mut qualified = opow_data
+            .num_qualifiers_by_challenge_by_track
+            .iter()
+            .chain(prev_opow_data.num_qualifiers_by_challenge_by_track.iter())
+            .flat_map(|(challenge_id, num_qualifiers_by_track)| {
+                num_qualifiers_by_track
+                    .keys()
+                    .map(move |track_id| (challenge_id, track_id))
+            })
+            .filter(|&(challenge_id, track_id)| {
+                num_qualifiers(opow_data, challenge_id, track_id)
+                    != num_qualifiers(prev_opow_data, challenge_id, track_id)
+            })
+            .collect::<Vec<_>>();
+        qualified.sort();
+        qualified.dedup();
+        for (challenge_id, track_id) in qualified {
+            let num_bundles = num_qualifiers(opow_data, challenge_id, track_id);
+            events.push(BlockEvent::PlayerQualified {
+                player_id: player_id.clone(),
+                challenge_id: challenge_id.clone(),
+                track_id: track_id.clone(),
+                num_bundles,
+            });
+        }
+    }
+
     // update influence
     if active_opow_ids.len() == 0 {
         return;
@@ -286,7 +388,7 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
     for player_id in active_player_ids.iter() {
         let player_data = active_players_block_data.get_mut(player_id).unwrap();
         let player_state = &active_players_state[player_id];
-        if let Some(delegatees) = &player_state.delegatees {
+        if let Some(delegatees) = player_state.delegatees.value_at(block_details.height) {
             player_data.delegatees = delegatees
                 .value
                 .iter()
@@ -328,15 +430,12 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
     let imbalance_multiplier = PreciseNumber::from_f64(config.opow.imbalance_multiplier);
     let mut factor_weights = active_challenge_ids
         .iter()
-        .map(|challenge_id| {
-            if phase_in_challenge_ids.contains(challenge_id) && block_details.height < phase_in_end
-            {
-                PreciseNumber::from(block_details.height - phase_in_start)
-                    / PreciseNumber::from(config.opow.cutoff_phase_in_period)
-            } else {
-                one.clone()
-            }
-        })
+        .map(
+            |challenge_id| match phase_weight_by_challenge.get(challenge_id) {
+                Some(&weight) => PreciseNumber::from(weight) / PreciseNumber::from(phase_in_period),
+                None => one.clone(),
+            },
+        )
         .collect::<Vec<_>>()
         .normalise()
         .into_iter()
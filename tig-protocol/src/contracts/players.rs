@@ -1,14 +1,17 @@
 use crate::context::*;
 use anyhow::{anyhow, Result};
 use logging_timer::time;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use tig_structs::core::*;
 
+/// Schedules the split of the player's rewards from `block_effective`, by default the next block
 #[time]
 pub async fn set_coinbase<T: Context>(
     ctx: &T,
     player_id: String,
     coinbase: HashMap<String, f64>,
+    block_effective: Option<u32>,
 ) -> Result<()> {
     let config = ctx.get_config().await;
     let latest_block_id = ctx.get_latest_block_id().await;
@@ -21,18 +24,13 @@ pub async fn set_coinbase<T: Context>(
             config.opow.max_coinbase_outputs
         ));
     }
-    if let Some(curr_coinbase) = &player_state.coinbase {
-        if latest_block_details.height - curr_coinbase.block_set
-            < config.opow.coinbase_update_period
-        {
-            return Err(anyhow!(
-                "Can only update coinbase every {} blocks. Please wait {} blocks",
-                config.opow.coinbase_update_period,
-                config.opow.coinbase_update_period
-                    - (latest_block_details.height - curr_coinbase.block_set)
-            ));
-        }
-    }
+    let block_effective = verify_block_effective(
+        "coinbase",
+        &player_state.coinbase,
+        config.opow.coinbase_update_period,
+        latest_block_details.height,
+        block_effective,
+    )?;
 
     if coinbase.values().any(|&v| v <= 0.0 || v > 1.0) {
         return Err(anyhow!(
@@ -50,15 +48,19 @@ pub async fn set_coinbase<T: Context>(
         }
     }
 
-    ctx.set_player_coinbase(player_id, coinbase).await?;
+    ctx.set_player_coinbase(player_id, coinbase, block_effective)
+        .await?;
     Ok(())
 }
 
+/// Schedules the player's delegatees from `block_effective`, by default the next block. OPoW
+/// weighs the player's deposit by the delegatees in effect at each block
 #[time]
 pub async fn set_delegatees<T: Context>(
     ctx: &T,
     player_id: String,
     delegatees: HashMap<String, f64>,
+    block_effective: Option<u32>,
 ) -> Result<()> {
     let config = ctx.get_config().await;
     let latest_block_id = ctx.get_latest_block_id().await;
@@ -71,18 +73,13 @@ pub async fn set_delegatees<T: Context>(
             config.deposits.max_delegations
         ));
     }
-    if let Some(curr_delegatees) = &player_state.delegatees {
-        if latest_block_details.height - curr_delegatees.block_set
-            < config.deposits.delegatees_update_period
-        {
-            return Err(anyhow!(
-                "Can only update delegatees every {} blocks. Please wait {} blocks",
-                config.deposits.delegatees_update_period,
-                config.deposits.delegatees_update_period
-                    - (latest_block_details.height - curr_delegatees.block_set)
-            ));
-        }
-    }
+    let block_effective = verify_block_effective(
+        "delegatees",
+        &player_state.delegatees,
+        config.deposits.delegatees_update_period,
+        latest_block_details.height,
+        block_effective,
+    )?;
 
     if delegatees.keys().any(|k| k == &player_id) {
         return Err(anyhow!(
@@ -104,36 +101,39 @@ pub async fn set_delegatees<T: Context>(
         }
     }
 
-    ctx.set_player_delegatees(player_id, delegatees).await?;
+    ctx.set_player_delegatees(player_id, delegatees, block_effective)
+        .await?;
     Ok(())
 }
 
+/// Schedules the share of rewards the player passes on to its delegators from `block_effective`,
+/// by default the next block
 #[time]
 pub async fn set_reward_share<T: Context>(
     ctx: &T,
     player_id: String,
     reward_share: f64,
+    block_effective: Option<u32>,
 ) -> Result<()> {
     let config = ctx.get_config().await;
     let latest_block_id = ctx.get_latest_block_id().await;
     let latest_block_details = ctx.get_block_details(&latest_block_id).await.unwrap();
     let player_state = ctx.get_player_state(&player_id).await.unwrap();
 
-    if let Some(curr_reward_share) = &player_state.reward_share {
-        if curr_reward_share.value == reward_share {
-            return Err(anyhow!("Reward share is already set to {}", reward_share));
-        }
-        if latest_block_details.height - curr_reward_share.block_set
-            < config.deposits.reward_share_update_period
-        {
-            return Err(anyhow!(
-                "Can only update reward share every {} blocks. Please wait {} blocks",
-                config.deposits.reward_share_update_period,
-                config.deposits.reward_share_update_period
-                    - (latest_block_details.height - curr_reward_share.block_set)
-            ));
-        }
+    if player_state
+        .reward_share
+        .latest()
+        .is_some_and(|v| v.value == reward_share)
+    {
+        return Err(anyhow!("Reward share is already set to {}", reward_share));
     }
+    let block_effective = verify_block_effective(
+        "reward share",
+        &player_state.reward_share,
+        config.deposits.reward_share_update_period,
+        latest_block_details.height,
+        block_effective,
+    )?;
 
     if reward_share < 0.0 {
         return Err(anyhow!("Reward share cannot be negative"));
@@ -146,7 +146,8 @@ pub async fn set_reward_share<T: Context>(
         ));
     }
 
-    ctx.set_player_reward_share(player_id, reward_share).await?;
+    ctx.set_player_reward_share(player_id, reward_share, block_effective)
+        .await?;
     Ok(())
 }
 
@@ -172,27 +173,41 @@ pub async fn set_vote<T: Context>(
         return Err(anyhow!("Cannot vote on advance '{}'", algorithm_id));
     }
 
-    if player_state.votes.contains_key(&algorithm_id) {
+    // votes can be changed until they are tallied
+    if player_state
+        .votes
+        .get(&algorithm_id)
+        .is_some_and(|v| v.value == yes)
+    {
         return Err(anyhow!(
-            "You have already voted on advance '{}'",
+            "You have already voted {} on advance '{}'",
+            if yes { "yes" } else { "no" },
             algorithm_id
         ));
     }
 
-    let player_data = ctx
-        .get_player_block_data(&player_id, &latest_block_id)
-        .await;
+    // either the player or one of their vote delegators must have deposit locked long enough
     let n = advance_state.round_votes_tallied - latest_block_details.round
         + config.advances.min_lock_period_to_vote;
     let zero = PreciseNumber::from(0);
-    if !player_data.is_some_and(|d| {
-        d.deposit_by_locked_period
-            .iter()
-            .skip(n as usize)
-            .any(|x| *x > zero)
-    }) {
+    let mut voter_ids = vec![player_id.clone()];
+    voter_ids.extend(ctx.get_vote_delegator_ids(&player_id).await);
+    let mut has_locked_deposit = false;
+    for id in voter_ids.iter() {
+        let player_data = ctx.get_player_block_data(id, &latest_block_id).await;
+        if player_data.is_some_and(|d| {
+            d.deposit_by_locked_period
+                .iter()
+                .skip(n as usize)
+                .any(|x| *x > zero)
+        }) {
+            has_locked_deposit = true;
+            break;
+        }
+    }
+    if !has_locked_deposit {
         return Err(anyhow!(
-            "You must have deposit still locked {} rounds from now to vote",
+            "You or your vote delegators must have deposit still locked {} rounds from now to vote",
             n
         ));
     }
@@ -201,6 +216,168 @@ pub async fn set_vote<T: Context>(
     Ok(())
 }
 
+/// Delegates the player's voting power on advances, separately from OPoW delegation. Advances the
+/// player votes on directly are not delegated. `None` removes the delegation
+#[time]
+pub async fn set_vote_delegatee<T: Context>(
+    ctx: &T,
+    player_id: String,
+    vote_delegatee: Option<String>,
+) -> Result<()> {
+    let player_state = ctx.get_player_state(&player_id).await.unwrap();
+    if player_state.vote_delegatee.as_ref().map(|d| &d.value) == vote_delegatee.as_ref() {
+        return Err(anyhow!(
+            "Vote delegatee is already set to {:?}",
+            vote_delegatee
+        ));
+    }
+    if let Some(delegatee) = &vote_delegatee {
+        if *delegatee == player_id {
+            return Err(anyhow!("Cannot delegate votes to yourself"));
+        }
+        if ctx.get_player_details(delegatee).await.is_none() {
+            return Err(anyhow!("Invalid vote delegatee '{}'", delegatee));
+        }
+    }
+
+    ctx.set_player_vote_delegatee(player_id, vote_delegatee)
+        .await?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VoteTally {
+    pub advance_id: String,
+    pub round_votes_tallied: u32,
+    pub yes: PreciseNumber,
+    pub no: PreciseNumber,
+    /// players that voted directly, sorted by player id
+    pub voters: Vec<VoterTally>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VoterTally {
+    pub player_id: String,
+    pub yes: bool,
+    pub own_votes: PreciseNumber,
+    pub delegated_votes: PreciseNumber,
+    /// vote delegators that did not vote directly, sorted by player id
+    pub delegators: Vec<String>,
+}
+
+/// Tallies the votes on an advance in a round. A player's votes are their deposit still locked
+/// when the votes are tallied. Delegation is a single hop: the votes of a delegator that did not
+/// vote go to their vote delegatee if the delegatee voted, and are not delegated any further
+pub fn tally_votes(
+    advance_id: &str,
+    advance_state: &AdvanceState,
+    round: u32,
+    active_player_ids: &HashSet<String>,
+    active_players_state: &HashMap<String, PlayerState>,
+    active_players_block_data: &HashMap<String, PlayerBlockData>,
+) -> VoteTally {
+    let n = advance_state.round_votes_tallied - round;
+    let votes_of = |player_id: &String| -> PreciseNumber {
+        active_players_block_data[player_id]
+            .deposit_by_locked_period
+            .iter()
+            .skip(n as usize)
+            .sum()
+    };
+
+    let mut voters = HashMap::<String, VoterTally>::new();
+    for player_id in active_player_ids.iter() {
+        if let Some(vote) = active_players_state[player_id].votes.get(advance_id) {
+            voters.insert(
+                player_id.clone(),
+                VoterTally {
+                    player_id: player_id.clone(),
+                    yes: vote.value,
+                    own_votes: votes_of(player_id),
+                    delegated_votes: PreciseNumber::from(0),
+                    delegators: Vec::new(),
+                },
+            );
+        }
+    }
+    for player_id in active_player_ids.iter() {
+        let player_state = &active_players_state[player_id];
+        if player_state.votes.contains_key(advance_id) {
+            continue;
+        }
+        if let Some(voter) = player_state
+            .vote_delegatee
+            .as_ref()
+            .and_then(|d| voters.get_mut(&d.value))
+        {
+            voter.delegated_votes += votes_of(player_id);
+            voter.delegators.push(player_id.clone());
+        }
+    }
+
+    let mut voters = voters.into_values().collect::<Vec<_>>();
+    voters.sort_by(|a, b| a.player_id.cmp(&b.player_id));
+    let (mut yes, mut no) = (PreciseNumber::from(0), PreciseNumber::from(0));
+    for voter in voters.iter_mut() {
+        voter.delegators.sort();
+        let votes = voter.own_votes + voter.delegated_votes;
+        if voter.yes {
+            yes += votes;
+        } else {
+            no += votes;
+        }
+    }
+    VoteTally {
+        advance_id: advance_id.to_string(),
+        round_votes_tallied: advance_state.round_votes_tallied,
+        yes,
+        no,
+        voters,
+    }
+}
+
+/// Live tally of the votes on an advance, using the votes as they are now and the deposits as of
+/// the latest block
+pub async fn get_vote_tally<T: Context>(ctx: &T, advance_id: String) -> Result<VoteTally> {
+    let latest_block_id = ctx.get_latest_block_id().await;
+    let latest_block_details = ctx.get_block_details(&latest_block_id).await.unwrap();
+    let advance_state = ctx
+        .get_advance_state(&advance_id)
+        .await
+        .ok_or_else(|| anyhow!("Invalid advance '{}'", advance_id))?;
+    let round = latest_block_details.round;
+    if round < advance_state.round_voting_starts || round >= advance_state.round_votes_tallied {
+        return Err(anyhow!("Advance '{}' is not being voted on", advance_id));
+    }
+
+    let active_player_ids = ctx
+        .get_active_ids(&latest_block_id, ActiveType::Player)
+        .await
+        .unwrap_or_default();
+    let mut active_players_state = HashMap::new();
+    let mut active_players_block_data = HashMap::new();
+    for player_id in active_player_ids.iter() {
+        active_players_state.insert(
+            player_id.clone(),
+            ctx.get_player_state(player_id).await.unwrap(),
+        );
+        active_players_block_data.insert(
+            player_id.clone(),
+            ctx.get_player_block_data(player_id, &latest_block_id)
+                .await
+                .unwrap(),
+        );
+    }
+    Ok(tally_votes(
+        &advance_id,
+        &advance_state,
+        round,
+        &active_player_ids,
+        &active_players_state,
+        &active_players_block_data,
+    ))
+}
+
 #[time]
 pub(crate) async fn update(cache: &mut AddBlockCache) {
     let AddBlockCache {
@@ -270,3 +447,48 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
         }
     }
 }
+
+// changes take effect from the next block unless scheduled for a later one, at most
+// `update_period` blocks ahead. The cooldown between changes is measured between their effective
+// blocks rather than the blocks they were set in, so a change must take effect at least
+// `update_period` blocks after the previously scheduled one
+fn verify_block_effective<T>(
+    name: &str,
+    schedule: &ValueSchedule<T>,
+    update_period: u32,
+    latest_height: u32,
+    block_effective: Option<u32>,
+) -> Result<u32> {
+    let update_period = update_period.max(1);
+    let overflow = || anyhow!("Invalid block_effective. Block height overflow");
+    let latest_allowed = latest_height
+        .checked_add(update_period)
+        .ok_or_else(overflow)?;
+    let block_effective = match block_effective {
+        Some(block_effective) => block_effective,
+        None => latest_height.checked_add(1).ok_or_else(overflow)?,
+    };
+    if block_effective <= latest_height || block_effective > latest_allowed {
+        return Err(anyhow!(
+            "Invalid block_effective {}. Must be after the latest block {} and at most {}",
+            block_effective,
+            latest_height,
+            latest_allowed
+        ));
+    }
+    if let Some(prev) = schedule.latest() {
+        let earliest = prev
+            .block_effective
+            .checked_add(update_period)
+            .ok_or_else(overflow)?;
+        if block_effective < earliest {
+            return Err(anyhow!(
+                "Can only update {} every {} blocks. Earliest block_effective is {}",
+                name,
+                update_period,
+                earliest
+            ));
+        }
+    }
+    Ok(block_effective)
+}
@@ -1,10 +1,11 @@
 use crate::context::*;
+use anyhow::{anyhow, Result};
 use logging_timer::time;
 use std::collections::HashMap;
 use tig_structs::core::*;
 
 #[time]
-pub(crate) async fn update(cache: &mut AddBlockCache) {
+pub(crate) async fn update(cache: &mut AddBlockCache) -> Result<()> {
     let AddBlockCache {
         config,
         block_details,
@@ -18,6 +19,7 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
         active_advances_state,
         active_advances_block_data,
         active_advances_details,
+        events,
         ..
     } = cache;
 
@@ -45,15 +47,17 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
             })
             .block_reward,
     );
-    let scaled_reward = block_reward * PreciseNumber::from_f64(block_details.gamma_value.clone());
+    let scaled_reward = block_reward
+        .checked_mul(&PreciseNumber::from_f64(block_details.gamma_value))
+        .ok_or_else(|| anyhow!("Scaled reward overflow"))?;
+    let mut payouts = Vec::<EmissionsPayout>::new();
 
     // update code rewards
     let adoption_threshold = PreciseNumber::from_f64(config.codes.adoption_threshold);
-    let codes_reward_pool =
-        scaled_reward * PreciseNumber::from_f64(config.rewards.distribution.codes);
+    let codes_reward_pool = pool_of("Codes", &scaled_reward, config.rewards.distribution.codes)?;
+    // there are no active codes once every challenge has retired
     let reward_pool_per_challenge =
-        codes_reward_pool / PreciseNumber::from(active_challenge_ids.len());
-    let mut total_codes_reward = zero.clone();
+        pool_per_challenge("Codes", &codes_reward_pool, active_challenge_ids.len())?;
     for algorithm_id in active_code_ids.iter() {
         let code_state = &active_codes_state[algorithm_id];
         let code_data = active_codes_block_data.get_mut(algorithm_id).unwrap();
@@ -71,22 +75,32 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
         if code_data.adoption >= adoption_threshold || (is_merged && code_data.adoption > zero) {
             let reward = reward_pool_per_challenge * code_data.adoption;
             code_data.reward = reward;
-            total_codes_reward += reward;
 
             *player_data
                 .reward_by_type
                 .entry(EmissionsType::Code)
                 .or_insert_with(|| zero.clone()) += code_data.reward;
+            payouts.push(EmissionsPayout {
+                r#type: EmissionsType::Code,
+                player_id: code_details.player_id.clone(),
+                source_id: algorithm_id.clone(),
+                amount: reward,
+            });
         }
     }
 
     // update advance rewards
     let adoption_threshold = PreciseNumber::from_f64(config.advances.adoption_threshold);
-    let advances_reward_pool =
-        scaled_reward * PreciseNumber::from_f64(config.rewards.distribution.advances);
-    let mut total_advances_reward = zero.clone();
-    let reward_pool_per_challenge =
-        advances_reward_pool / PreciseNumber::from(active_challenge_ids.len());
+    let advances_reward_pool = pool_of(
+        "Advances",
+        &scaled_reward,
+        config.rewards.distribution.advances,
+    )?;
+    let reward_pool_per_challenge = pool_per_challenge(
+        "Advances",
+        &advances_reward_pool,
+        active_challenge_ids.len(),
+    )?;
     for algorithm_id in active_advance_ids.iter() {
         let advance_state = &active_advances_state[algorithm_id];
         let advance_details = &active_advances_details[algorithm_id];
@@ -106,19 +120,22 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
         {
             let reward = reward_pool_per_challenge * advance_data.adoption;
             advance_data.reward = reward;
-            total_advances_reward += reward;
 
             *player_data
                 .reward_by_type
                 .entry(EmissionsType::Advance)
                 .or_insert_with(|| zero.clone()) += advance_data.reward;
+            payouts.push(EmissionsPayout {
+                r#type: EmissionsType::Advance,
+                player_id: advance_details.player_id.clone(),
+                source_id: algorithm_id.clone(),
+                amount: reward,
+            });
         }
     }
 
     // update benchmark rewards
-    let reward_pool = scaled_reward * PreciseNumber::from_f64(config.rewards.distribution.opow);
-    let mut total_benchmarkers_reward = zero.clone();
-    let mut total_delegators_reward = zero.clone();
+    let reward_pool = pool_of("OPoW", &scaled_reward, config.rewards.distribution.opow)?;
     for (delegatee, opow_data) in active_opow_block_data.iter_mut() {
         opow_data.reward = opow_data.influence * reward_pool;
 
@@ -131,7 +148,7 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
                 * PreciseNumber::from_f64(
                     active_players_state[delegatee]
                         .reward_share
-                        .as_ref()
+                        .value_at(block_details.height)
                         .map_or(config.deposits.default_reward_share, |x| x.value)
                         .clone(),
                 )
@@ -140,7 +157,7 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
 
         for (output, fraction) in active_players_state[delegatee]
             .coinbase
-            .as_ref()
+            .value_at(block_details.height)
             .map_or_else(
                 || HashMap::from([(delegatee.clone(), 1.0)]),
                 |x| x.value.clone(),
@@ -156,7 +173,12 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
                 .reward_by_type
                 .entry(EmissionsType::Benchmarker)
                 .or_insert_with(|| zero.clone()) += reward;
-            total_benchmarkers_reward += reward;
+            payouts.push(EmissionsPayout {
+                r#type: EmissionsType::Benchmarker,
+                player_id: output.clone(),
+                source_id: delegatee.clone(),
+                amount: reward,
+            });
         }
 
         if opow_data.reward_share == zero {
@@ -172,39 +194,186 @@ pub(crate) async fn update(cache: &mut AddBlockCache) {
                 .reward_by_type
                 .entry(EmissionsType::Delegator)
                 .or_insert_with(|| zero.clone()) += reward;
-            total_delegators_reward += reward;
+            payouts.push(EmissionsPayout {
+                r#type: EmissionsType::Delegator,
+                player_id: delegator.clone(),
+                source_id: delegatee.clone(),
+                amount: reward,
+            });
         }
     }
 
-    let challenge_owners_reward_pool =
-        scaled_reward * PreciseNumber::from_f64(config.rewards.distribution.challenge_owners);
+    let challenge_owners_reward_pool = pool_of(
+        "Challenge owners",
+        &scaled_reward,
+        config.rewards.distribution.challenge_owners,
+    )?;
 
-    block_details.emissions.insert(
-        EmissionsType::Bootstrap,
-        (advances_reward_pool - total_advances_reward) + (codes_reward_pool - total_codes_reward),
-    );
-    block_details.emissions.insert(
-        EmissionsType::Vault,
-        block_reward
-            - codes_reward_pool
-            - advances_reward_pool
-            - total_benchmarkers_reward
-            - total_delegators_reward
-            - challenge_owners_reward_pool,
-    );
-    block_details
-        .emissions
-        .insert(EmissionsType::Code, total_codes_reward);
-    block_details
-        .emissions
-        .insert(EmissionsType::Advance, total_advances_reward);
-    block_details
-        .emissions
-        .insert(EmissionsType::Benchmarker, total_benchmarkers_reward);
-    block_details
-        .emissions
-        .insert(EmissionsType::Delegator, total_delegators_reward);
-    block_details
-        .emissions
-        .insert(EmissionsType::ChallengeOwner, challenge_owners_reward_pool);
+    let audit = audit_emissions(
+        block_reward,
+        scaled_reward,
+        codes_reward_pool,
+        advances_reward_pool,
+        reward_pool,
+        challenge_owners_reward_pool,
+        payouts,
+    )
+    .map_err(|e| anyhow!("audit_emissions error: {}", e))?;
+    events.extend(audit.payouts.iter().cloned().map(BlockEvent::RewardPaid));
+    block_details.emissions = emissions_from_audit(&audit);
+    block_data.emissions_audit = Some(audit);
+    Ok(())
+}
+
+// fraction of the scaled reward, failing instead of overflowing
+fn pool_of(name: &str, scaled_reward: &PreciseNumber, fraction: f64) -> Result<PreciseNumber> {
+    scaled_reward
+        .checked_mul(&PreciseNumber::from_f64(fraction))
+        .ok_or_else(|| anyhow!("{} reward pool overflow", name))
+}
+
+// shared equally by the active challenges
+fn pool_per_challenge(
+    name: &str,
+    pool: &PreciseNumber,
+    num_challenges: usize,
+) -> Result<PreciseNumber> {
+    pool.checked_div(&PreciseNumber::from(num_challenges.max(1)))
+        .ok_or_else(|| anyhow!("{} reward pool per challenge overflow", name))
+}
+
+fn audit_emissions(
+    block_reward: PreciseNumber,
+    scaled_reward: PreciseNumber,
+    codes_pool: PreciseNumber,
+    advances_pool: PreciseNumber,
+    opow_pool: PreciseNumber,
+    challenge_owners_pool: PreciseNumber,
+    mut payouts: Vec<EmissionsPayout>,
+) -> Result<EmissionsAudit> {
+    // payouts are pushed in hashmap order, sorted so that the audit is deterministic
+    payouts.sort_by(|a, b| (&a.player_id, &a.source_id).cmp(&(&b.player_id, &b.source_id)));
+    let total_by_type = total_payouts_by_type(&payouts)?;
+    let total = |emissions_type| total_by_type[&emissions_type];
+
+    let bootstrap = unused_pool("Codes", &codes_pool, &total(EmissionsType::Code))?
+        .checked_add(&unused_pool(
+            "Advances",
+            &advances_pool,
+            &total(EmissionsType::Advance),
+        )?)
+        .ok_or_else(|| anyhow!("Bootstrap emissions overflow"))?;
+    let mut vault = block_reward;
+    for (name, amount) in [
+        ("codes pool", codes_pool),
+        ("advances pool", advances_pool),
+        ("benchmarker payouts", total(EmissionsType::Benchmarker)),
+        ("delegator payouts", total(EmissionsType::Delegator)),
+        ("challenge owners pool", challenge_owners_pool),
+    ] {
+        vault = vault.checked_sub(&amount).ok_or_else(|| {
+            anyhow!(
+                "Block reward of {} over-allocated when deducting {} of {}",
+                block_reward,
+                name,
+                amount
+            )
+        })?;
+    }
+
+    let audit = EmissionsAudit {
+        block_reward,
+        scaled_reward,
+        codes_pool,
+        advances_pool,
+        opow_pool,
+        challenge_owners_pool,
+        payouts,
+        bootstrap,
+        vault,
+    };
+    verify_emissions_audit(&audit)?;
+    Ok(audit)
+}
+
+/// Checks that the books of a block balance: every pool covers its payouts, and the emissions
+/// itemised by the audit add up to the block reward
+pub fn verify_emissions_audit(audit: &EmissionsAudit) -> Result<()> {
+    let total_by_type = total_payouts_by_type(&audit.payouts)?;
+    let total = |emissions_type| total_by_type[&emissions_type];
+
+    unused_pool("Codes", &audit.codes_pool, &total(EmissionsType::Code))?;
+    unused_pool(
+        "Advances",
+        &audit.advances_pool,
+        &total(EmissionsType::Advance),
+    )?;
+    unused_pool(
+        "OPoW",
+        &audit.opow_pool,
+        &total(EmissionsType::Benchmarker)
+            .checked_add(&total(EmissionsType::Delegator))
+            .ok_or_else(|| anyhow!("OPoW payouts overflow"))?,
+    )?;
+
+    let mut emissions = PreciseNumber::from(0);
+    for amount in [&audit.bootstrap, &audit.vault, &audit.challenge_owners_pool]
+        .into_iter()
+        .chain(total_by_type.values())
+    {
+        emissions = emissions
+            .checked_add(amount)
+            .ok_or_else(|| anyhow!("Emissions overflow"))?;
+    }
+    if emissions != audit.block_reward {
+        return Err(anyhow!(
+            "Emissions of {} do not balance with block reward of {}",
+            emissions,
+            audit.block_reward
+        ));
+    }
+    Ok(())
+}
+
+// totals of code, advance, benchmarker and delegator payouts
+fn total_payouts_by_type(
+    payouts: &[EmissionsPayout],
+) -> Result<HashMap<EmissionsType, PreciseNumber>> {
+    let mut total_by_type = [
+        EmissionsType::Code,
+        EmissionsType::Advance,
+        EmissionsType::Benchmarker,
+        EmissionsType::Delegator,
+    ]
+    .into_iter()
+    .map(|t| (t, PreciseNumber::from(0)))
+    .collect::<HashMap<_, _>>();
+    for payout in payouts.iter() {
+        let total = total_by_type
+            .get_mut(&payout.r#type)
+            .ok_or_else(|| anyhow!("Unexpected {:?} payout", payout.r#type))?;
+        *total = total
+            .checked_add(&payout.amount)
+            .ok_or_else(|| anyhow!("{:?} payouts overflow", payout.r#type))?;
+    }
+    Ok(total_by_type)
+}
+
+fn unused_pool(name: &str, pool: &PreciseNumber, paid: &PreciseNumber) -> Result<PreciseNumber> {
+    pool.checked_sub(paid).ok_or_else(|| {
+        anyhow!(
+            "{} pool of {} over-allocated with {} paid",
+            name,
+            pool,
+            paid
+        )
+    })
+}
+
+fn emissions_from_audit(audit: &EmissionsAudit) -> HashMap<EmissionsType, PreciseNumber> {
+    let mut emissions = total_payouts_by_type(&audit.payouts).unwrap();
+    emissions.insert(EmissionsType::Bootstrap, audit.bootstrap);
+    emissions.insert(EmissionsType::Vault, audit.vault);
+    emissions.insert(EmissionsType::ChallengeOwner, audit.challenge_owners_pool);
+    emissions
 }
@@ -0,0 +1,56 @@
+//! Semantic events emitted by the contracts while updating a block.
+//!
+//! `add_block` returns the events of the block it adds, which are also kept in
+//! `AddBlockCache.events`, so that explorers and alerting can follow what happened without diffing
+//! state. Each contract emits its events in a deterministic order, and the contracts run in the
+//! order of `update_block_cache`.
+use serde::{Deserialize, Serialize};
+use tig_structs::core::*;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum BlockEvent {
+    /// a player's cutoff differs from the previous block. Players new to OPoW had a cutoff of 0,
+    /// and players that left it have a cutoff of 0
+    CutoffChanged {
+        player_id: String,
+        prev_cutoff: u64,
+        cutoff: u64,
+    },
+    /// bundles of a player that qualified on a track differ from the previous block. Players new
+    /// to OPoW had no qualifying bundles, and players that left it have none
+    PlayerQualified {
+        player_id: String,
+        challenge_id: String,
+        track_id: String,
+        num_bundles: u64,
+    },
+    CodeMerged {
+        code_id: String,
+        round_merged: u32,
+    },
+    AdvanceMerged {
+        advance_id: String,
+        round_merged: u32,
+    },
+    /// the votes on an advance were tallied. Advances banned by then are not tallied
+    VoteTallied {
+        advance_id: String,
+        yes: PreciseNumber,
+        no: PreciseNumber,
+        passed: bool,
+    },
+    AdvanceActivated {
+        advance_id: String,
+        round_active: u32,
+    },
+    /// a lifecycle transition of the contracts was rejected, leaving the algorithm's state
+    /// unchanged. This is a bug in the contracts
+    TransitionRejected {
+        algorithm_id: String,
+        stage: LifecycleStage,
+        round: u32,
+        reason: String,
+    },
+    RewardPaid(EmissionsPayout),
+}
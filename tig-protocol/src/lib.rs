@@ -1,18 +1,74 @@
 pub mod context;
 mod contracts;
+pub mod events;
+pub mod memory;
+pub mod replay;
+pub mod simulate;
+use anyhow::Result;
 use context::*;
 
 pub use contracts::{
-    algorithms::{submit_advance, submit_binary, submit_code},
-    benchmarks::{submit_benchmark, submit_precommit, submit_proof},
-    players::{set_coinbase, set_delegatees, set_reward_share, set_vote},
+    algorithms::{get_lifecycle, submit_advance, submit_binary, submit_code, AlgorithmLifecycle},
+    bans::set_ban,
+    benchmarks::{
+        get_settings_performance, quote_precommit, sample_benchmark, submit_benchmark,
+        submit_precommit, submit_proof, BenchmarkSample, BundleSample, PrecommitQuote,
+        SettingsPerformance, TrackQuote,
+    },
+    frauds::submit_fraud,
+    players::{
+        get_vote_tally, set_coinbase, set_delegatees, set_reward_share, set_vote,
+        set_vote_delegatee, tally_votes, VoteTally, VoterTally,
+    },
+    rewards::verify_emissions_audit,
 };
 
-pub async fn add_block<T: Context>(ctx: &T) {
+/// Adds a block, returning the events emitted by the contracts. The block is not committed if
+/// its emissions fail the audit
+pub async fn add_block<T: Context>(ctx: &T) -> Result<Vec<BlockEvent>> {
     let mut cache = ctx.build_block_cache().await;
-    contracts::players::update(&mut cache).await;
-    contracts::opow::update(&mut cache).await;
-    contracts::algorithms::update(&mut cache).await;
-    contracts::rewards::update(&mut cache).await;
+    update_block_cache(&mut cache).await?;
+    let events = cache.events.clone();
     ctx.commit_block_cache(cache).await;
+    Ok(events)
+}
+
+/// Same as `add_block`, also returning the caches before and after the update for `replay`
+pub async fn add_block_with_snapshot<T: Context>(ctx: &T) -> Result<replay::BlockSnapshot> {
+    let input = ctx.build_block_cache().await;
+    let mut output = input.clone();
+    update_block_cache(&mut output).await?;
+    ctx.commit_block_cache(output.clone()).await;
+    Ok(replay::BlockSnapshot {
+        input,
+        output: Some(output),
+    })
+}
+
+pub async fn update_block_cache(cache: &mut AddBlockCache) -> Result<()> {
+    select_active(cache).await;
+    update_active(cache).await?;
+    cache.block_details.num_active = cache
+        .block_data
+        .active_ids
+        .iter()
+        .map(|(t, ids)| (*t, ids.len() as u32))
+        .collect();
+    Ok(())
+}
+
+// selects the entities active in the block from those the context put in the cache
+pub(crate) async fn select_active(cache: &mut AddBlockCache) {
+    contracts::challenges::select_active(cache).await;
+    contracts::algorithms::select_active(cache).await;
+    contracts::benchmarks::select_active(cache).await;
+}
+
+pub(crate) async fn update_active(cache: &mut AddBlockCache) -> Result<()> {
+    contracts::frauds::update(cache).await;
+    contracts::benchmarks::update(cache).await;
+    contracts::players::update(cache).await;
+    contracts::opow::update(cache).await;
+    contracts::algorithms::update(cache).await;
+    contracts::rewards::update(cache).await
 }
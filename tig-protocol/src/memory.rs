@@ -0,0 +1,1335 @@
+//! In-memory implementation of `Context`, for running the protocol locally and in tests.
+//!
+//! Items added to the mempool are only visible through the `get_*` methods once confirmed by
+//! `add_block`. Submission fees are deducted from the player's balance when the item enters the
+//! mempool, so that several submissions in the same block cannot overspend.
+//!
+//! Items are stamped with the rounds of their schedule when confirmed: codes are pushed
+//! `codes.push_delay_period` rounds after submission, advances are voted on from
+//! `advances.vote_start_delay` rounds after submission for `advances.vote_period` rounds, and
+//! proofs become active `submission_delay * submission_delay_multiplier` blocks after being
+//! confirmed.
+//!
+//! When a block is built, the cache holds the candidates the contracts select the active ones
+//! from:
+//! * challenges in the config, from round 1
+//! * codes with a binary or a ban, and advances not being voted on that were activated or have a
+//!   ban
+//! * benchmarks until `lifespan_period` blocks after their precommit, along with their proof and
+//!   fraud
+//! * all registered players. Players are registered with `add_player`, or by their first deposit
+//!   or top up
+//! * deposits, until the end of linear deposits
+use crate::context::*;
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+use tig_structs::{config::*, core::*};
+use tig_utils::{jsonify, u8s_from_str, PreciseNumber};
+
+pub struct MemoryContext {
+    state: RwLock<MemoryState>,
+}
+
+enum MempoolItem {
+    Advance {
+        id: String,
+        details: AdvanceDetails,
+        evidence: String,
+    },
+    Benchmark {
+        id: String,
+        details: BenchmarkDetails,
+        solution_quality: Option<Vec<i32>>,
+    },
+    Binary {
+        id: String,
+        details: BinaryDetails,
+    },
+    Code {
+        id: String,
+        details: CodeDetails,
+        source_code: HashMap<String, String>,
+    },
+    Deposit {
+        id: String,
+        details: DepositDetails,
+    },
+    Precommit {
+        id: String,
+        settings: BenchmarkSettings,
+        details: PrecommitDetails,
+    },
+    Fraud {
+        id: String,
+        allegation: String,
+    },
+    Proof {
+        id: String,
+        merkle_proofs: Vec<MerkleProof>,
+        allegation: Option<String>,
+    },
+    TopUp {
+        id: String,
+        details: TopUpDetails,
+    },
+}
+
+impl MempoolItem {
+    fn tx_type(&self) -> TxType {
+        match self {
+            MempoolItem::Advance { .. } => TxType::Advance,
+            MempoolItem::Benchmark { .. } => TxType::Benchmark,
+            MempoolItem::Binary { .. } => TxType::Binary,
+            MempoolItem::Code { .. } => TxType::Code,
+            MempoolItem::Deposit { .. } => TxType::Deposit,
+            MempoolItem::Precommit { .. } => TxType::Precommit,
+            MempoolItem::Fraud { .. } => TxType::Fraud,
+            MempoolItem::Proof { .. } => TxType::Proof,
+            MempoolItem::TopUp { .. } => TxType::TopUp,
+        }
+    }
+
+    fn id(&self) -> &String {
+        match self {
+            MempoolItem::Advance { id, .. }
+            | MempoolItem::Benchmark { id, .. }
+            | MempoolItem::Binary { id, .. }
+            | MempoolItem::Code { id, .. }
+            | MempoolItem::Deposit { id, .. }
+            | MempoolItem::Precommit { id, .. }
+            | MempoolItem::Fraud { id, .. }
+            | MempoolItem::Proof { id, .. }
+            | MempoolItem::TopUp { id, .. } => id,
+        }
+    }
+}
+
+// block data of every active entity at a block
+#[derive(Default)]
+struct BlockEntities {
+    advances: HashMap<String, AdvanceBlockData>,
+    challenges: HashMap<String, ChallengeBlockData>,
+    codes: HashMap<String, CodeBlockData>,
+    opow: HashMap<String, OPoWBlockData>,
+    players: HashMap<String, PlayerBlockData>,
+}
+
+struct MemoryState {
+    config: ProtocolConfig,
+    latest_block_id: String,
+    blocks: HashMap<String, Block>,
+    block_entities: HashMap<String, BlockEntities>,
+    mempool: Vec<MempoolItem>,
+    advances: HashMap<String, Advance>,
+    advance_evidence: HashMap<String, String>,
+    benchmarks: HashMap<String, Benchmark>,
+    binaries: HashMap<String, Binary>,
+    challenges: HashMap<String, Challenge>,
+    codes: HashMap<String, Code>,
+    code_source_code: HashMap<String, HashMap<String, String>>,
+    deposits: HashMap<String, Deposit>,
+    frauds: HashMap<String, Fraud>,
+    players: HashMap<String, Player>,
+    precommits: HashMap<String, Precommit>,
+    proofs: HashMap<String, Proof>,
+    topups: HashMap<String, TopUp>,
+}
+
+impl MemoryContext {
+    /// Creates a chain with a genesis block at height 0 (round 1, timestamp 0). Every challenge
+    /// in `config` is active from round 1
+    pub fn new(config: ProtocolConfig) -> Self {
+        let challenges = config
+            .challenges
+            .iter()
+            .map(|(challenge_id, challenge_config)| {
+                (
+                    challenge_id.clone(),
+                    Challenge {
+                        id: challenge_id.clone(),
+                        config: challenge_config.clone(),
+                        state: ChallengeState { round_active: 1 },
+                        block_data: None,
+                    },
+                )
+            })
+            .collect();
+        let details = BlockDetails {
+            prev_block_id: String::new(),
+            height: 0,
+            round: 1,
+            num_confirmed: HashMap::new(),
+            num_active: HashMap::new(),
+            timestamp: 0,
+            emissions: HashMap::new(),
+            gamma_value: 0.0,
+        };
+        let data = BlockData {
+            confirmed_ids: HashMap::new(),
+            active_ids: all_active_types()
+                .into_iter()
+                .map(|t| (t, HashSet::new()))
+                .collect(),
+            emissions_audit: None,
+        };
+        let block_id = calc_block_id(&details);
+        Self {
+            state: RwLock::new(MemoryState {
+                latest_block_id: block_id.clone(),
+                blocks: HashMap::from([(
+                    block_id.clone(),
+                    Block {
+                        id: block_id.clone(),
+                        details,
+                        data: Some(data),
+                        config: Some(config.clone()),
+                    },
+                )]),
+                block_entities: HashMap::from([(block_id, BlockEntities::default())]),
+                config,
+                mempool: Vec::new(),
+                advances: HashMap::new(),
+                advance_evidence: HashMap::new(),
+                benchmarks: HashMap::new(),
+                binaries: HashMap::new(),
+                challenges,
+                codes: HashMap::new(),
+                code_source_code: HashMap::new(),
+                deposits: HashMap::new(),
+                frauds: HashMap::new(),
+                players: HashMap::new(),
+                precommits: HashMap::new(),
+                proofs: HashMap::new(),
+                topups: HashMap::new(),
+            }),
+        }
+    }
+
+    pub fn add_player(&self, player_id: String, details: PlayerDetails) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        if state.players.contains_key(&player_id) {
+            return Err(anyhow!("Player '{}' is already registered", player_id));
+        }
+        state
+            .players
+            .insert(player_id.clone(), new_player(player_id, details));
+        Ok(())
+    }
+
+    pub fn get_block(&self, block_id: &String) -> Option<Block> {
+        self.state.read().unwrap().blocks.get(block_id).cloned()
+    }
+
+    pub fn get_block_data(&self, block_id: &String) -> Option<BlockData> {
+        let state = self.state.read().unwrap();
+        state.blocks.get(block_id)?.data.clone()
+    }
+
+    pub fn get_advance_block_data(
+        &self,
+        advance_id: &String,
+        block_id: &String,
+    ) -> Option<AdvanceBlockData> {
+        let state = self.state.read().unwrap();
+        state
+            .block_entities
+            .get(block_id)?
+            .advances
+            .get(advance_id)
+            .cloned()
+    }
+
+    pub fn get_challenge_block_data(
+        &self,
+        challenge_id: &String,
+        block_id: &String,
+    ) -> Option<ChallengeBlockData> {
+        let state = self.state.read().unwrap();
+        state
+            .block_entities
+            .get(block_id)?
+            .challenges
+            .get(challenge_id)
+            .cloned()
+    }
+
+    pub fn get_code_block_data(
+        &self,
+        code_id: &String,
+        block_id: &String,
+    ) -> Option<CodeBlockData> {
+        let state = self.state.read().unwrap();
+        state
+            .block_entities
+            .get(block_id)?
+            .codes
+            .get(code_id)
+            .cloned()
+    }
+
+    pub fn get_opow_block_data(
+        &self,
+        player_id: &String,
+        block_id: &String,
+    ) -> Option<OPoWBlockData> {
+        let state = self.state.read().unwrap();
+        state
+            .block_entities
+            .get(block_id)?
+            .opow
+            .get(player_id)
+            .cloned()
+    }
+
+    pub fn get_fraud(&self, benchmark_id: &String) -> Option<Fraud> {
+        self.state.read().unwrap().frauds.get(benchmark_id).cloned()
+    }
+
+    /// Number of items waiting to be confirmed by the next block
+    pub fn mempool_len(&self) -> usize {
+        self.state.read().unwrap().mempool.len()
+    }
+}
+
+impl MemoryState {
+    fn latest_block(&self) -> &Block {
+        &self.blocks[&self.latest_block_id]
+    }
+
+    fn in_mempool(&self, tx_type: TxType, id: &String) -> bool {
+        self.mempool
+            .iter()
+            .any(|item| item.tx_type() == tx_type && item.id() == id)
+    }
+
+    fn pay_fee(&mut self, player_id: &String, fee: PreciseNumber) -> Result<()> {
+        let player = self
+            .players
+            .get_mut(player_id)
+            .ok_or_else(|| anyhow!("Player '{}' is not registered", player_id))?;
+        if player.state.available_fee_balance < fee {
+            return Err(anyhow!("Insufficient balance"));
+        }
+        player.state.available_fee_balance -= fee;
+        player.state.total_fees_paid += fee;
+        Ok(())
+    }
+
+    fn player_state_mut(&mut self, player_id: &String) -> Result<&mut PlayerState> {
+        self.players
+            .get_mut(player_id)
+            .map(|p| &mut p.state)
+            .ok_or_else(|| anyhow!("Player '{}' is not registered", player_id))
+    }
+
+    fn confirm(&mut self, item: MempoolItem, height: u32, round: u32) {
+        match item {
+            MempoolItem::Advance {
+                id,
+                details,
+                evidence,
+            } => {
+                let round_voting_starts = round + self.config.advances.vote_start_delay;
+                let zero = PreciseNumber::from(0);
+                self.advance_evidence.insert(id.clone(), evidence);
+                self.advances.insert(
+                    id.clone(),
+                    Advance {
+                        id,
+                        details,
+                        state: AdvanceState {
+                            block_confirmed: height,
+                            round_submitted: round,
+                            round_pushed: round,
+                            round_voting_starts,
+                            round_votes_tallied: round_voting_starts
+                                + self.config.advances.vote_period,
+                            votes_tally: HashMap::from([(true, zero), (false, zero)]),
+                            round_active: None,
+                            round_merged: None,
+                            banned: false,
+                            lifecycle: new_lifecycle(round, round),
+                            bans: Vec::new(),
+                        },
+                        block_data: None,
+                    },
+                );
+            }
+            MempoolItem::Benchmark {
+                id,
+                details,
+                solution_quality,
+            } => {
+                self.benchmarks.insert(
+                    id.clone(),
+                    Benchmark {
+                        id,
+                        details,
+                        state: BenchmarkState {
+                            block_confirmed: height,
+                        },
+                        solution_quality,
+                    },
+                );
+            }
+            MempoolItem::Binary { id, details } => {
+                self.binaries.insert(
+                    id.clone(),
+                    Binary {
+                        algorithm_id: id,
+                        details,
+                        state: BinaryState {
+                            block_confirmed: height,
+                        },
+                    },
+                );
+            }
+            MempoolItem::Code {
+                id,
+                details,
+                source_code,
+            } => {
+                self.code_source_code.insert(id.clone(), source_code);
+                let round_pushed = round + self.config.codes.push_delay_period;
+                self.codes.insert(
+                    id.clone(),
+                    Code {
+                        id,
+                        details,
+                        state: CodeState {
+                            block_confirmed: height,
+                            round_submitted: round,
+                            round_pushed,
+                            round_active: None,
+                            round_merged: None,
+                            banned: false,
+                            lifecycle: new_lifecycle(round, round_pushed),
+                            bans: Vec::new(),
+                        },
+                        block_data: None,
+                    },
+                );
+            }
+            MempoolItem::Deposit { id, details } => {
+                self.players
+                    .entry(details.player_id.clone())
+                    .or_insert_with(|| new_player(details.player_id.clone(), default_details()));
+                self.deposits.insert(
+                    id.clone(),
+                    Deposit {
+                        id,
+                        details,
+                        state: DepositState {
+                            block_confirmed: height,
+                        },
+                    },
+                );
+            }
+            MempoolItem::Precommit {
+                id,
+                settings,
+                details,
+            } => {
+                self.precommits.insert(
+                    id.clone(),
+                    Precommit {
+                        benchmark_id: id,
+                        details,
+                        settings,
+                        state: PrecommitState {
+                            block_confirmed: height,
+                        },
+                    },
+                );
+            }
+            MempoolItem::Fraud { id, allegation } => {
+                self.frauds.insert(
+                    id.clone(),
+                    Fraud {
+                        benchmark_id: id,
+                        state: FraudState {
+                            block_confirmed: height,
+                        },
+                        allegation: Some(allegation),
+                    },
+                );
+            }
+            MempoolItem::Proof {
+                id,
+                merkle_proofs,
+                allegation,
+            } => {
+                let precommit = &self.precommits[&id];
+                let submission_delay = height - precommit.details.block_started;
+                let multiplier = self.config.challenges[&precommit.settings.challenge_id]
+                    .submission_delay_multiplier;
+                if allegation.is_some() {
+                    self.frauds.insert(
+                        id.clone(),
+                        Fraud {
+                            benchmark_id: id.clone(),
+                            state: FraudState {
+                                block_confirmed: height,
+                            },
+                            allegation,
+                        },
+                    );
+                }
+                self.proofs.insert(
+                    id.clone(),
+                    Proof {
+                        benchmark_id: id,
+                        details: ProofDetails {
+                            submission_delay,
+                            block_active: height
+                                + (submission_delay as f64 * multiplier).ceil() as u32,
+                        },
+                        state: ProofState {
+                            block_confirmed: height,
+                        },
+                        merkle_proofs: Some(merkle_proofs),
+                    },
+                );
+            }
+            MempoolItem::TopUp { id, details } => {
+                let player = self
+                    .players
+                    .entry(details.player_id.clone())
+                    .or_insert_with(|| new_player(details.player_id.clone(), default_details()));
+                player.state.available_fee_balance += details.amount;
+                self.topups.insert(
+                    id.clone(),
+                    TopUp {
+                        id,
+                        details,
+                        state: TopUpState {
+                            block_confirmed: height,
+                        },
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl Context for MemoryContext {
+    async fn get_advance_state(&self, advance_id: &String) -> Option<AdvanceState> {
+        let state = self.state.read().unwrap();
+        state.advances.get(advance_id).map(|a| a.state.clone())
+    }
+
+    async fn get_recent_advance_ids(
+        &self,
+        player_id: &String,
+        min_round_submitted: u32,
+    ) -> HashSet<String> {
+        let state = self.state.read().unwrap();
+        let confirmed = state.advances.values().filter(|a| {
+            a.details.player_id == *player_id && a.state.round_submitted >= min_round_submitted
+        });
+        let in_mempool = state.mempool.iter().filter_map(|item| match item {
+            MempoolItem::Advance { id, details, .. } if details.player_id == *player_id => Some(id),
+            _ => None,
+        });
+        confirmed
+            .map(|a| &a.id)
+            .chain(in_mempool)
+            .cloned()
+            .collect()
+    }
+
+    async fn add_advance_to_mempool(
+        &self,
+        details: AdvanceDetails,
+        evidence: String,
+    ) -> Result<String> {
+        let mut state = self.state.write().unwrap();
+        let prefix = format!("{}_b", details.challenge_id);
+        let num_advances = state
+            .advances
+            .keys()
+            .chain(state.mempool.iter().filter_map(|item| match item {
+                MempoolItem::Advance { id, .. } => Some(id),
+                _ => None,
+            }))
+            .filter(|id| id.starts_with(&prefix))
+            .count();
+        let id = format!("{}{:03}", prefix, num_advances + 1);
+        state.pay_fee(&details.player_id, details.fee_paid)?;
+        state.mempool.push(MempoolItem::Advance {
+            id: id.clone(),
+            details,
+            evidence,
+        });
+        Ok(id)
+    }
+
+    async fn get_benchmark_details(&self, benchmark_id: &String) -> Option<BenchmarkDetails> {
+        let state = self.state.read().unwrap();
+        state
+            .benchmarks
+            .get(benchmark_id)
+            .map(|b| b.details.clone())
+    }
+
+    async fn get_benchmark_solution_quality(&self, benchmark_id: &String) -> Option<Vec<i32>> {
+        let state = self.state.read().unwrap();
+        state.benchmarks.get(benchmark_id)?.solution_quality.clone()
+    }
+
+    async fn add_benchmark_to_mempool(
+        &self,
+        benchmark_id: String,
+        details: BenchmarkDetails,
+        solution_quality: Option<Vec<i32>>,
+    ) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        if state.benchmarks.contains_key(&benchmark_id)
+            || state.in_mempool(TxType::Benchmark, &benchmark_id)
+        {
+            return Err(anyhow!("Duplicate benchmark: {}", benchmark_id));
+        }
+        state.mempool.push(MempoolItem::Benchmark {
+            id: benchmark_id,
+            details,
+            solution_quality,
+        });
+        Ok(())
+    }
+
+    async fn get_binary_details(&self, code_id: &String) -> Option<BinaryDetails> {
+        let state = self.state.read().unwrap();
+        state.binaries.get(code_id).map(|b| b.details.clone())
+    }
+
+    async fn add_binary_to_mempool(&self, code_id: String, details: BinaryDetails) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        if state.binaries.contains_key(&code_id) || state.in_mempool(TxType::Binary, &code_id) {
+            return Err(anyhow!(
+                "Binary already submitted for algorithm: {}",
+                code_id
+            ));
+        }
+        state.mempool.push(MempoolItem::Binary {
+            id: code_id,
+            details,
+        });
+        Ok(())
+    }
+
+    async fn get_latest_block_id(&self) -> String {
+        self.state.read().unwrap().latest_block_id.clone()
+    }
+
+    async fn get_active_ids(
+        &self,
+        block_id: &String,
+        active_type: ActiveType,
+    ) -> Option<HashSet<String>> {
+        let state = self.state.read().unwrap();
+        let block_data = state.blocks.get(block_id)?.data.as_ref()?;
+        block_data.active_ids.get(&active_type).cloned()
+    }
+
+    async fn get_block_details(&self, block_id: &String) -> Option<BlockDetails> {
+        let state = self.state.read().unwrap();
+        state.blocks.get(block_id).map(|b| b.details.clone())
+    }
+
+    async fn get_challenge_state(&self, challenge_id: &String) -> Option<ChallengeState> {
+        let state = self.state.read().unwrap();
+        state.challenges.get(challenge_id).map(|c| c.state.clone())
+    }
+
+    async fn get_code_details(&self, code_id: &String) -> Option<CodeDetails> {
+        let state = self.state.read().unwrap();
+        state.codes.get(code_id).map(|c| c.details.clone())
+    }
+
+    async fn get_code_state(&self, code_id: &String) -> Option<CodeState> {
+        let state = self.state.read().unwrap();
+        state.codes.get(code_id).map(|c| c.state.clone())
+    }
+
+    async fn get_recent_code_ids(
+        &self,
+        player_id: &String,
+        min_round_submitted: u32,
+    ) -> HashSet<String> {
+        let state = self.state.read().unwrap();
+        let confirmed = state.codes.values().filter(|c| {
+            c.details.player_id == *player_id && c.state.round_submitted >= min_round_submitted
+        });
+        let in_mempool = state.mempool.iter().filter_map(|item| match item {
+            MempoolItem::Code { id, details, .. } if details.player_id == *player_id => Some(id),
+            _ => None,
+        });
+        confirmed
+            .map(|c| &c.id)
+            .chain(in_mempool)
+            .cloned()
+            .collect()
+    }
+
+    async fn add_code_to_mempool(
+        &self,
+        details: CodeDetails,
+        source_code: HashMap<String, String>,
+    ) -> Result<String> {
+        let mut state = self.state.write().unwrap();
+        let prefix = format!("{}_a", details.challenge_id);
+        let num_codes = state
+            .codes
+            .keys()
+            .chain(state.mempool.iter().filter_map(|item| match item {
+                MempoolItem::Code { id, .. } => Some(id),
+                _ => None,
+            }))
+            .filter(|id| id.starts_with(&prefix))
+            .count();
+        let id = format!("{}{:03}", prefix, num_codes + 1);
+        state.pay_fee(&details.player_id, details.fee_paid)?;
+        state.mempool.push(MempoolItem::Code {
+            id: id.clone(),
+            details,
+            source_code,
+        });
+        Ok(id)
+    }
+
+    async fn get_config(&self) -> ProtocolConfig {
+        self.state.read().unwrap().config.clone()
+    }
+
+    async fn add_deposit_to_mempool(&self, details: DepositDetails) -> Result<String> {
+        let mut state = self.state.write().unwrap();
+        let id = format!("{}_{}", details.tx_hash, details.log_idx);
+        if state.deposits.contains_key(&id) || state.in_mempool(TxType::Deposit, &id) {
+            return Err(anyhow!("Duplicate deposit: {}", id));
+        }
+        state.mempool.push(MempoolItem::Deposit {
+            id: id.clone(),
+            details,
+        });
+        Ok(id)
+    }
+
+    async fn get_fraud_state(&self, benchmark_id: &String) -> Option<FraudState> {
+        let state = self.state.read().unwrap();
+        state.frauds.get(benchmark_id).map(|f| f.state.clone())
+    }
+
+    async fn add_fraud_to_mempool(&self, benchmark_id: String, allegation: String) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        if state.frauds.contains_key(&benchmark_id)
+            || state.in_mempool(TxType::Fraud, &benchmark_id)
+        {
+            return Err(anyhow!("Duplicate fraud: {}", benchmark_id));
+        }
+        state.mempool.push(MempoolItem::Fraud {
+            id: benchmark_id,
+            allegation,
+        });
+        Ok(())
+    }
+
+    async fn get_player_details(&self, player_id: &String) -> Option<PlayerDetails> {
+        let state = self.state.read().unwrap();
+        state.players.get(player_id).map(|p| p.details.clone())
+    }
+
+    async fn get_player_state(&self, player_id: &String) -> Option<PlayerState> {
+        let state = self.state.read().unwrap();
+        state.players.get(player_id).map(|p| p.state.clone())
+    }
+
+    async fn get_player_block_data(
+        &self,
+        player_id: &String,
+        block_id: &String,
+    ) -> Option<PlayerBlockData> {
+        let state = self.state.read().unwrap();
+        state
+            .block_entities
+            .get(block_id)?
+            .players
+            .get(player_id)
+            .cloned()
+    }
+
+    async fn set_player_delegatees(
+        &self,
+        player_id: String,
+        delegatees: HashMap<String, f64>,
+        block_effective: u32,
+    ) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        let block_set = state.latest_block().details.height;
+        state
+            .player_state_mut(&player_id)?
+            .delegatees
+            .schedule(ScheduledValue {
+                value: delegatees,
+                block_set,
+                block_effective,
+            })
+            .map_err(|e| anyhow!(e))
+    }
+
+    async fn set_player_reward_share(
+        &self,
+        player_id: String,
+        reward_share: f64,
+        block_effective: u32,
+    ) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        let block_set = state.latest_block().details.height;
+        state
+            .player_state_mut(&player_id)?
+            .reward_share
+            .schedule(ScheduledValue {
+                value: reward_share,
+                block_set,
+                block_effective,
+            })
+            .map_err(|e| anyhow!(e))
+    }
+
+    async fn set_player_coinbase(
+        &self,
+        player_id: String,
+        coinbase: HashMap<String, f64>,
+        block_effective: u32,
+    ) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        let block_set = state.latest_block().details.height;
+        state
+            .player_state_mut(&player_id)?
+            .coinbase
+            .schedule(ScheduledValue {
+                value: coinbase,
+                block_set,
+                block_effective,
+            })
+            .map_err(|e| anyhow!(e))
+    }
+
+    async fn set_player_vote(
+        &self,
+        player_id: String,
+        advance_id: String,
+        yes: bool,
+    ) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        let block_set = state.latest_block().details.height;
+        state.player_state_mut(&player_id)?.votes.insert(
+            advance_id,
+            PlayerValue {
+                value: yes,
+                block_set,
+            },
+        );
+        Ok(())
+    }
+
+    async fn set_algorithm_ban(&self, algorithm_id: String, change: BanChange) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        let bans = if let Some(code) = state.codes.get_mut(&algorithm_id) {
+            &mut code.state.bans
+        } else if let Some(advance) = state.advances.get_mut(&algorithm_id) {
+            &mut advance.state.bans
+        } else {
+            return Err(anyhow!("Algorithm '{}' does not exist", algorithm_id));
+        };
+        if bans
+            .last()
+            .is_some_and(|b| b.round_effective >= change.round_effective)
+        {
+            return Err(anyhow!("Bans must be in order of round_effective"));
+        }
+        bans.push(change);
+        Ok(())
+    }
+
+    async fn set_player_vote_delegatee(
+        &self,
+        player_id: String,
+        vote_delegatee: Option<String>,
+    ) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        let block_set = state.latest_block().details.height;
+        state.player_state_mut(&player_id)?.vote_delegatee =
+            vote_delegatee.map(|value| PlayerValue { value, block_set });
+        Ok(())
+    }
+
+    async fn get_vote_delegator_ids(&self, player_id: &String) -> HashSet<String> {
+        let state = self.state.read().unwrap();
+        state
+            .players
+            .iter()
+            .filter(|(_, p)| {
+                p.state
+                    .vote_delegatee
+                    .as_ref()
+                    .is_some_and(|d| d.value == *player_id)
+            })
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    async fn get_precommit_settings(&self, benchmark_id: &String) -> Option<BenchmarkSettings> {
+        let state = self.state.read().unwrap();
+        state
+            .precommits
+            .get(benchmark_id)
+            .map(|p| p.settings.clone())
+    }
+
+    async fn get_precommit_details(&self, benchmark_id: &String) -> Option<PrecommitDetails> {
+        let state = self.state.read().unwrap();
+        state
+            .precommits
+            .get(benchmark_id)
+            .map(|p| p.details.clone())
+    }
+
+    async fn get_pending_precommit_ids(
+        &self,
+        player_id: &String,
+        min_block_started: u32,
+    ) -> HashSet<String> {
+        let state = self.state.read().unwrap();
+        let confirmed = state
+            .precommits
+            .values()
+            .filter(|p| {
+                p.settings.player_id == *player_id && p.details.block_started >= min_block_started
+            })
+            .map(|p| &p.benchmark_id);
+        let in_mempool = state.mempool.iter().filter_map(|item| match item {
+            MempoolItem::Precommit {
+                id,
+                settings,
+                details,
+            } if settings.player_id == *player_id && details.block_started >= min_block_started => {
+                Some(id)
+            }
+            _ => None,
+        });
+        confirmed
+            .chain(in_mempool)
+            .filter(|id| {
+                !state.benchmarks.contains_key(*id) && !state.in_mempool(TxType::Benchmark, id)
+            })
+            .cloned()
+            .collect()
+    }
+
+    async fn add_precommit_to_mempool(
+        &self,
+        settings: BenchmarkSettings,
+        details: PrecommitDetails,
+    ) -> Result<String> {
+        let mut state = self.state.write().unwrap();
+        let hash = u8s_from_str(&format!("{}_{}", jsonify(&settings), details.rand_hash));
+        let id = hex::encode(&hash[..16]);
+        if state.precommits.contains_key(&id) || state.in_mempool(TxType::Precommit, &id) {
+            return Err(anyhow!("Duplicate precommit: {}", id));
+        }
+        state.pay_fee(&settings.player_id, details.fee_paid)?;
+        state.mempool.push(MempoolItem::Precommit {
+            id: id.clone(),
+            settings,
+            details,
+        });
+        Ok(id)
+    }
+
+    async fn get_proof_details(&self, benchmark_id: &String) -> Option<ProofDetails> {
+        let state = self.state.read().unwrap();
+        state.proofs.get(benchmark_id).map(|p| p.details.clone())
+    }
+
+    async fn get_proof_state(&self, benchmark_id: &String) -> Option<ProofState> {
+        let state = self.state.read().unwrap();
+        state.proofs.get(benchmark_id).map(|p| p.state.clone())
+    }
+
+    async fn add_proof_to_mempool(
+        &self,
+        benchmark_id: String,
+        merkle_proofs: Vec<MerkleProof>,
+        allegation: Option<String>,
+    ) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        if state.proofs.contains_key(&benchmark_id)
+            || state.in_mempool(TxType::Proof, &benchmark_id)
+        {
+            return Err(anyhow!("Duplicate proof: {}", benchmark_id));
+        }
+        state.mempool.push(MempoolItem::Proof {
+            id: benchmark_id,
+            merkle_proofs,
+            allegation,
+        });
+        Ok(())
+    }
+
+    async fn add_topup_to_mempool(&self, details: TopUpDetails) -> Result<String> {
+        let mut state = self.state.write().unwrap();
+        let id = format!("{}_{}", details.tx_hash, details.log_idx);
+        if state.topups.contains_key(&id) || state.in_mempool(TxType::TopUp, &id) {
+            return Err(anyhow!("Duplicate topup: {}", id));
+        }
+        state.mempool.push(MempoolItem::TopUp {
+            id: id.clone(),
+            details,
+        });
+        Ok(id)
+    }
+
+    async fn build_block_cache(&self) -> AddBlockCache {
+        let mut state = self.state.write().unwrap();
+        let config = state.config.clone();
+        let prev_block = state.latest_block().clone();
+        let height = prev_block.details.height + 1;
+        let round = height / config.rounds.blocks_per_round + 1;
+        let timestamp = prev_block.details.timestamp + config.rounds.seconds_between_blocks as u64;
+
+        // confirm mempool
+        let mut confirmed_ids = all_tx_types()
+            .into_iter()
+            .map(|t| (t, HashSet::new()))
+            .collect::<HashMap<TxType, HashSet<String>>>();
+        for item in std::mem::take(&mut state.mempool) {
+            let tx_type = item.tx_type();
+            let id = item.id().clone();
+            if tx_type == TxType::Proof && item_has_allegation(&item) {
+                confirmed_ids
+                    .get_mut(&TxType::Fraud)
+                    .unwrap()
+                    .insert(id.clone());
+            }
+            state.confirm(item, height, round);
+            confirmed_ids.get_mut(&tx_type).unwrap().insert(id);
+        }
+
+        // active sets
+        let challenge_ids = state
+            .challenges
+            .values()
+            .filter(|c| c.state.round_active <= round)
+            .map(|c| c.id.clone())
+            .collect::<HashSet<String>>();
+        let code_ids = state
+            .codes
+            .values()
+            .filter(|c| state.binaries.contains_key(&c.id) || !c.state.bans.is_empty())
+            .map(|c| c.id.clone())
+            .collect::<HashSet<String>>();
+        let voting_advance_ids = state
+            .advances
+            .values()
+            .filter(|a| a.state.round_voting_starts <= round && round < a.state.round_votes_tallied)
+            .map(|a| a.id.clone())
+            .collect::<HashSet<String>>();
+        let advance_ids = state
+            .advances
+            .values()
+            .filter(|a| {
+                !voting_advance_ids.contains(&a.id)
+                    && (a.state.round_active.is_some() || !a.state.bans.is_empty())
+            })
+            .map(|a| a.id.clone())
+            .collect::<HashSet<String>>();
+        let recent_benchmarks = state
+            .benchmarks
+            .iter()
+            .filter(|(id, _)| {
+                let precommit = &state.precommits[*id];
+                let lifespan_period =
+                    config.challenges[&precommit.settings.challenge_id].lifespan_period;
+                height <= precommit.details.block_started + lifespan_period
+            })
+            .map(|(id, benchmark)| {
+                let precommit = &state.precommits[id];
+                (
+                    id.clone(),
+                    RecentBenchmark {
+                        settings: precommit.settings.clone(),
+                        precommit_details: precommit.details.clone(),
+                        details: benchmark.details.clone(),
+                        proof_details: state.proofs.get(id).map(|p| p.details.clone()),
+                        fraud_state: state.frauds.get(id).map(|f| f.state.clone()),
+                    },
+                )
+            })
+            .collect();
+        let active_player_ids = state.players.keys().cloned().collect::<HashSet<String>>();
+        let active_deposit_ids = state
+            .deposits
+            .values()
+            .filter(|d| match d.details.r#type {
+                DepositType::Linear { end_timestamp, .. } => timestamp < end_timestamp,
+                DepositType::Lock { .. } => true,
+            })
+            .map(|d| d.id.clone())
+            .collect::<HashSet<String>>();
+
+        let zero = PreciseNumber::from(0);
+        // merge points accumulate over the blocks of a round
+        let prev_entities = &state.block_entities[&prev_block.id];
+        let same_round = prev_block.details.round == round;
+        let active_codes_block_data = code_ids
+            .iter()
+            .map(|id| {
+                let merge_points = match prev_entities.codes.get(id) {
+                    Some(data) if same_round => data.merge_points,
+                    _ => 0,
+                };
+                (
+                    id.clone(),
+                    CodeBlockData {
+                        num_qualifiers_by_track_by_player: HashMap::new(),
+                        adoption: zero,
+                        merge_points,
+                        reward: zero,
+                    },
+                )
+            })
+            .collect();
+        let active_advances_block_data = advance_ids
+            .iter()
+            .map(|id| {
+                let merge_points = match prev_entities.advances.get(id) {
+                    Some(data) if same_round => data.merge_points,
+                    _ => 0,
+                };
+                (
+                    id.clone(),
+                    AdvanceBlockData {
+                        adoption: zero,
+                        merge_points,
+                        reward: zero,
+                    },
+                )
+            })
+            .collect();
+        let lock_period_cap = config.deposits.lock_period_cap as usize;
+        let active_players_block_data = active_player_ids
+            .iter()
+            .map(|id| {
+                (
+                    id.clone(),
+                    PlayerBlockData {
+                        delegatees: HashMap::new(),
+                        reward_by_type: HashMap::new(),
+                        deposit_by_locked_period: vec![zero; lock_period_cap],
+                        weighted_deposit: zero,
+                    },
+                )
+            })
+            .collect();
+        let active_challenges_block_data = challenge_ids
+            .iter()
+            .map(|id| {
+                (
+                    id.clone(),
+                    ChallengeBlockData {
+                        num_qualifiers_by_track: HashMap::new(),
+                        qualifier_qualities_by_track: HashMap::new(),
+                    },
+                )
+            })
+            .collect();
+
+        let active_ids = HashMap::from([
+            (ActiveType::Deposit, active_deposit_ids),
+            (ActiveType::Player, active_player_ids),
+        ]);
+        let block_details = BlockDetails {
+            prev_block_id: prev_block.id.clone(),
+            height,
+            round,
+            num_confirmed: confirmed_ids
+                .iter()
+                .map(|(t, ids)| (*t, ids.len() as u32))
+                .collect(),
+            num_active: HashMap::new(),
+            timestamp,
+            emissions: HashMap::new(),
+            gamma_value: 0.0,
+        };
+
+        AddBlockCache {
+            active_deposit_details: active_ids[&ActiveType::Deposit]
+                .iter()
+                .map(|id| (id.clone(), state.deposits[id].details.clone()))
+                .collect(),
+            active_players_state: active_ids[&ActiveType::Player]
+                .iter()
+                .map(|id| (id.clone(), state.players[id].state.clone()))
+                .collect(),
+            active_players_block_data,
+            active_opow_block_data: prev_entities.opow.clone(),
+            active_challenges_block_data,
+            active_codes_state: code_ids
+                .iter()
+                .map(|id| (id.clone(), state.codes[id].state.clone()))
+                .collect(),
+            active_codes_details: code_ids
+                .iter()
+                .map(|id| (id.clone(), state.codes[id].details.clone()))
+                .collect(),
+            active_codes_block_data,
+            voting_advances_state: voting_advance_ids
+                .iter()
+                .map(|id| (id.clone(), state.advances[id].state.clone()))
+                .collect(),
+            active_advances_state: advance_ids
+                .iter()
+                .map(|id| (id.clone(), state.advances[id].state.clone()))
+                .collect(),
+            active_advances_details: advance_ids
+                .iter()
+                .map(|id| (id.clone(), state.advances[id].details.clone()))
+                .collect(),
+            active_advances_block_data,
+            recent_benchmarks,
+            active_benchmarks: Vec::new(),
+            confirmed_binaries_details: confirmed_ids[&TxType::Binary]
+                .iter()
+                .map(|id| (id.clone(), state.binaries[id].details.clone()))
+                .collect(),
+            events: Vec::new(),
+            block_details,
+            block_data: BlockData {
+                confirmed_ids,
+                active_ids,
+                emissions_audit: None,
+            },
+            config,
+        }
+    }
+
+    async fn commit_block_cache(&self, cache: AddBlockCache) {
+        let mut state = self.state.write().unwrap();
+        let block_id = calc_block_id(&cache.block_details);
+        for (id, code_state) in cache.active_codes_state {
+            state.codes.get_mut(&id).unwrap().state = code_state;
+        }
+        for (id, advance_state) in cache
+            .voting_advances_state
+            .into_iter()
+            .chain(cache.active_advances_state)
+        {
+            state.advances.get_mut(&id).unwrap().state = advance_state;
+        }
+        for (id, player_state) in cache.active_players_state {
+            state.players.get_mut(&id).unwrap().state = player_state;
+        }
+        state.block_entities.insert(
+            block_id.clone(),
+            BlockEntities {
+                advances: cache.active_advances_block_data,
+                challenges: cache.active_challenges_block_data,
+                codes: cache.active_codes_block_data,
+                opow: cache.active_opow_block_data,
+                players: cache.active_players_block_data,
+            },
+        );
+        state.blocks.insert(
+            block_id.clone(),
+            Block {
+                id: block_id.clone(),
+                details: cache.block_details,
+                data: Some(cache.block_data),
+                config: Some(cache.config),
+            },
+        );
+        state.latest_block_id = block_id;
+    }
+}
+
+fn calc_block_id(details: &BlockDetails) -> String {
+    hex::encode(&u8s_from_str(&jsonify(details))[..16])
+}
+
+fn item_has_allegation(item: &MempoolItem) -> bool {
+    matches!(
+        item,
+        MempoolItem::Proof {
+            allegation: Some(_),
+            ..
+        }
+    )
+}
+
+fn new_lifecycle(round_submitted: u32, round_pushed: u32) -> Lifecycle {
+    let mut lifecycle = Lifecycle::default();
+    lifecycle
+        .transition(LifecycleStage::Submitted, round_submitted)
+        .unwrap();
+    lifecycle
+        .transition(LifecycleStage::Pushed, round_pushed)
+        .unwrap();
+    lifecycle
+}
+
+fn new_player(player_id: String, details: PlayerDetails) -> Player {
+    let zero = PreciseNumber::from(0);
+    Player {
+        id: player_id,
+        details,
+        state: PlayerState {
+            total_fees_paid: zero,
+            available_fee_balance: zero,
+            delegatees: ValueSchedule::default(),
+            votes: HashMap::new(),
+            vote_delegatee: None,
+            reward_share: ValueSchedule::default(),
+            coinbase: ValueSchedule::default(),
+        },
+        block_data: None,
+    }
+}
+
+fn default_details() -> PlayerDetails {
+    PlayerDetails {
+        name: None,
+        x: None,
+        telegram: None,
+        discord: None,
+        is_multisig: false,
+    }
+}
+
+fn all_active_types() -> [ActiveType; 7] {
+    [
+        ActiveType::Advance,
+        ActiveType::Benchmark,
+        ActiveType::Challenge,
+        ActiveType::Code,
+        ActiveType::Deposit,
+        ActiveType::OPoW,
+        ActiveType::Player,
+    ]
+}
+
+fn all_tx_types() -> [TxType; 11] {
+    [
+        TxType::Advance,
+        TxType::Benchmark,
+        TxType::Binary,
+        TxType::Challenge,
+        TxType::Code,
+        TxType::Deposit,
+        TxType::Fraud,
+        TxType::Precommit,
+        TxType::Proof,
+        TxType::TopUp,
+        TxType::Verified,
+    ]
+}
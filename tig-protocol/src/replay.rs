@@ -0,0 +1,135 @@
+//! Replays `add_block` over exported block snapshots, reporting where the replayed emissions,
+//! OPoW data, code data and player rewards differ from what was recorded.
+//!
+//! Snapshots are exported with `add_block_with_snapshot`, or by serializing the caches passed
+//! through `Context::build_block_cache` and `Context::commit_block_cache`.
+use crate::{context::AddBlockCache, update_block_cache};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeSet;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BlockSnapshot {
+    /// cache before the update, as built by `Context::build_block_cache`
+    pub input: AddBlockCache,
+    /// cache after the update, as committed by `Context::commit_block_cache`. When missing, the
+    /// replayed block is diffed against the previous replayed block
+    pub output: Option<AddBlockCache>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ValueDiff {
+    /// dot separated path, e.g. `opow.<player_id>.influence`
+    pub path: String,
+    pub expected: Option<Value>,
+    pub actual: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BlockDiff {
+    pub height: u32,
+    pub diffs: Vec<ValueDiff>,
+}
+
+pub async fn replay_block(snapshot: &BlockSnapshot) -> Result<AddBlockCache> {
+    let mut cache = snapshot.input.clone();
+    update_block_cache(&mut cache).await?;
+    Ok(cache)
+}
+
+pub async fn replay(snapshots: &[BlockSnapshot]) -> Result<Vec<BlockDiff>> {
+    let mut block_diffs = Vec::new();
+    let mut prev_cache: Option<AddBlockCache> = None;
+    for snapshot in snapshots {
+        let cache = replay_block(snapshot).await?;
+        let expected = snapshot
+            .output
+            .as_ref()
+            .or(prev_cache.as_ref())
+            .map(audited_values)
+            .unwrap_or_else(|| json!({}));
+        let mut diffs = Vec::new();
+        diff_values(
+            "",
+            Some(&expected),
+            Some(&audited_values(&cache)),
+            &mut diffs,
+        );
+        block_diffs.push(BlockDiff {
+            height: cache.block_details.height,
+            diffs,
+        });
+        prev_cache = Some(cache);
+    }
+    Ok(block_diffs)
+}
+
+pub fn diff_block_caches(expected: &AddBlockCache, actual: &AddBlockCache) -> BlockDiff {
+    let mut diffs = Vec::new();
+    diff_values(
+        "",
+        Some(&audited_values(expected)),
+        Some(&audited_values(actual)),
+        &mut diffs,
+    );
+    BlockDiff {
+        height: actual.block_details.height,
+        diffs,
+    }
+}
+
+fn audited_values(cache: &AddBlockCache) -> Value {
+    json!({
+        "emissions": cache.block_details.emissions,
+        "opow": cache.active_opow_block_data,
+        "codes": cache.active_codes_block_data,
+        "rewards": cache
+            .active_players_block_data
+            .iter()
+            .map(|(player_id, data)| (player_id.clone(), json!(data.reward_by_type)))
+            .collect::<Map<String, Value>>(),
+    })
+}
+
+fn diff_values(
+    path: &str,
+    expected: Option<&Value>,
+    actual: Option<&Value>,
+    diffs: &mut Vec<ValueDiff>,
+) {
+    // objects are compared key by key, with a missing object treated as empty
+    let empty = Map::new();
+    let (expected_map, actual_map) = match (expected, actual) {
+        (Some(Value::Object(e)), Some(Value::Object(a))) => (e, a),
+        (Some(Value::Object(e)), None) => (e, &empty),
+        (None, Some(Value::Object(a))) => (&empty, a),
+        _ => {
+            if expected != actual {
+                diffs.push(ValueDiff {
+                    path: path.to_string(),
+                    expected: expected.cloned(),
+                    actual: actual.cloned(),
+                });
+            }
+            return;
+        }
+    };
+    let keys = expected_map
+        .keys()
+        .chain(actual_map.keys())
+        .collect::<BTreeSet<_>>();
+    for key in keys {
+        let child_path = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        diff_values(
+            &child_path,
+            expected_map.get(key),
+            actual_map.get(key),
+            diffs,
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! What-if simulation of a player's influence and rewards.
+//!
+//! Takes the cache of a block before its update (e.g. `BlockSnapshot::input` from
+//! `add_block_with_snapshot`), applies a hypothetical change to one player's bundles or deposits,
+//! and reruns the block update to project the player's influence, imbalance and rewards.
+use crate::{context::*, select_active, update_active};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tig_structs::core::*;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Scenario {
+    pub player_id: String,
+    /// replaces the player's benchmarks on each given challenge and track. An empty
+    /// `average_quality_by_bundle` removes them
+    pub benchmarks: Vec<SimulatedBenchmark>,
+    /// replaces the player's deposits when set
+    pub deposits: Option<Vec<SimulatedDeposit>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SimulatedBenchmark {
+    pub challenge_id: String,
+    pub track_id: String,
+    pub algorithm_id: String,
+    pub average_quality_by_bundle: Vec<i32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SimulatedDeposit {
+    pub amount: PreciseNumber,
+    pub r#type: DepositType,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Projection {
+    pub cutoff: u64,
+    pub num_qualifiers_by_challenge_by_track: HashMap<String, HashMap<String, u64>>,
+    pub imbalance: PreciseNumber,
+    pub influence: PreciseNumber,
+    pub reward: PreciseNumber,
+    pub reward_by_type: HashMap<EmissionsType, PreciseNumber>,
+}
+
+pub async fn simulate(cache: &AddBlockCache, scenario: &Scenario) -> Result<Projection> {
+    let mut cache = cache.clone();
+    select_active(&mut cache).await;
+    apply_scenario(&mut cache, scenario)?;
+    update_active(&mut cache).await?;
+
+    let player_data = &cache.active_players_block_data[&scenario.player_id];
+    let opow_data = &cache.active_opow_block_data[&scenario.player_id];
+    Ok(Projection {
+        cutoff: opow_data.cutoff,
+        num_qualifiers_by_challenge_by_track: opow_data
+            .num_qualifiers_by_challenge_by_track
+            .clone(),
+        imbalance: opow_data.imbalance,
+        influence: opow_data.influence,
+        reward: opow_data.reward,
+        reward_by_type: player_data.reward_by_type.clone(),
+    })
+}
+
+fn apply_scenario(cache: &mut AddBlockCache, scenario: &Scenario) -> Result<()> {
+    let player_id = &scenario.player_id;
+    if !cache.active_players_block_data.contains_key(player_id) {
+        return Err(anyhow!("Player '{}' is not active", player_id));
+    }
+
+    for benchmark in scenario.benchmarks.iter() {
+        if !cache
+            .config
+            .challenges
+            .get(&benchmark.challenge_id)
+            .is_some_and(|c| {
+                c.active_tracks_at(cache.block_details.round)
+                    .contains_key(&benchmark.track_id)
+            })
+            || !cache.block_data.active_ids[&ActiveType::Challenge]
+                .contains(&benchmark.challenge_id)
+        {
+            return Err(anyhow!(
+                "Invalid challenge '{}' or track '{}'",
+                benchmark.challenge_id,
+                benchmark.track_id
+            ));
+        }
+        if !cache.block_data.active_ids[&ActiveType::Code].contains(&benchmark.algorithm_id)
+            || cache.active_codes_details[&benchmark.algorithm_id].challenge_id
+                != benchmark.challenge_id
+        {
+            return Err(anyhow!("Invalid algorithm '{}'", benchmark.algorithm_id));
+        }
+        cache.active_benchmarks.retain(|(settings, _)| {
+            settings.player_id != *player_id
+                || settings.challenge_id != benchmark.challenge_id
+                || settings.track_id != benchmark.track_id
+        });
+        if !benchmark.average_quality_by_bundle.is_empty() {
+            cache.active_benchmarks.push((
+                BenchmarkSettings {
+                    player_id: player_id.clone(),
+                    block_id: cache.block_details.prev_block_id.clone(),
+                    challenge_id: benchmark.challenge_id.clone(),
+                    algorithm_id: benchmark.algorithm_id.clone(),
+                    track_id: benchmark.track_id.clone(),
+                },
+                benchmark.average_quality_by_bundle.clone(),
+            ));
+        }
+    }
+
+    if let Some(deposits) = &scenario.deposits {
+        let active_deposit_ids = cache
+            .block_data
+            .active_ids
+            .get_mut(&ActiveType::Deposit)
+            .unwrap();
+        let player_deposit_ids = cache
+            .active_deposit_details
+            .iter()
+            .filter(|(_, details)| details.player_id == *player_id)
+            .map(|(deposit_id, _)| deposit_id.clone())
+            .collect::<Vec<_>>();
+        for deposit_id in player_deposit_ids {
+            cache.active_deposit_details.remove(&deposit_id);
+            active_deposit_ids.remove(&deposit_id);
+        }
+        for (i, deposit) in deposits.iter().enumerate() {
+            let deposit_id = format!("simulated_{}_{}", player_id, i);
+            active_deposit_ids.insert(deposit_id.clone());
+            cache.active_deposit_details.insert(
+                deposit_id.clone(),
+                DepositDetails {
+                    player_id: player_id.clone(),
+                    tx_hash: deposit_id,
+                    log_idx: 0,
+                    amount: deposit.amount,
+                    r#type: deposit.r#type,
+                },
+            );
+        }
+    }
+
+    // the player takes part in opow even if left without benchmarks, projecting zero influence
+    cache
+        .block_data
+        .active_ids
+        .get_mut(&ActiveType::OPoW)
+        .unwrap()
+        .insert(player_id.clone());
+    Ok(())
+}
@@ -0,0 +1,273 @@
+mod common;
+
+use common::*;
+use std::collections::HashMap;
+use tig_protocol::{
+    add_block, context::Context, get_lifecycle, memory::MemoryContext, set_ban, set_vote,
+    submit_advance, submit_binary, submit_code, submit_precommit,
+};
+use tig_structs::core::*;
+use tig_utils::PreciseNumber;
+
+async fn latest_round(ctx: &impl Context) -> u32 {
+    let block_id = ctx.get_latest_block_id().await;
+    ctx.get_block_details(&block_id).await.unwrap().round
+}
+
+async fn add_blocks_until_round(ctx: &MemoryContext, round: u32) {
+    while latest_round(ctx).await < round {
+        add_block(ctx).await.unwrap();
+    }
+}
+
+async fn ban(ctx: &MemoryContext, algorithm_id: &str, banned: bool) -> u32 {
+    set_ban(
+        ctx,
+        GOVERNANCE.to_string(),
+        algorithm_id.to_string(),
+        banned,
+        "plagiarism".to_string(),
+        None,
+    )
+    .await
+    .unwrap();
+    let code_state = ctx.get_code_state(&algorithm_id.to_string()).await;
+    let bans = match code_state {
+        Some(code_state) => code_state.bans,
+        None => {
+            ctx.get_advance_state(&algorithm_id.to_string())
+                .await
+                .unwrap()
+                .bans
+        }
+    };
+    bans.last().unwrap().round_effective
+}
+
+async fn stage(ctx: &MemoryContext, algorithm_id: &str) -> LifecycleStage {
+    get_lifecycle(ctx, algorithm_id.to_string())
+        .await
+        .unwrap()
+        .stage
+}
+
+#[tokio::test]
+async fn test_ban_and_unban_code() {
+    let ctx = setup().await;
+    let code_id = setup_active_code(&ctx).await;
+    let benchmark_id = run_benchmark(&ctx, PLAYER, &code_id, false).await;
+    for _ in 0..3 {
+        add_block(&ctx).await.unwrap();
+    }
+    assert!(latest_active_ids(&ctx, ActiveType::Benchmark)
+        .await
+        .contains(&benchmark_id));
+
+    // the ban takes effect from the next round
+    let round_effective = ban(&ctx, &code_id, true).await;
+    while latest_round(&ctx).await < round_effective {
+        assert!(!ctx.get_code_state(&code_id).await.unwrap().banned);
+        add_block(&ctx).await.unwrap();
+    }
+    assert!(ctx.get_code_state(&code_id).await.unwrap().banned);
+    assert_eq!(stage(&ctx, &code_id).await, LifecycleStage::Banned);
+    assert!(!latest_active_ids(&ctx, ActiveType::Code)
+        .await
+        .contains(&code_id));
+    assert!(!latest_active_ids(&ctx, ActiveType::Benchmark)
+        .await
+        .contains(&benchmark_id));
+    let settings = BenchmarkSettings {
+        player_id: PLAYER.to_string(),
+        block_id: ctx.get_latest_block_id().await,
+        challenge_id: CHALLENGE.to_string(),
+        algorithm_id: code_id.clone(),
+        track_id: String::new(),
+    };
+    let track_settings = HashMap::from([(
+        TRACK.to_string(),
+        TrackSettings {
+            hyperparameters: None,
+            fuel_budget: 1000,
+            num_bundles: 2,
+        },
+    )]);
+    assert!(
+        submit_precommit(&ctx, PLAYER.to_string(), settings, track_settings, 0)
+            .await
+            .is_err()
+    );
+
+    // unbanned codes return to the stage they were banned in
+    let round_effective = ban(&ctx, &code_id, false).await;
+    add_blocks_until_round(&ctx, round_effective).await;
+    let code_state = ctx.get_code_state(&code_id).await.unwrap();
+    assert!(!code_state.banned);
+    assert_eq!(
+        code_state
+            .bans
+            .iter()
+            .map(|b| (b.banned, b.reason.as_str()))
+            .collect::<Vec<_>>(),
+        vec![(true, "plagiarism"), (false, "plagiarism")]
+    );
+    assert_eq!(stage(&ctx, &code_id).await, LifecycleStage::Active);
+    assert!(latest_active_ids(&ctx, ActiveType::Code)
+        .await
+        .contains(&code_id));
+}
+
+#[tokio::test]
+async fn test_code_banned_before_binary_is_active_when_unbanned() {
+    let ctx = setup().await;
+    let code_id = submit_code(
+        &ctx,
+        PLAYER.to_string(),
+        "test_code".to_string(),
+        CHALLENGE.to_string(),
+        None,
+        HashMap::new(),
+    )
+    .await
+    .unwrap();
+    add_block(&ctx).await.unwrap();
+    let round_effective = ban(&ctx, &code_id, true).await;
+    add_blocks_until_round(&ctx, round_effective).await;
+    assert!(submit_compiled_binary(&ctx, &code_id).await.is_err());
+
+    let round_effective = ban(&ctx, &code_id, false).await;
+    add_blocks_until_round(&ctx, round_effective).await;
+    assert_eq!(stage(&ctx, &code_id).await, LifecycleStage::Pushed);
+    submit_compiled_binary(&ctx, &code_id).await.unwrap();
+    add_block(&ctx).await.unwrap();
+    assert_eq!(stage(&ctx, &code_id).await, LifecycleStage::Active);
+}
+
+#[tokio::test]
+async fn test_banned_advance_is_not_activated() {
+    let ctx = setup().await;
+    ctx.add_deposit_to_mempool(DepositDetails {
+        player_id: PLAYER.to_string(),
+        tx_hash: "0xdeposit".to_string(),
+        log_idx: 0,
+        amount: PreciseNumber::from(100),
+        r#type: DepositType::Lock { eth_block_num: 0 },
+    })
+    .await
+    .unwrap();
+    let advance_id = submit_advance(
+        &ctx,
+        PLAYER.to_string(),
+        "advance".to_string(),
+        CHALLENGE.to_string(),
+        "evidence".to_string(),
+    )
+    .await
+    .unwrap();
+    add_block(&ctx).await.unwrap();
+    let advance_state = ctx.get_advance_state(&advance_id).await.unwrap();
+    add_blocks_until_round(&ctx, advance_state.round_voting_starts).await;
+    set_vote(&ctx, PLAYER.to_string(), advance_id.clone(), true)
+        .await
+        .unwrap();
+    ban(&ctx, &advance_id, true).await;
+
+    add_blocks_until_round(&ctx, advance_state.round_votes_tallied).await;
+    let advance_state = ctx.get_advance_state(&advance_id).await.unwrap();
+    assert!(advance_state.banned);
+    assert_eq!(advance_state.round_active, None);
+    assert_eq!(stage(&ctx, &advance_id).await, LifecycleStage::Banned);
+}
+
+#[tokio::test]
+async fn test_invalid_bans() {
+    let ctx = setup().await;
+    let code_id = setup_active_code(&ctx).await;
+    let round = latest_round(&ctx).await;
+    let try_ban = |player_id: &str, banned: bool, reason: &str, round_effective: Option<u32>| {
+        set_ban(
+            &ctx,
+            player_id.to_string(),
+            code_id.clone(),
+            banned,
+            reason.to_string(),
+            round_effective,
+        )
+    };
+    let err = |result: anyhow::Result<()>| result.unwrap_err().to_string();
+
+    assert!(err(try_ban(PLAYER, true, "plagiarism", None).await).starts_with("Only"));
+    assert_eq!(
+        err(try_ban(GOVERNANCE, true, " ", None).await),
+        "Missing reason"
+    );
+    assert!(
+        err(try_ban(GOVERNANCE, false, "plagiarism", None).await).ends_with("is already unbanned")
+    );
+    assert!(
+        err(try_ban(GOVERNANCE, true, "plagiarism", Some(round)).await)
+            .starts_with("Invalid round_effective")
+    );
+    try_ban(GOVERNANCE, true, "plagiarism", Some(round + 2))
+        .await
+        .unwrap();
+    assert!(err(try_ban(GOVERNANCE, true, "plagiarism", None).await).ends_with("is already banned"));
+    assert!(
+        err(try_ban(GOVERNANCE, false, "mistake", Some(round + 2)).await)
+            .starts_with("Invalid round_effective")
+    );
+    assert!(err(set_ban(
+        &ctx,
+        GOVERNANCE.to_string(),
+        "c001_x999".to_string(),
+        true,
+        "plagiarism".to_string(),
+        None,
+    )
+    .await)
+    .starts_with("Invalid algorithm"));
+
+    // codes that failed to compile stay banned
+    let code_id = submit_code(
+        &ctx,
+        PLAYER.to_string(),
+        "broken_code".to_string(),
+        CHALLENGE.to_string(),
+        None,
+        HashMap::new(),
+    )
+    .await
+    .unwrap();
+    add_block(&ctx).await.unwrap();
+    submit_binary(&ctx, code_id.clone(), None, HashMap::new(), None, None)
+        .await
+        .unwrap();
+    add_block(&ctx).await.unwrap();
+    assert!(err(set_ban(
+        &ctx,
+        GOVERNANCE.to_string(),
+        code_id,
+        false,
+        "mistake".to_string(),
+        None,
+    )
+    .await)
+    .starts_with("Cannot unban"));
+
+    let mut config = test_config();
+    config.bans = None;
+    let ctx = setup_with(config, &[PLAYER]).await;
+    let code_id = setup_active_code(&ctx).await;
+    assert_eq!(
+        err(set_ban(
+            &ctx,
+            GOVERNANCE.to_string(),
+            code_id,
+            true,
+            "plagiarism".to_string(),
+            None,
+        )
+        .await),
+        "Banning is disabled"
+    );
+}
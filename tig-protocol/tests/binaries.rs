@@ -0,0 +1,256 @@
+mod common;
+
+use common::*;
+use std::collections::HashMap;
+use tig_protocol::{
+    add_block, context::Context, memory::MemoryContext, submit_binary, submit_code,
+};
+use tig_structs::{config::*, core::*};
+use tig_utils::blake3_hex;
+
+const FAILED: BinaryArtifact = BinaryArtifact {
+    compile_success: false,
+    hash: None,
+};
+
+async fn pushed_code(ctx: &MemoryContext) -> String {
+    let code_id = submit_code(
+        ctx,
+        PLAYER.to_string(),
+        "test_code".to_string(),
+        CHALLENGE.to_string(),
+        None,
+        HashMap::new(),
+    )
+    .await
+    .unwrap();
+    add_block(ctx).await.unwrap();
+    code_id
+}
+
+async fn submit(
+    ctx: &MemoryContext,
+    code_id: &str,
+    so_by_arch: HashMap<CPUArchitecture, BinaryArtifact>,
+    ptx: Option<BinaryArtifact>,
+) -> anyhow::Result<()> {
+    submit_binary(
+        ctx,
+        code_id.to_string(),
+        Some("https://example.com/binary".to_string()),
+        so_by_arch,
+        ptx,
+        Some("rustc 1.82.0".to_string()),
+    )
+    .await
+}
+
+fn amd64(artifact: BinaryArtifact) -> HashMap<CPUArchitecture, BinaryArtifact> {
+    HashMap::from([(CPUArchitecture::AMD64, artifact)])
+}
+
+#[tokio::test]
+async fn test_partially_compiled_binary() {
+    let ctx = setup().await;
+    let code_id = setup_active_code(&ctx).await;
+
+    let binary = ctx.get_binary_details(&code_id).await.unwrap();
+    assert!(binary.compile_success);
+    assert_eq!(
+        binary.so_by_arch[&CPUArchitecture::AMD64].hash,
+        Some(blake3_hex(b"amd64 binary"))
+    );
+    assert!(binary.has_approved_binary(CPUArchitecture::AMD64));
+    assert!(!binary.has_approved_binary(CPUArchitecture::ARM64));
+    assert_eq!(binary.toolchain_version, Some("rustc 1.82.0".to_string()));
+
+    // proofs of nonces computed on an architecture without an approved binary are rejected
+    let (benchmark_id, submission_result) = run_benchmark_on(
+        &ctx,
+        PLAYER,
+        &code_id,
+        false,
+        CPUArchitecture::ARM64,
+        track_settings(None, 1000),
+    )
+    .await;
+    assert!(submission_result
+        .unwrap_err()
+        .to_string()
+        .ends_with("No approved ARM64 binary"));
+    assert!(ctx.get_proof_details(&benchmark_id).await.is_none());
+    assert!(ctx.get_fraud_state(&benchmark_id).await.is_none());
+    let (benchmark_id, submission_result) = run_benchmark_on(
+        &ctx,
+        PLAYER,
+        &code_id,
+        false,
+        CPUArchitecture::AMD64,
+        track_settings(None, 1000),
+    )
+    .await;
+    submission_result.unwrap().unwrap();
+    assert!(ctx.get_fraud_state(&benchmark_id).await.is_none());
+}
+
+#[tokio::test]
+async fn test_binary_failing_on_every_architecture() {
+    let ctx = setup().await;
+    let code_id = pushed_code(&ctx).await;
+    let so_by_arch = HashMap::from([
+        (CPUArchitecture::AMD64, FAILED),
+        (CPUArchitecture::ARM64, FAILED),
+    ]);
+    assert_eq!(
+        submit(&ctx, &code_id, so_by_arch.clone(), None)
+            .await
+            .unwrap_err()
+            .to_string(),
+        "A failed compilation has no download URL"
+    );
+    submit_binary(&ctx, code_id.clone(), None, so_by_arch, None, None)
+        .await
+        .unwrap();
+    add_block(&ctx).await.unwrap();
+
+    let binary = ctx.get_binary_details(&code_id).await.unwrap();
+    assert!(!binary.compile_success);
+    assert!(!binary.has_approved_binary(CPUArchitecture::AMD64));
+    assert!(ctx.get_code_state(&code_id).await.unwrap().banned);
+}
+
+#[test]
+fn test_legacy_binary_approved_on_every_architecture() {
+    // binaries recorded before artifacts were tracked per architecture
+    let binary = BinaryDetails {
+        compile_success: true,
+        download_url: Some("https://example.com/binary".to_string()),
+        so_by_arch: HashMap::new(),
+        ptx: None,
+        toolchain_version: None,
+    };
+    assert!(binary.has_approved_binary(CPUArchitecture::AMD64));
+    assert!(binary.has_approved_binary(CPUArchitecture::ARM64));
+    let binary = BinaryDetails {
+        compile_success: false,
+        ..binary
+    };
+    assert!(!binary.has_approved_binary(CPUArchitecture::AMD64));
+}
+
+#[tokio::test]
+async fn test_invalid_binary_artifacts() {
+    let ctx = setup().await;
+    let code_id = pushed_code(&ctx).await;
+    let so_hash = blake3_hex(b"amd64 binary");
+    let err = |result: anyhow::Result<()>| result.unwrap_err().to_string();
+
+    assert_eq!(
+        err(submit(
+            &ctx,
+            &code_id,
+            amd64(BinaryArtifact {
+                compile_success: true,
+                hash: None,
+            }),
+            None
+        )
+        .await),
+        "Missing AMD64 so hash"
+    );
+    assert_eq!(
+        err(submit(
+            &ctx,
+            &code_id,
+            amd64(BinaryArtifact {
+                compile_success: false,
+                hash: Some(so_hash.clone()),
+            }),
+            None
+        )
+        .await),
+        "A failed AMD64 so compilation has no hash"
+    );
+    assert_eq!(
+        err(submit(
+            &ctx,
+            &code_id,
+            amd64(BinaryArtifact {
+                compile_success: true,
+                hash: Some(so_hash.to_uppercase()),
+            }),
+            None
+        )
+        .await),
+        "Invalid AMD64 so hash. Must be a blake3 hex digest"
+    );
+    assert_eq!(
+        err(submit(
+            &ctx,
+            &code_id,
+            amd64(compiled_artifact(b"amd64 binary")),
+            Some(compiled_artifact(b"ptx"))
+        )
+        .await),
+        "Unexpected ptx for a CPU challenge"
+    );
+    assert_eq!(
+        err(submit_binary(
+            &ctx,
+            code_id.clone(),
+            Some("https://example.com/binary".to_string()),
+            amd64(compiled_artifact(b"amd64 binary")),
+            None,
+            None,
+        )
+        .await),
+        "Missing toolchain version"
+    );
+}
+
+#[tokio::test]
+async fn test_gpu_binary_requires_ptx() {
+    let mut config = test_config();
+    config.challenges.get_mut(CHALLENGE).unwrap().r#type = ChallengeType::GPU;
+    let ctx = setup_with(config, &[PLAYER]).await;
+    let code_id = pushed_code(&ctx).await;
+    let so_by_arch = amd64(compiled_artifact(b"amd64 binary"));
+
+    assert_eq!(
+        submit(&ctx, &code_id, so_by_arch.clone(), None)
+            .await
+            .unwrap_err()
+            .to_string(),
+        "Missing ptx for a GPU challenge"
+    );
+    assert_eq!(
+        submit(
+            &ctx,
+            &code_id,
+            so_by_arch.clone(),
+            Some(BinaryArtifact {
+                compile_success: true,
+                hash: Some("ptx".to_string()),
+            })
+        )
+        .await
+        .unwrap_err()
+        .to_string(),
+        "Invalid ptx hash. Must be a blake3 hex digest"
+    );
+    // a GPU code whose ptx failed to compile does not compile, whatever its architectures
+    assert_eq!(
+        submit(&ctx, &code_id, so_by_arch.clone(), Some(FAILED))
+            .await
+            .unwrap_err()
+            .to_string(),
+        "A failed compilation has no download URL"
+    );
+    submit(&ctx, &code_id, so_by_arch, Some(compiled_artifact(b"ptx")))
+        .await
+        .unwrap();
+    add_block(&ctx).await.unwrap();
+    let binary = ctx.get_binary_details(&code_id).await.unwrap();
+    assert!(binary.compile_success);
+    assert_eq!(binary.ptx.unwrap().hash, Some(blake3_hex(b"ptx")));
+}
@@ -0,0 +1,176 @@
+mod common;
+
+use common::*;
+use std::collections::HashMap;
+use tig_protocol::{
+    add_block, context::Context, memory::MemoryContext, submit_advance, submit_benchmark,
+    submit_code, submit_precommit,
+};
+use tig_structs::{config::*, core::*};
+
+const NEW_TRACK: &str = "t2";
+
+async fn latest_round(ctx: &impl Context) -> u32 {
+    let block_id = ctx.get_latest_block_id().await;
+    ctx.get_block_details(&block_id).await.unwrap().round
+}
+
+async fn add_blocks_until_round(ctx: &MemoryContext, round: u32) {
+    while latest_round(ctx).await < round {
+        add_block(ctx).await.unwrap();
+    }
+}
+
+async fn precommit(
+    ctx: &MemoryContext,
+    code_id: &str,
+    track_ids: &[&str],
+) -> anyhow::Result<String> {
+    let settings = BenchmarkSettings {
+        player_id: PLAYER.to_string(),
+        block_id: ctx.get_latest_block_id().await,
+        challenge_id: CHALLENGE.to_string(),
+        algorithm_id: code_id.to_string(),
+        track_id: String::new(),
+    };
+    let track_settings = track_ids
+        .iter()
+        .map(|track_id| {
+            (
+                track_id.to_string(),
+                TrackSettings {
+                    hyperparameters: None,
+                    fuel_budget: 1000,
+                    num_bundles: 2,
+                },
+            )
+        })
+        .collect();
+    submit_precommit(ctx, PLAYER.to_string(), settings, track_settings, 0).await
+}
+
+#[tokio::test]
+async fn test_retired_challenge() {
+    let mut config = test_config();
+    config.challenges.get_mut(CHALLENGE).unwrap().round_retired = Some(4);
+    let ctx = setup_with(config, &[PLAYER]).await;
+    let code_id = setup_active_code(&ctx).await;
+    let benchmark_id = run_benchmark(&ctx, PLAYER, &code_id, false).await;
+    add_blocks_until_round(&ctx, 2).await;
+    assert!(latest_active_ids(&ctx, ActiveType::Challenge)
+        .await
+        .contains(CHALLENGE));
+
+    // no new advances for a challenge retiring before their votes are tallied
+    let err = submit_advance(
+        &ctx,
+        PLAYER.to_string(),
+        "late_advance".to_string(),
+        CHALLENGE.to_string(),
+        "evidence".to_string(),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("Invalid challenge '{}'", CHALLENGE)
+    );
+
+    add_blocks_until_round(&ctx, 4).await;
+    assert!(!latest_active_ids(&ctx, ActiveType::Challenge)
+        .await
+        .contains(CHALLENGE));
+    assert!(!latest_active_ids(&ctx, ActiveType::Code)
+        .await
+        .contains(&code_id));
+    assert!(!latest_active_ids(&ctx, ActiveType::Benchmark)
+        .await
+        .contains(&benchmark_id));
+    let err = precommit(&ctx, &code_id, &[TRACK]).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("Invalid challenge '{}'", CHALLENGE)
+    );
+    let err = submit_code(
+        &ctx,
+        PLAYER.to_string(),
+        "late_code".to_string(),
+        CHALLENGE.to_string(),
+        None,
+        HashMap::new(),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!("Invalid challenge '{}'", CHALLENGE)
+    );
+}
+
+#[tokio::test]
+async fn test_track_change() {
+    let mut config = test_config();
+    let challenge_config = config.challenges.get_mut(CHALLENGE).unwrap();
+    challenge_config.track_changes = vec![TrackChange {
+        round_effective: 2,
+        active_tracks: HashMap::from([(
+            NEW_TRACK.to_string(),
+            TrackConfig {
+                num_nonces_per_bundle: NUM_NONCES_PER_BUNDLE,
+                min_active_quality: 0,
+            },
+        )]),
+    }];
+    let ctx = setup_with(config, &[PLAYER]).await;
+    let code_id = setup_active_code(&ctx).await;
+    let in_flight_id = precommit(&ctx, &code_id, &[TRACK]).await.unwrap();
+    add_blocks_until_round(&ctx, 2).await;
+
+    // qualifiers are counted on the tracks of the round
+    let block_id = ctx.get_latest_block_id().await;
+    let challenge_data = ctx
+        .get_challenge_block_data(&CHALLENGE.to_string(), &block_id)
+        .unwrap();
+    assert_eq!(
+        challenge_data
+            .num_qualifiers_by_track
+            .keys()
+            .collect::<Vec<_>>(),
+        vec![NEW_TRACK]
+    );
+
+    // precommits made before the change must be stopped, as their track no longer qualifies
+    let num_nonces = ctx
+        .get_precommit_details(&in_flight_id)
+        .await
+        .unwrap()
+        .num_nonces;
+    let submit = |stopped: bool| {
+        submit_benchmark(
+            &ctx,
+            PLAYER.to_string(),
+            in_flight_id.clone(),
+            stopped,
+            Some(merkle_tree(num_nonces).calc_merkle_root()),
+            Some(vec![0; num_nonces as usize]),
+            0,
+        )
+    };
+    let err = submit(false).await.unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with(&format!("Track '{}' is no longer active", TRACK)),
+        "{}",
+        err
+    );
+    submit(true).await.unwrap();
+
+    let err = precommit(&ctx, &code_id, &[TRACK]).await.unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("Must submit settings for all active tracks"));
+    let benchmark_id = precommit(&ctx, &code_id, &[NEW_TRACK]).await.unwrap();
+    add_block(&ctx).await.unwrap();
+    let settings = ctx.get_precommit_settings(&benchmark_id).await.unwrap();
+    assert_eq!(settings.track_id, NEW_TRACK);
+}
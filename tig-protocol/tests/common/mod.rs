@@ -0,0 +1,330 @@
+// helpers shared by the integration tests, not every test uses all of them
+#![allow(dead_code)]
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use tig_challenges::c001;
+use tig_protocol::{context::Context, memory::MemoryContext, *};
+use tig_structs::{config::*, core::*};
+use tig_utils::{blake3_hex, MerkleHash, MerkleTree, PreciseNumber};
+
+pub const PLAYER: &str = "player1";
+pub const CHALLENGE: &str = "c001";
+// a satisfiability track, so that solutions can be verified by the protocol
+pub const TRACK: &str = "n_vars=100,ratio=4200";
+pub const TRACK_NUM_VARIABLES: usize = 100;
+pub const NUM_NONCES_PER_BUNDLE: u64 = 4;
+pub const GOVERNANCE: &str = "governance";
+
+pub fn test_config() -> ProtocolConfig {
+    ProtocolConfig {
+        advances: AdvancesConfig {
+            bootstrap_address: "bootstrap".to_string(),
+            min_percent_yes_votes: 0.5,
+            vote_start_delay: 1,
+            vote_period: 1,
+            min_lock_period_to_vote: 1,
+            submission_fee: PreciseNumber::from(1),
+            adoption_threshold: 0.25,
+            merge_points_threshold: 5,
+        },
+        bans: Some(BansConfig {
+            governance_address: GOVERNANCE.to_string(),
+        }),
+        challenges: HashMap::from([(
+            CHALLENGE.to_string(),
+            ChallengeConfig {
+                name: "satisfiability".to_string(),
+                r#type: ChallengeType::CPU,
+                quality_type: QualityType::Continuous,
+                submission_delay_multiplier: 1.0,
+                num_samples_gte_average: 1,
+                num_samples_lt_average: 1,
+                lifespan_period: 20,
+                per_nonce_fee: PreciseNumber::from(1),
+                base_fee: PreciseNumber::from(1),
+                active_tracks: HashMap::from([(
+                    TRACK.to_string(),
+                    TrackConfig {
+                        num_nonces_per_bundle: NUM_NONCES_PER_BUNDLE,
+                        min_active_quality: 0,
+                    },
+                )]),
+                max_fuel_budget: 1_000_000,
+                max_qualifiers_per_track: 10,
+                legacy_multiplier_span: 1.0,
+                min_num_bundles: 1,
+                round_retired: None,
+                track_changes: Vec::new(),
+            },
+        )]),
+        codes: CodesConfig {
+            submission_fee: PreciseNumber::from(1),
+            adoption_threshold: 0.25,
+            merge_points_threshold: 5,
+            push_delay_period: 0,
+        },
+        deposits: DepositsConfig {
+            lock_address: "lock".to_string(),
+            min_lock_amount: PreciseNumber::from(1),
+            min_lock_period: 1,
+            lock_period_cap: 4,
+            max_reward_share: 0.5,
+            default_reward_share: 0.1,
+            reward_share_update_period: 1,
+            delegatees_update_period: 1,
+            max_delegations: 5,
+            token_locker_weight: 1,
+        },
+        erc20: ERC20Config {
+            rpc_url: String::new(),
+            chain_id: String::new(),
+            token_address: String::new(),
+        },
+        frauds: Some(FraudsConfig {
+            slash_fraction: 0.5,
+        }),
+        opow: OPoWConfig {
+            imbalance_multiplier: 3.0,
+            cutoff_phase_in_period: 10,
+            cutoff_multiplier: 2.0,
+            max_deposit_to_qualifier_ratio: 1.0,
+            challenge_factors_weight: 0.5,
+            max_coinbase_outputs: 5,
+            coinbase_update_period: 1,
+        },
+        rounds: RoundsConfig {
+            blocks_per_round: 10,
+            seconds_between_blocks: 60,
+        },
+        rewards: RewardsConfig {
+            gamma: GammaConfig {
+                a: 1.0,
+                b: 0.5,
+                c: 0.1,
+            },
+            distribution: DistributionConfig {
+                opow: 0.7,
+                codes: 0.15,
+                advances: 0.1,
+                challenge_owners: 0.05,
+            },
+            schedule: vec![EmissionsConfig {
+                block_reward: 100.0,
+                round_start: 0,
+            }],
+        },
+        submission_limits: None,
+        topups: TopUpsConfig {
+            topup_address: "topup".to_string(),
+            min_topup_amount: PreciseNumber::from(1),
+        },
+    }
+}
+
+pub async fn setup() -> MemoryContext {
+    setup_with(test_config(), &[PLAYER]).await
+}
+
+pub async fn setup_with(config: ProtocolConfig, player_ids: &[&str]) -> MemoryContext {
+    let ctx = MemoryContext::new(config);
+    for (i, player_id) in player_ids.iter().enumerate() {
+        ctx.add_topup_to_mempool(TopUpDetails {
+            player_id: player_id.to_string(),
+            tx_hash: "0xtopup".to_string(),
+            log_idx: i,
+            amount: PreciseNumber::from(1000),
+        })
+        .await
+        .unwrap();
+    }
+    add_block(&ctx).await.unwrap();
+    ctx
+}
+
+pub async fn setup_active_code(ctx: &MemoryContext) -> String {
+    setup_active_code_for(ctx, CHALLENGE).await
+}
+
+pub async fn setup_active_code_for(ctx: &MemoryContext, challenge_id: &str) -> String {
+    let code_id = submit_code(
+        ctx,
+        PLAYER.to_string(),
+        "test_code".to_string(),
+        challenge_id.to_string(),
+        None,
+        HashMap::new(),
+    )
+    .await
+    .unwrap();
+    add_block(ctx).await.unwrap();
+    submit_compiled_binary(ctx, &code_id).await.unwrap();
+    add_block(ctx).await.unwrap();
+    code_id
+}
+
+pub fn compiled_artifact(content: &[u8]) -> BinaryArtifact {
+    BinaryArtifact {
+        compile_success: true,
+        hash: Some(blake3_hex(content)),
+    }
+}
+
+// submits a binary of a CPU code that only compiled for amd64
+pub async fn submit_compiled_binary(ctx: &MemoryContext, code_id: &str) -> anyhow::Result<()> {
+    submit_binary(
+        ctx,
+        code_id.to_string(),
+        Some("https://example.com/binary".to_string()),
+        HashMap::from([
+            (CPUArchitecture::AMD64, compiled_artifact(b"amd64 binary")),
+            (
+                CPUArchitecture::ARM64,
+                BinaryArtifact {
+                    compile_success: false,
+                    hash: None,
+                },
+            ),
+        ]),
+        None,
+        Some("rustc 1.82.0".to_string()),
+    )
+    .await
+}
+
+pub fn output_data(nonce: u64) -> OutputData {
+    output_data_on(nonce, CPUArchitecture::AMD64)
+}
+
+// the solution assigns false to every variable, which does not satisfy instances of TRACK
+pub fn output_data_on(nonce: u64, cpu_arch: CPUArchitecture) -> OutputData {
+    let solution = c001::Solution {
+        variables: vec![false; TRACK_NUM_VARIABLES],
+    };
+    OutputData {
+        nonce,
+        runtime_signature: nonce * 7,
+        fuel_consumed: 100,
+        solution: serde_json::to_string(&solution).unwrap(),
+        cpu_arch,
+    }
+}
+
+pub fn merkle_tree(num_nonces: u64) -> MerkleTree {
+    merkle_tree_on(num_nonces, CPUArchitecture::AMD64)
+}
+
+pub fn merkle_tree_on(num_nonces: u64, cpu_arch: CPUArchitecture) -> MerkleTree {
+    let hashes = (0..num_nonces)
+        .map(|nonce| MerkleHash::from(OutputMetaData::from(output_data_on(nonce, cpu_arch))))
+        .collect::<Vec<_>>();
+    MerkleTree::new(hashes, (num_nonces as usize).next_power_of_two()).unwrap()
+}
+
+// precommits, benchmarks and proves a benchmark of 2 bundles. Returns the benchmark id
+pub async fn run_benchmark(
+    ctx: &MemoryContext,
+    player_id: &str,
+    code_id: &str,
+    tamper_proof: bool,
+) -> String {
+    let (benchmark_id, verification_result) = run_benchmark_on(
+        ctx,
+        player_id,
+        code_id,
+        tamper_proof,
+        CPUArchitecture::AMD64,
+        track_settings(None, 1000),
+    )
+    .await;
+    assert_eq!(verification_result.unwrap().is_ok(), !tamper_proof);
+    benchmark_id
+}
+
+pub fn track_settings(
+    hyperparameters: Option<Map<String, Value>>,
+    fuel_budget: u64,
+) -> TrackSettings {
+    TrackSettings {
+        hyperparameters,
+        fuel_budget,
+        num_bundles: 2,
+    }
+}
+
+// same as run_benchmark with nonces computed on cpu_arch and the given track settings. Also
+// returns the result of submitting the proof
+pub async fn run_benchmark_on(
+    ctx: &MemoryContext,
+    player_id: &str,
+    code_id: &str,
+    tamper_proof: bool,
+    cpu_arch: CPUArchitecture,
+    track_settings: TrackSettings,
+) -> (String, anyhow::Result<anyhow::Result<()>>) {
+    let settings = BenchmarkSettings {
+        player_id: player_id.to_string(),
+        block_id: ctx.get_latest_block_id().await,
+        challenge_id: CHALLENGE.to_string(),
+        algorithm_id: code_id.to_string(),
+        track_id: String::new(),
+    };
+    let track_settings = HashMap::from([(TRACK.to_string(), track_settings)]);
+    let benchmark_id = submit_precommit(ctx, player_id.to_string(), settings, track_settings, 0)
+        .await
+        .unwrap();
+    add_block(ctx).await.unwrap();
+
+    let num_nonces = ctx
+        .get_precommit_details(&benchmark_id)
+        .await
+        .unwrap()
+        .num_nonces;
+    let tree = merkle_tree_on(num_nonces, cpu_arch);
+    let solution_quality = (0..num_nonces as i32).map(|q| q * 10).collect();
+    submit_benchmark(
+        ctx,
+        player_id.to_string(),
+        benchmark_id.clone(),
+        false,
+        Some(tree.calc_merkle_root()),
+        Some(solution_quality),
+        0,
+    )
+    .await
+    .unwrap();
+    add_block(ctx).await.unwrap();
+
+    let sampled_nonces = ctx
+        .get_benchmark_details(&benchmark_id)
+        .await
+        .unwrap()
+        .sampled_nonces
+        .unwrap();
+    let merkle_proofs = sampled_nonces
+        .iter()
+        .map(|&nonce| {
+            let mut leaf = output_data_on(nonce, cpu_arch);
+            if tamper_proof {
+                leaf.solution = "tampered".to_string();
+            }
+            MerkleProof {
+                leaf,
+                branch: tree.calc_merkle_branch(nonce as usize).unwrap(),
+            }
+        })
+        .collect();
+    let submission_result = submit_proof(
+        ctx,
+        player_id.to_string(),
+        benchmark_id.clone(),
+        merkle_proofs,
+    )
+    .await;
+    add_block(ctx).await.unwrap();
+    (benchmark_id, submission_result)
+}
+
+pub async fn latest_active_ids(ctx: &MemoryContext, active_type: ActiveType) -> HashSet<String> {
+    let block_id = ctx.get_latest_block_id().await;
+    ctx.get_block_data(&block_id).unwrap().active_ids[&active_type].clone()
+}
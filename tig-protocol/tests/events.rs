@@ -0,0 +1,153 @@
+mod common;
+
+use common::*;
+use tig_protocol::{
+    add_block, context::Context, events::BlockEvent, memory::MemoryContext, set_vote,
+    submit_advance,
+};
+use tig_structs::core::*;
+use tig_utils::PreciseNumber;
+
+async fn add_blocks(ctx: &MemoryContext, num_blocks: u32) -> Vec<BlockEvent> {
+    let mut events = Vec::new();
+    for _ in 0..num_blocks {
+        events.extend(add_block(ctx).await.unwrap());
+    }
+    events
+}
+
+async fn latest_round(ctx: &MemoryContext) -> u32 {
+    let block_id = ctx.get_latest_block_id().await;
+    ctx.get_block_details(&block_id).await.unwrap().round
+}
+
+#[tokio::test]
+async fn test_benchmarker_events() {
+    let ctx = setup().await;
+    let code_id = setup_active_code(&ctx).await;
+    run_benchmark(&ctx, PLAYER, &code_id, false).await;
+    let events = add_blocks(&ctx, 3).await;
+
+    // emitted once, as the qualifying bundles are unchanged in the following blocks
+    let num_bundles = events
+        .iter()
+        .filter_map(|e| match e {
+            BlockEvent::PlayerQualified {
+                player_id,
+                challenge_id,
+                track_id,
+                num_bundles,
+            } if player_id == PLAYER && challenge_id == CHALLENGE && track_id == TRACK => {
+                Some(*num_bundles)
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(num_bundles.len(), 1);
+    assert!(num_bundles[0] > 0);
+    let cutoff = ctx
+        .get_opow_block_data(&PLAYER.to_string(), &ctx.get_latest_block_id().await)
+        .unwrap()
+        .cutoff;
+    assert!(events.iter().any(|e| matches!(
+        e,
+        BlockEvent::CutoffChanged { player_id, prev_cutoff: 0, cutoff: c }
+            if player_id == PLAYER && *c == cutoff
+    )));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        BlockEvent::RewardPaid(payout)
+            if payout.r#type == EmissionsType::Benchmarker && payout.player_id == PLAYER
+    )));
+
+    // events serialise tagged by kind
+    let event = serde_json::to_value(events.last().unwrap()).unwrap();
+    assert!(event["event"].is_string());
+}
+
+#[tokio::test]
+async fn test_player_leaving_opow_events() {
+    let ctx = setup().await;
+    let code_id = setup_active_code(&ctx).await;
+    run_benchmark(&ctx, PLAYER, &code_id, false).await;
+    let lifespan_period = test_config().challenges[CHALLENGE].lifespan_period;
+    let events = add_blocks(&ctx, lifespan_period).await;
+    assert!(ctx
+        .get_opow_block_data(&PLAYER.to_string(), &ctx.get_latest_block_id().await)
+        .is_none());
+
+    // the benchmark's lifespan ended, so the player's cutoff and qualifiers drop to 0
+    assert!(events.iter().any(|e| matches!(
+        e,
+        BlockEvent::CutoffChanged { player_id, prev_cutoff, cutoff: 0 }
+            if player_id == PLAYER && *prev_cutoff > 0
+    )));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        BlockEvent::PlayerQualified { player_id, track_id, num_bundles: 0, .. }
+            if player_id == PLAYER && track_id == TRACK
+    )));
+}
+
+#[tokio::test]
+async fn test_advance_events() {
+    let ctx = setup().await;
+    ctx.add_deposit_to_mempool(DepositDetails {
+        player_id: PLAYER.to_string(),
+        tx_hash: "0xdeposit".to_string(),
+        log_idx: 0,
+        amount: PreciseNumber::from(100),
+        r#type: DepositType::Lock { eth_block_num: 0 },
+    })
+    .await
+    .unwrap();
+    let advance_id = submit_advance(
+        &ctx,
+        PLAYER.to_string(),
+        "advance".to_string(),
+        CHALLENGE.to_string(),
+        "evidence".to_string(),
+    )
+    .await
+    .unwrap();
+    add_block(&ctx).await.unwrap();
+    let advance_state = ctx.get_advance_state(&advance_id).await.unwrap();
+    while latest_round(&ctx).await < advance_state.round_voting_starts {
+        add_block(&ctx).await.unwrap();
+    }
+    set_vote(&ctx, PLAYER.to_string(), advance_id.clone(), true)
+        .await
+        .unwrap();
+
+    let mut events = Vec::new();
+    while latest_round(&ctx).await < advance_state.round_votes_tallied {
+        events.extend(add_block(&ctx).await.unwrap());
+    }
+    let tallies = events
+        .iter()
+        .filter_map(|e| match e {
+            BlockEvent::VoteTallied {
+                advance_id: id,
+                yes,
+                no,
+                passed,
+            } if *id == advance_id => Some((*yes, *no, *passed)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(tallies.len(), 1);
+    let (yes, no, passed) = tallies[0];
+    assert!(yes > PreciseNumber::from(0));
+    assert_eq!(no, PreciseNumber::from(0));
+    assert!(passed);
+    let round_active = ctx
+        .get_advance_state(&advance_id)
+        .await
+        .unwrap()
+        .round_active
+        .unwrap();
+    assert!(events.contains(&BlockEvent::AdvanceActivated {
+        advance_id,
+        round_active,
+    }));
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use common::*;
+use tig_protocol::{add_block, context::Context, memory::MemoryContext, submit_fraud};
+use tig_structs::core::*;
+use tig_utils::PreciseNumber;
+
+const ALLEGER: &str = "player2";
+
+// an active benchmark by PLAYER, reporting quality `nonce * 10` for each nonce. Its solutions do
+// not satisfy their instance, so only the quality of nonce 0 is honest
+async fn setup_benchmark() -> (MemoryContext, String) {
+    let ctx = setup_with(test_config(), &[PLAYER, ALLEGER]).await;
+    let code_id = setup_active_code(&ctx).await;
+    let benchmark_id = run_benchmark(&ctx, PLAYER, &code_id, false).await;
+    (ctx, benchmark_id)
+}
+
+async fn evidence(ctx: &MemoryContext, benchmark_id: &String, nonce: u64) -> MerkleProof {
+    let num_nonces = ctx
+        .get_precommit_details(benchmark_id)
+        .await
+        .unwrap()
+        .num_nonces;
+    MerkleProof {
+        leaf: output_data(nonce),
+        branch: merkle_tree(num_nonces)
+            .calc_merkle_branch(nonce as usize)
+            .unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn test_honest_benchmark() {
+    let (ctx, benchmark_id) = setup_benchmark().await;
+    let merkle_proof = evidence(&ctx, &benchmark_id, 0).await;
+    let result = submit_fraud(
+        &ctx,
+        ALLEGER.to_string(),
+        benchmark_id.clone(),
+        merkle_proof,
+    )
+    .await;
+    assert!(result.unwrap_err().to_string().contains("No fraud found"));
+    assert_eq!(ctx.mempool_len(), 0);
+}
+
+#[tokio::test]
+async fn test_invalid_evidence() {
+    let (ctx, benchmark_id) = setup_benchmark().await;
+
+    // evidence must match the benchmark's merkle root
+    let mut merkle_proof = evidence(&ctx, &benchmark_id, 3).await;
+    merkle_proof.leaf.solution = "other".to_string();
+    assert!(submit_fraud(
+        &ctx,
+        ALLEGER.to_string(),
+        benchmark_id.clone(),
+        merkle_proof
+    )
+    .await
+    .is_err());
+
+    let merkle_proof = evidence(&ctx, &benchmark_id, 3).await;
+    assert!(submit_fraud(
+        &ctx,
+        "unknown".to_string(),
+        benchmark_id.clone(),
+        merkle_proof.clone()
+    )
+    .await
+    .is_err());
+    assert!(submit_fraud(
+        &ctx,
+        ALLEGER.to_string(),
+        "unknown".to_string(),
+        merkle_proof
+    )
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn test_fraud_consequences() {
+    let (ctx, benchmark_id) = setup_benchmark().await;
+    loop {
+        add_block(&ctx).await.unwrap();
+        if latest_active_ids(&ctx, ActiveType::Benchmark)
+            .await
+            .contains(&benchmark_id)
+        {
+            break;
+        }
+    }
+
+    let merkle_proof = evidence(&ctx, &benchmark_id, 3).await;
+    let allegation = submit_fraud(
+        &ctx,
+        ALLEGER.to_string(),
+        benchmark_id.clone(),
+        merkle_proof.clone(),
+    )
+    .await
+    .unwrap();
+    assert!(allegation.contains("Invalid quality for nonce 3. Submitted 30, verified 0"));
+    assert!(allegation.contains(ALLEGER));
+    assert!(submit_fraud(
+        &ctx,
+        ALLEGER.to_string(),
+        benchmark_id.clone(),
+        merkle_proof
+    )
+    .await
+    .is_err());
+
+    let player_state = ctx.get_player_state(&PLAYER.to_string()).await.unwrap();
+    add_block(&ctx).await.unwrap();
+
+    assert!(ctx.get_fraud_state(&benchmark_id).await.is_some());
+    assert_eq!(
+        ctx.get_fraud(&benchmark_id).unwrap().allegation,
+        Some(allegation)
+    );
+    assert!(!latest_active_ids(&ctx, ActiveType::Benchmark)
+        .await
+        .contains(&benchmark_id));
+    let block_id = ctx.get_latest_block_id().await;
+    assert!(
+        ctx.get_block_data(&block_id).unwrap().confirmed_ids[&TxType::Fraud]
+            .contains(&benchmark_id)
+    );
+    // the slashed fee balance is counted as fees paid
+    let slashed = player_state.available_fee_balance * PreciseNumber::from_f64(0.5);
+    let slashed_state = ctx.get_player_state(&PLAYER.to_string()).await.unwrap();
+    assert_eq!(
+        slashed_state.available_fee_balance,
+        player_state.available_fee_balance - slashed
+    );
+    assert_eq!(
+        slashed_state.total_fees_paid,
+        player_state.total_fees_paid + slashed
+    );
+}
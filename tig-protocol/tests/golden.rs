@@ -0,0 +1,143 @@
+// Golden scenarios: the emissions, influences, cutoffs and rewards of the players over blocks
+// recorded with the memory context must match the recorded values. Only these audited values are
+// stored, keyed by player, so a golden changes only when the rewards do.
+//
+// After an intended change to the contracts, regenerate the files with
+// `UPDATE_GOLDEN=1 cargo test -p tig-protocol --test golden` and review the diff.
+mod common;
+
+use common::*;
+use serde_json::{json, Map, Value};
+use std::{collections::HashMap, path::PathBuf};
+use tig_protocol::{
+    add_block, add_block_with_snapshot, context::AddBlockCache, context::Context, set_delegatees,
+    set_reward_share,
+};
+use tig_structs::core::*;
+use tig_utils::PreciseNumber;
+
+const NUM_BLOCKS: usize = 3;
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.json", name))
+}
+
+fn audited_values(cache: &AddBlockCache) -> Value {
+    let players = cache
+        .active_players_block_data
+        .iter()
+        .map(|(player_id, player_data)| {
+            let opow_data = cache.active_opow_block_data.get(player_id);
+            (
+                player_id.clone(),
+                json!({
+                    "influence": opow_data.map(|d| d.influence),
+                    "cutoff": opow_data.map(|d| d.cutoff),
+                    "reward_by_type": player_data.reward_by_type,
+                }),
+            )
+        })
+        .collect::<Map<String, Value>>();
+    sort_keys(json!({
+        "height": cache.block_details.height,
+        "emissions": cache.block_details.emissions,
+        "players": players,
+    }))
+}
+
+async fn check_golden(name: &str, outputs: Vec<AddBlockCache>) {
+    let path = golden_path(name);
+    let actual = outputs.iter().map(audited_values).collect::<Vec<_>>();
+    if std::env::var("UPDATE_GOLDEN").is_ok() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+    }
+    let golden = serde_json::from_str::<Vec<Value>>(
+        &std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e)),
+    )
+    .unwrap();
+    assert_eq!(golden.len(), NUM_BLOCKS);
+    for (expected, actual) in golden.iter().zip(actual.iter()) {
+        assert_eq!(expected, actual, "{} block {}", name, actual["height"]);
+    }
+}
+
+// tig-utils enables serde_json's preserve_order, so maps keep the random order of the hashmaps
+// they were serialised from
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries = map.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, sort_keys(v)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
+
+async fn record(ctx: &impl Context) -> Vec<AddBlockCache> {
+    let mut outputs = Vec::new();
+    for _ in 0..NUM_BLOCKS {
+        outputs.push(add_block_with_snapshot(ctx).await.unwrap().output.unwrap());
+    }
+    outputs
+}
+
+#[tokio::test]
+async fn test_golden_benchmarkers() {
+    let player_ids = [PLAYER, "player2"];
+    let ctx = setup_with(test_config(), &player_ids).await;
+    let code_id = setup_active_code(&ctx).await;
+    for player_id in player_ids {
+        run_benchmark(&ctx, player_id, &code_id, false).await;
+    }
+    check_golden("benchmarkers", record(&ctx).await).await;
+}
+
+#[tokio::test]
+async fn test_golden_delegation() {
+    let delegator = "player3";
+    let player_ids = [PLAYER, "player2", delegator];
+    let ctx = setup_with(test_config(), &player_ids).await;
+    for (i, (player_id, amount)) in [(PLAYER, 100), (delegator, 300)].into_iter().enumerate() {
+        ctx.add_deposit_to_mempool(DepositDetails {
+            player_id: player_id.to_string(),
+            tx_hash: "0xdeposit".to_string(),
+            log_idx: i,
+            amount: PreciseNumber::from(amount),
+            r#type: DepositType::Lock { eth_block_num: 0 },
+        })
+        .await
+        .unwrap();
+    }
+    add_block(&ctx).await.unwrap();
+    set_delegatees(
+        &ctx,
+        delegator.to_string(),
+        HashMap::from([(PLAYER.to_string(), 0.6), ("player2".to_string(), 0.4)]),
+        None,
+    )
+    .await
+    .unwrap();
+    set_reward_share(&ctx, PLAYER.to_string(), 0.3, None)
+        .await
+        .unwrap();
+    let code_id = setup_active_code(&ctx).await;
+    for player_id in [PLAYER, "player2"] {
+        run_benchmark(&ctx, player_id, &code_id, false).await;
+    }
+
+    let outputs = record(&ctx).await;
+    let emissions = &outputs.last().unwrap().block_details.emissions;
+    assert!(emissions[&EmissionsType::Delegator] > PreciseNumber::from(0));
+    check_golden("delegation", outputs).await;
+}
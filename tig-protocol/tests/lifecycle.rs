@@ -0,0 +1,206 @@
+mod common;
+
+use common::*;
+use std::collections::HashMap;
+use tig_protocol::{
+    add_block, add_block_with_snapshot, context::Context, get_lifecycle, memory::MemoryContext,
+    set_vote, submit_advance, submit_binary, submit_code, update_block_cache,
+};
+use tig_structs::core::*;
+use tig_utils::PreciseNumber;
+use LifecycleStage::*;
+
+async fn latest_round(ctx: &impl Context) -> u32 {
+    let block_id = ctx.get_latest_block_id().await;
+    ctx.get_block_details(&block_id).await.unwrap().round
+}
+
+async fn add_blocks_until_round(ctx: &MemoryContext, round: u32) {
+    while latest_round(ctx).await < round {
+        add_block(ctx).await.unwrap();
+    }
+}
+
+async fn stage(ctx: &MemoryContext, algorithm_id: &str) -> LifecycleStage {
+    get_lifecycle(ctx, algorithm_id.to_string())
+        .await
+        .unwrap()
+        .stage
+}
+
+fn stages(lifecycle: &Lifecycle) -> Vec<(LifecycleStage, u32)> {
+    lifecycle.0.iter().map(|t| (t.stage, t.round)).collect()
+}
+
+async fn submit_test_code(ctx: &MemoryContext) -> String {
+    let code_id = submit_code(
+        ctx,
+        PLAYER.to_string(),
+        "test_code".to_string(),
+        CHALLENGE.to_string(),
+        None,
+        HashMap::new(),
+    )
+    .await
+    .unwrap();
+    add_block(ctx).await.unwrap();
+    code_id
+}
+
+#[tokio::test]
+async fn test_code_lifecycle() {
+    let mut config = test_config();
+    config.codes.push_delay_period = 1;
+    let ctx = setup_with(config, &[PLAYER]).await;
+    let round = latest_round(&ctx).await;
+    let code_id = submit_test_code(&ctx).await;
+    assert_eq!(stage(&ctx, &code_id).await, Submitted);
+
+    submit_compiled_binary(&ctx, &code_id).await.unwrap();
+    add_block(&ctx).await.unwrap();
+    // the code becomes active once pushed
+    assert_eq!(stage(&ctx, &code_id).await, Submitted);
+    add_blocks_until_round(&ctx, round + 1).await;
+    assert_eq!(stage(&ctx, &code_id).await, Active);
+
+    // the only code of the challenge is adopted by every benchmark, and merged at the end of
+    // the round in which it gathers enough merge points
+    run_benchmark(&ctx, PLAYER, &code_id, false).await;
+    add_blocks_until_round(&ctx, round + 3).await;
+    let lifecycle = get_lifecycle(&ctx, code_id.clone()).await.unwrap();
+    assert_eq!(lifecycle.stage, Merged);
+    let round_merged = ctx
+        .get_code_state(&code_id)
+        .await
+        .unwrap()
+        .round_merged
+        .unwrap();
+    assert_eq!(
+        stages(&lifecycle.lifecycle),
+        vec![
+            (Submitted, round),
+            (Pushed, round + 1),
+            (Active, round + 1),
+            (Merged, round_merged),
+        ]
+    );
+
+    // merged codes stay merged in later rounds
+    add_blocks_until_round(&ctx, round_merged + 1).await;
+    let code_state = ctx.get_code_state(&code_id).await.unwrap();
+    assert_eq!(code_state.round_merged, Some(round_merged));
+    assert_eq!(code_state.lifecycle.latest(), Some(Merged));
+}
+
+#[tokio::test]
+async fn test_code_failing_to_compile_is_banned() {
+    let ctx = setup().await;
+    let code_id = submit_test_code(&ctx).await;
+    submit_binary(&ctx, code_id.clone(), None, HashMap::new(), None, None)
+        .await
+        .unwrap();
+    add_block(&ctx).await.unwrap();
+    assert_eq!(stage(&ctx, &code_id).await, Banned);
+    let code_state = ctx.get_code_state(&code_id).await.unwrap();
+    assert!(code_state.banned);
+    assert_eq!(code_state.round_active, None);
+    assert_eq!(code_state.bans[0].reason, "Failed to compile");
+}
+
+#[tokio::test]
+async fn test_advance_lifecycle() {
+    let ctx = setup().await;
+    ctx.add_deposit_to_mempool(DepositDetails {
+        player_id: PLAYER.to_string(),
+        tx_hash: "0xdeposit".to_string(),
+        log_idx: 0,
+        amount: PreciseNumber::from(100),
+        r#type: DepositType::Lock { eth_block_num: 0 },
+    })
+    .await
+    .unwrap();
+    let mut advance_ids = Vec::new();
+    for name in ["accepted", "rejected"] {
+        advance_ids.push(
+            submit_advance(
+                &ctx,
+                PLAYER.to_string(),
+                name.to_string(),
+                CHALLENGE.to_string(),
+                "evidence".to_string(),
+            )
+            .await
+            .unwrap(),
+        );
+    }
+    add_block(&ctx).await.unwrap();
+    let advance_state = ctx.get_advance_state(&advance_ids[0]).await.unwrap();
+    assert_eq!(stage(&ctx, &advance_ids[0]).await, Pushed);
+
+    add_blocks_until_round(&ctx, advance_state.round_voting_starts).await;
+    set_vote(&ctx, PLAYER.to_string(), advance_ids[0].clone(), true)
+        .await
+        .unwrap();
+    set_vote(&ctx, PLAYER.to_string(), advance_ids[1].clone(), false)
+        .await
+        .unwrap();
+    add_blocks_until_round(&ctx, advance_state.round_votes_tallied).await;
+    assert_eq!(stage(&ctx, &advance_ids[0]).await, Active);
+    assert_eq!(stage(&ctx, &advance_ids[1]).await, Expired);
+    let lifecycle = get_lifecycle(&ctx, advance_ids[1].clone())
+        .await
+        .unwrap()
+        .lifecycle;
+    assert_eq!(
+        stages(&lifecycle),
+        vec![
+            (Submitted, advance_state.round_submitted),
+            (Pushed, advance_state.round_submitted),
+            (Expired, advance_state.round_votes_tallied),
+        ]
+    );
+
+    assert!(get_lifecycle(&ctx, "c001_x999".to_string()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_legacy_lifecycle_is_backfilled() {
+    let ctx = setup().await;
+    let code_id = setup_active_code(&ctx).await;
+    let mut cache = add_block_with_snapshot(&ctx).await.unwrap().input;
+    let code_state = cache.active_codes_state.get_mut(&code_id).unwrap();
+    code_state.lifecycle = Lifecycle::default();
+    code_state.round_merged = Some(code_state.round_pushed + 1);
+    let code_state = code_state.clone();
+
+    // a block is added with a lifecycle recorded before lifecycles were
+    update_block_cache(&mut cache).await.unwrap();
+    assert_eq!(
+        stages(&cache.active_codes_state[&code_id].lifecycle),
+        vec![
+            (Submitted, code_state.round_submitted),
+            (Pushed, code_state.round_pushed),
+            (Active, code_state.round_active.unwrap()),
+            (Merged, code_state.round_pushed + 1),
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_transitions() {
+    let mut lifecycle = Lifecycle::default();
+    assert!(lifecycle.transition(Pushed, 1).is_err());
+    lifecycle.transition(Submitted, 1).unwrap();
+    lifecycle.transition(Pushed, 2).unwrap();
+    assert!(lifecycle.transition(Merged, 2).is_err());
+    assert!(lifecycle.transition(Active, 1).is_err());
+    lifecycle.transition(Active, 2).unwrap();
+    lifecycle.transition(Banned, 3).unwrap();
+    for stage in [Submitted, Pushed, Active, Merged, Banned, Expired] {
+        assert!(lifecycle.transition(stage, 4).is_err());
+    }
+    assert_eq!(lifecycle.stage_at(0), None);
+    assert_eq!(lifecycle.stage_at(1), Some(Submitted));
+    assert_eq!(lifecycle.stage_at(2), Some(Active));
+    assert_eq!(lifecycle.stage_at(10), Some(Banned));
+}
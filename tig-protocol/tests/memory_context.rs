@@ -0,0 +1,228 @@
+mod common;
+
+use common::*;
+use std::collections::HashMap;
+use tig_protocol::{context::Context, memory::MemoryContext, *};
+use tig_structs::core::*;
+use tig_utils::PreciseNumber;
+
+#[tokio::test]
+async fn test_genesis_block() {
+    let ctx = MemoryContext::new(test_config());
+    let block_id = ctx.get_latest_block_id().await;
+    let details = ctx.get_block_details(&block_id).await.unwrap();
+    assert_eq!(details.height, 0);
+    assert_eq!(details.round, 1);
+    assert_eq!(
+        ctx.get_challenge_state(&CHALLENGE.to_string())
+            .await
+            .unwrap()
+            .round_active,
+        1
+    );
+
+    add_block(&ctx).await.unwrap();
+    let next_block_id = ctx.get_latest_block_id().await;
+    let next_details = ctx.get_block_details(&next_block_id).await.unwrap();
+    assert_ne!(next_block_id, block_id);
+    assert_eq!(next_details.prev_block_id, block_id);
+    assert_eq!(next_details.height, 1);
+    assert_eq!(next_details.timestamp, 60);
+    assert!(latest_active_ids(&ctx, ActiveType::Challenge)
+        .await
+        .contains(CHALLENGE));
+}
+
+#[tokio::test]
+async fn test_mempool_confirmation() {
+    let ctx = MemoryContext::new(test_config());
+    let topup = TopUpDetails {
+        player_id: PLAYER.to_string(),
+        tx_hash: "0xtopup".to_string(),
+        log_idx: 0,
+        amount: PreciseNumber::from(1000),
+    };
+    ctx.add_topup_to_mempool(topup.clone()).await.unwrap();
+    assert!(ctx.add_topup_to_mempool(topup).await.is_err());
+    assert_eq!(ctx.mempool_len(), 1);
+    assert!(ctx.get_player_state(&PLAYER.to_string()).await.is_none());
+
+    add_block(&ctx).await.unwrap();
+    assert_eq!(ctx.mempool_len(), 0);
+    let player_state = ctx.get_player_state(&PLAYER.to_string()).await.unwrap();
+    assert_eq!(
+        player_state.available_fee_balance,
+        PreciseNumber::from(1000)
+    );
+    let block_id = ctx.get_latest_block_id().await;
+    let block_data = ctx.get_block_data(&block_id).unwrap();
+    assert!(block_data.confirmed_ids[&TxType::TopUp].contains("0xtopup_0"));
+
+    // fees are deducted on entering the mempool, the code is visible once confirmed
+    let code_id = submit_code(
+        &ctx,
+        PLAYER.to_string(),
+        "test_code".to_string(),
+        CHALLENGE.to_string(),
+        None,
+        HashMap::new(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(code_id, "c001_a001");
+    assert!(ctx.get_code_state(&code_id).await.is_none());
+    let player_state = ctx.get_player_state(&PLAYER.to_string()).await.unwrap();
+    assert_eq!(player_state.available_fee_balance, PreciseNumber::from(999));
+    assert_eq!(player_state.total_fees_paid, PreciseNumber::from(1));
+
+    add_block(&ctx).await.unwrap();
+    let code_state = ctx.get_code_state(&code_id).await.unwrap();
+    assert_eq!(code_state.block_confirmed, 2);
+    assert_eq!(code_state.round_active, None);
+    assert!(!latest_active_ids(&ctx, ActiveType::Code)
+        .await
+        .contains(&code_id));
+}
+
+#[tokio::test]
+async fn test_code_activation() {
+    let ctx = setup().await;
+    let code_id = setup_active_code(&ctx).await;
+    let code_state = ctx.get_code_state(&code_id).await.unwrap();
+    assert_eq!(code_state.round_active, Some(1));
+    assert!(latest_active_ids(&ctx, ActiveType::Code)
+        .await
+        .contains(&code_id));
+    let block_id = ctx.get_latest_block_id().await;
+    assert!(ctx.get_code_block_data(&code_id, &block_id).is_some());
+}
+
+#[tokio::test]
+async fn test_benchmark_lifecycle() {
+    let ctx = setup().await;
+    let code_id = setup_active_code(&ctx).await;
+    let benchmark_id = run_benchmark(&ctx, PLAYER, &code_id, false).await;
+
+    let proof_details = ctx.get_proof_details(&benchmark_id).await.unwrap();
+    let proof_state = ctx.get_proof_state(&benchmark_id).await.unwrap();
+    let precommit_details = ctx.get_precommit_details(&benchmark_id).await.unwrap();
+    assert_eq!(
+        proof_details.submission_delay,
+        proof_state.block_confirmed - precommit_details.block_started
+    );
+    assert_eq!(
+        proof_details.block_active,
+        proof_state.block_confirmed + proof_details.submission_delay
+    );
+
+    // benchmark becomes active after the submission delay
+    assert!(!latest_active_ids(&ctx, ActiveType::Benchmark)
+        .await
+        .contains(&benchmark_id));
+    loop {
+        add_block(&ctx).await.unwrap();
+        let block_id = ctx.get_latest_block_id().await;
+        let height = ctx.get_block_details(&block_id).await.unwrap().height;
+        if height >= proof_details.block_active {
+            break;
+        }
+    }
+    assert!(latest_active_ids(&ctx, ActiveType::Benchmark)
+        .await
+        .contains(&benchmark_id));
+    assert!(latest_active_ids(&ctx, ActiveType::OPoW)
+        .await
+        .contains(PLAYER));
+    let block_id = ctx.get_latest_block_id().await;
+    assert!(ctx
+        .get_opow_block_data(&PLAYER.to_string(), &block_id)
+        .is_some());
+
+    // benchmark expires after its lifespan
+    let lifespan_period = test_config().challenges[CHALLENGE].lifespan_period;
+    loop {
+        add_block(&ctx).await.unwrap();
+        let block_id = ctx.get_latest_block_id().await;
+        let height = ctx.get_block_details(&block_id).await.unwrap().height;
+        if height >= precommit_details.block_started + lifespan_period {
+            break;
+        }
+    }
+    assert!(!latest_active_ids(&ctx, ActiveType::Benchmark)
+        .await
+        .contains(&benchmark_id));
+}
+
+#[tokio::test]
+async fn test_fraudulent_proof() {
+    let ctx = setup().await;
+    let code_id = setup_active_code(&ctx).await;
+    let benchmark_id = run_benchmark(&ctx, PLAYER, &code_id, true).await;
+
+    let fraud = ctx.get_fraud(&benchmark_id).unwrap();
+    assert!(fraud.allegation.is_some());
+    let block_id = ctx.get_latest_block_id().await;
+    let block_data = ctx.get_block_data(&block_id).unwrap();
+    assert!(block_data.confirmed_ids[&TxType::Fraud].contains(&benchmark_id));
+
+    for _ in 0..5 {
+        add_block(&ctx).await.unwrap();
+        assert!(!latest_active_ids(&ctx, ActiveType::Benchmark)
+            .await
+            .contains(&benchmark_id));
+    }
+}
+
+#[tokio::test]
+async fn test_player_settings() {
+    let ctx = setup().await;
+    set_reward_share(&ctx, PLAYER.to_string(), 0.2, None)
+        .await
+        .unwrap();
+    let player_state = ctx.get_player_state(&PLAYER.to_string()).await.unwrap();
+    let reward_share = player_state.reward_share.latest().unwrap();
+    assert_eq!(reward_share.value, 0.2);
+    assert_eq!(reward_share.block_set, 1);
+    assert_eq!(reward_share.block_effective, 2);
+
+    assert!(ctx
+        .set_player_reward_share("unknown".to_string(), 0.2, 2)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_qualifier_selection_is_deterministic() {
+    // a single qualifier per track, contested by identical benchmarks
+    let mut config = test_config();
+    config
+        .challenges
+        .get_mut(CHALLENGE)
+        .unwrap()
+        .max_qualifiers_per_track = 1;
+    let player_ids = ["player1", "player2", "player3"];
+
+    let mut histories = Vec::new();
+    for _ in 0..3 {
+        let ctx = setup_with(config.clone(), &player_ids).await;
+        let code_id = setup_active_code(&ctx).await;
+        for player_id in player_ids {
+            run_benchmark(&ctx, player_id, &code_id, false).await;
+        }
+        let mut history = Vec::new();
+        for _ in 0..5 {
+            add_block(&ctx).await.unwrap();
+            let block_id = ctx.get_latest_block_id().await;
+            let opow_data = player_ids
+                .iter()
+                .map(|player_id| ctx.get_opow_block_data(&player_id.to_string(), &block_id))
+                .collect::<Vec<_>>();
+            history.push((block_id, opow_data));
+        }
+        histories.push(history);
+    }
+
+    let (_, last_opow_data) = histories[0].last().unwrap();
+    assert!(last_opow_data.iter().all(|d| d.is_some()));
+    assert!(histories.iter().all(|h| *h == histories[0]));
+}
@@ -0,0 +1,434 @@
+mod common;
+
+use common::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::HashMap;
+use tig_protocol::{
+    add_block, add_block_with_snapshot, context::AddBlockCache, update_block_cache,
+    verify_emissions_audit,
+};
+use tig_structs::core::*;
+use tig_utils::PreciseNumber;
+
+const NUM_CASES: u64 = 32;
+const OTHER_CHALLENGE: &str = "c002";
+const PLAYER_IDS: [&str; 4] = [PLAYER, "player2", "player3", "player4"];
+
+// cache of a block where every player has active benchmarks, with an active code on each of two
+// challenges. Returns the cache and the code id by challenge
+async fn base_cache() -> (AddBlockCache, HashMap<String, String>) {
+    let mut config = test_config();
+    let challenge_config = config.challenges[CHALLENGE].clone();
+    config
+        .challenges
+        .insert(OTHER_CHALLENGE.to_string(), challenge_config);
+    let ctx = setup_with(config, &PLAYER_IDS).await;
+    let code_id_by_challenge = HashMap::from([
+        (CHALLENGE.to_string(), setup_active_code(&ctx).await),
+        (
+            OTHER_CHALLENGE.to_string(),
+            setup_active_code_for(&ctx, OTHER_CHALLENGE).await,
+        ),
+    ]);
+    for player_id in PLAYER_IDS {
+        run_benchmark(&ctx, player_id, &code_id_by_challenge[CHALLENGE], false).await;
+    }
+    // the last proof becomes active after its submission delay
+    for _ in 0..3 {
+        add_block(&ctx).await.unwrap();
+    }
+    let snapshot = add_block_with_snapshot(&ctx).await.unwrap();
+    assert_eq!(
+        snapshot.output.as_ref().unwrap().block_data.active_ids[&ActiveType::OPoW].len(),
+        PLAYER_IDS.len()
+    );
+    (snapshot.input, code_id_by_challenge)
+}
+
+// randomises the bundles, deposits, delegations and reward shares of the base cache
+fn random_cache(
+    base: &AddBlockCache,
+    code_id_by_challenge: &HashMap<String, String>,
+    rng: &mut StdRng,
+) -> AddBlockCache {
+    let mut cache = base.clone();
+
+    // benchmarks are active throughout the round of the cache, whatever its height
+    let mut template = base.recent_benchmarks.values().next().unwrap().clone();
+    let round_start = (base.block_details.round - 1) * base.config.rounds.blocks_per_round;
+    template.precommit_details.block_started = round_start;
+    template.proof_details.as_mut().unwrap().block_active = round_start;
+    cache.recent_benchmarks.clear();
+    for player_id in PLAYER_IDS {
+        for (challenge_id, code_id) in code_id_by_challenge.iter() {
+            let num_bundles = rng.gen_range(0..6);
+            if num_bundles == 0 {
+                continue;
+            }
+            let mut benchmark = template.clone();
+            benchmark.settings = BenchmarkSettings {
+                player_id: player_id.to_string(),
+                block_id: cache.block_details.prev_block_id.clone(),
+                challenge_id: challenge_id.clone(),
+                algorithm_id: code_id.clone(),
+                track_id: TRACK.to_string(),
+            };
+            benchmark.details.average_quality_by_bundle =
+                Some((0..num_bundles).map(|_| rng.gen_range(0..1000)).collect());
+            cache
+                .recent_benchmarks
+                .insert(format!("{}_{}", player_id, challenge_id), benchmark);
+        }
+    }
+
+    let active_deposit_ids = cache
+        .block_data
+        .active_ids
+        .get_mut(&ActiveType::Deposit)
+        .unwrap();
+    active_deposit_ids.clear();
+    cache.active_deposit_details.clear();
+    for (i, player_id) in PLAYER_IDS.iter().enumerate() {
+        if rng.gen_bool(0.25) {
+            continue;
+        }
+        let deposit_id = format!("deposit_{}", i);
+        active_deposit_ids.insert(deposit_id.clone());
+        cache.active_deposit_details.insert(
+            deposit_id.clone(),
+            DepositDetails {
+                player_id: player_id.to_string(),
+                tx_hash: deposit_id,
+                log_idx: 0,
+                amount: PreciseNumber::from(rng.gen_range(1..1000u32)),
+                r#type: DepositType::Lock { eth_block_num: 0 },
+            },
+        );
+    }
+
+    for player_id in PLAYER_IDS {
+        let mut delegatees = HashMap::new();
+        let mut remaining = 1.0;
+        for delegatee in PLAYER_IDS.iter().filter(|&&id| id != player_id) {
+            if rng.gen_bool(0.4) {
+                let fraction = rng.gen_range(0.0..remaining);
+                remaining -= fraction;
+                delegatees.insert(delegatee.to_string(), fraction);
+            }
+        }
+        let player_state = cache.active_players_state.get_mut(player_id).unwrap();
+        player_state.delegatees = ValueSchedule(vec![ScheduledValue {
+            value: delegatees,
+            block_set: 0,
+            block_effective: 0,
+        }]);
+        player_state.reward_share = ValueSchedule(
+            rng.gen_bool(0.8)
+                .then(|| ScheduledValue {
+                    value: rng.gen_range(0.0..0.5),
+                    block_set: 0,
+                    block_effective: 0,
+                })
+                .into_iter()
+                .collect(),
+        );
+    }
+    cache
+}
+
+// updated caches of randomised blocks, by seed
+async fn cases(modify: impl Fn(&mut AddBlockCache)) -> Vec<(u64, AddBlockCache)> {
+    let (base, code_id_by_challenge) = base_cache().await;
+    let mut cases = Vec::new();
+    for seed in 0..NUM_CASES {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut cache = random_cache(&base, &code_id_by_challenge, &mut rng);
+        modify(&mut cache);
+        update_block_cache(&mut cache).await.unwrap();
+        cases.push((seed, cache));
+    }
+    cases
+}
+
+fn assert_approx_eq(seed: u64, name: &str, actual: PreciseNumber, expected: PreciseNumber) {
+    let (actual, expected) = (actual.to_f64(), expected.to_f64());
+    assert!(
+        (actual - expected).abs() <= 1e-9 * expected.abs().max(1.0),
+        "seed {}: {} is {}, expected {}",
+        seed,
+        name,
+        actual,
+        expected
+    );
+}
+
+#[tokio::test]
+async fn test_influences_sum_to_one() {
+    for (seed, cache) in cases(|_| {}).await {
+        let influences = cache
+            .active_opow_block_data
+            .values()
+            .map(|d| d.influence)
+            .collect::<Vec<_>>();
+        let total = influences.iter().sum::<PreciseNumber>();
+        if influences.iter().all(|&x| x == PreciseNumber::from(0)) {
+            continue;
+        }
+        assert_approx_eq(seed, "total influence", total, PreciseNumber::from(1));
+    }
+}
+
+#[tokio::test]
+async fn test_emissions_sum_to_block_reward() {
+    for (seed, cache) in cases(|_| {}).await {
+        let block_reward = PreciseNumber::from_f64(cache.config.rewards.schedule[0].block_reward);
+        let total = cache
+            .block_details
+            .emissions
+            .values()
+            .sum::<PreciseNumber>();
+        assert_eq!(total, block_reward, "seed {}", seed);
+
+        // emissions by type match the rewards paid out to players
+        for emissions_type in [
+            EmissionsType::Code,
+            EmissionsType::Benchmarker,
+            EmissionsType::Delegator,
+        ] {
+            let paid = cache
+                .active_players_block_data
+                .values()
+                .filter_map(|d| d.reward_by_type.get(&emissions_type))
+                .sum::<PreciseNumber>();
+            assert_eq!(
+                paid, cache.block_details.emissions[&emissions_type],
+                "seed {}: {:?}",
+                seed, emissions_type
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_banned_codes_are_not_active() {
+    // the code of OTHER_CHALLENGE is banned, its benchmarks are not active
+    let cases = cases(|cache| {
+        let code_id = cache
+            .active_codes_details
+            .iter()
+            .find(|(_, d)| d.challenge_id == OTHER_CHALLENGE)
+            .map(|(id, _)| id.clone())
+            .unwrap();
+        cache.active_codes_state.get_mut(&code_id).unwrap().banned = true;
+    })
+    .await;
+    for (seed, cache) in cases {
+        let active_code_ids = &cache.block_data.active_ids[&ActiveType::Code];
+        for (code_id, code_state) in cache.active_codes_state.iter() {
+            assert_eq!(
+                active_code_ids.contains(code_id),
+                !code_state.banned,
+                "seed {}: {}",
+                seed,
+                code_id
+            );
+            if code_state.banned {
+                assert!(!cache.active_codes_block_data.contains_key(code_id));
+            }
+        }
+        let unbanned_rewards = cache
+            .active_codes_state
+            .iter()
+            .filter(|(_, s)| !s.banned)
+            .map(|(id, _)| cache.active_codes_block_data[id].reward)
+            .sum::<PreciseNumber>();
+        assert_eq!(
+            unbanned_rewards,
+            cache.block_details.emissions[&EmissionsType::Code],
+            "seed {}",
+            seed
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_delegator_rewards_sum_to_reward_share() {
+    // every delegator delegates to at most one player, isolating each delegatee's share
+    let cases = cases(|cache| {
+        for player_state in cache.active_players_state.values_mut() {
+            if let Some(delegatees) = player_state.delegatees.0.last_mut() {
+                let first = delegatees.value.keys().min().cloned();
+                delegatees.value.retain(|k, _| Some(k) == first.as_ref());
+            }
+        }
+    })
+    .await;
+    let mut num_delegated = 0;
+    for (seed, cache) in cases {
+        for (delegatee, opow_data) in cache.active_opow_block_data.iter() {
+            if opow_data.reward_share == PreciseNumber::from(0) {
+                continue;
+            }
+            num_delegated += 1;
+            let delegator_rewards = opow_data
+                .delegators
+                .iter()
+                .map(|d| {
+                    cache.active_players_block_data[d].reward_by_type[&EmissionsType::Delegator]
+                })
+                .sum::<PreciseNumber>();
+            assert_approx_eq(
+                seed,
+                &format!("delegator rewards of {}", delegatee),
+                delegator_rewards,
+                opow_data.reward_share,
+            );
+        }
+    }
+    assert!(num_delegated > 0);
+}
+
+#[tokio::test]
+async fn test_cutoff_phase_in_is_monotone() {
+    let (base, code_id_by_challenge) = base_cache().await;
+    for seed in 0..NUM_CASES {
+        let mut cache = random_cache(
+            &base,
+            &code_id_by_challenge,
+            &mut StdRng::seed_from_u64(seed),
+        );
+        // the other challenge's code only became active this round, so its cutoff phases in
+        let round = cache.block_details.round;
+        let code_id = &code_id_by_challenge[OTHER_CHALLENGE];
+        let code_state = cache.active_codes_state.get_mut(code_id).unwrap();
+        code_state.banned = false;
+        code_state.round_active = Some(round);
+
+        let phase_in_start = (round - 1) * cache.config.rounds.blocks_per_round;
+        let phase_in_end = phase_in_start + cache.config.opow.cutoff_phase_in_period;
+        let mut cutoffs_by_player = HashMap::<String, Vec<u64>>::new();
+        for height in phase_in_start..=phase_in_end {
+            let mut cache = cache.clone();
+            cache.block_details.height = height;
+            update_block_cache(&mut cache).await.unwrap();
+            for (player_id, opow_data) in cache.active_opow_block_data.iter() {
+                cutoffs_by_player
+                    .entry(player_id.clone())
+                    .or_default()
+                    .push(opow_data.cutoff);
+            }
+        }
+
+        for (player_id, cutoffs) in cutoffs_by_player.iter() {
+            // cutoffs move monotonically towards the cutoff once the phase in has ended, up to
+            // the truncation of the interpolated cutoff
+            let end = *cutoffs.last().unwrap();
+            let distances = cutoffs.iter().map(|&c| c.abs_diff(end)).collect::<Vec<_>>();
+            assert!(
+                distances.windows(2).all(|w| w[0] + 1 >= w[1]),
+                "seed {}: cutoffs of {} are not monotone: {:?}",
+                seed,
+                player_id,
+                cutoffs
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_cutoff_phase_out_mirrors_phase_in() {
+    let (base, code_id_by_challenge) = base_cache().await;
+    for seed in 0..NUM_CASES {
+        let mut cache = random_cache(
+            &base,
+            &code_id_by_challenge,
+            &mut StdRng::seed_from_u64(seed),
+        );
+        let round = cache.block_details.round;
+        let code_id = &code_id_by_challenge[OTHER_CHALLENGE];
+        cache.active_codes_state.get_mut(code_id).unwrap().banned = false;
+        // phased in over this round, or phased out over this round as it retires next round
+        let mut phase_in = cache.clone();
+        phase_in
+            .active_codes_state
+            .get_mut(code_id)
+            .unwrap()
+            .round_active = Some(round);
+        let mut phase_out = cache.clone();
+        phase_out
+            .config
+            .challenges
+            .get_mut(OTHER_CHALLENGE)
+            .unwrap()
+            .round_retired = Some(round + 1);
+
+        let blocks_per_round = cache.config.rounds.blocks_per_round;
+        assert_eq!(cache.config.opow.cutoff_phase_in_period, blocks_per_round);
+        let start = (round - 1) * blocks_per_round;
+        // both have the challenge at a weight of `offset` blocks out of the period
+        for offset in 1..=blocks_per_round {
+            let mut phase_in = phase_in.clone();
+            phase_in.block_details.height = start + offset;
+            update_block_cache(&mut phase_in).await.unwrap();
+            let mut phase_out = phase_out.clone();
+            phase_out.block_details.height = start + blocks_per_round - offset;
+            update_block_cache(&mut phase_out).await.unwrap();
+            for (player_id, opow_data) in phase_in.active_opow_block_data.iter() {
+                let mirrored = &phase_out.active_opow_block_data[player_id];
+                assert_eq!(
+                    opow_data.cutoff, mirrored.cutoff,
+                    "seed {}: cutoff of {} at offset {}",
+                    seed, player_id, offset
+                );
+                assert_approx_eq(seed, "influence", mirrored.influence, opow_data.influence);
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_emissions_audit_balances() {
+    for (seed, cache) in cases(|_| {}).await {
+        let audit = cache.block_data.emissions_audit.as_ref().unwrap();
+        verify_emissions_audit(audit).unwrap_or_else(|e| panic!("seed {}: {}", seed, e));
+
+        // payouts itemise the rewards of each player
+        for (player_id, player_data) in cache.active_players_block_data.iter() {
+            for (emissions_type, reward) in player_data.reward_by_type.iter() {
+                let paid = audit
+                    .payouts
+                    .iter()
+                    .filter(|p| p.player_id == *player_id && p.r#type == *emissions_type)
+                    .map(|p| p.amount)
+                    .sum::<PreciseNumber>();
+                assert_eq!(paid, *reward, "seed {}: {}", seed, player_id);
+            }
+        }
+
+        let mut unbalanced = audit.clone();
+        unbalanced.vault += PreciseNumber::from(1);
+        assert!(verify_emissions_audit(&unbalanced).is_err());
+
+        let mut over_allocated = audit.clone();
+        over_allocated.payouts.push(EmissionsPayout {
+            r#type: EmissionsType::Code,
+            player_id: PLAYER.to_string(),
+            source_id: code_id(&cache),
+            amount: audit.codes_pool + PreciseNumber::from(1),
+        });
+        over_allocated.vault = PreciseNumber::from(0);
+        assert!(verify_emissions_audit(&over_allocated).is_err());
+    }
+}
+
+#[tokio::test]
+async fn test_over_allocated_pools_are_rejected() {
+    let (mut cache, _) = base_cache().await;
+    cache.config.rewards.distribution.challenge_owners = 2.0;
+    let err = update_block_cache(&mut cache).await.unwrap_err();
+    assert!(err.to_string().starts_with("audit_emissions error"));
+}
+
+fn code_id(cache: &AddBlockCache) -> String {
+    cache.active_codes_state.keys().next().unwrap().clone()
+}
@@ -0,0 +1,176 @@
+mod common;
+
+use common::*;
+use std::collections::HashMap;
+use tig_protocol::{
+    add_block, add_block_with_snapshot, context::Context, set_coinbase, set_delegatees,
+    set_reward_share,
+};
+use tig_structs::core::*;
+use tig_utils::PreciseNumber;
+
+async fn latest_height(ctx: &impl Context) -> u32 {
+    let block_id = ctx.get_latest_block_id().await;
+    ctx.get_block_details(&block_id).await.unwrap().height
+}
+
+#[tokio::test]
+async fn test_reward_share_applies_from_block_effective() {
+    let delegator = "player2";
+    let mut config = test_config();
+    config.deposits.reward_share_update_period = 3;
+    let ctx = setup_with(config, &[PLAYER, delegator]).await;
+    for (i, player_id) in [PLAYER, delegator].into_iter().enumerate() {
+        ctx.add_deposit_to_mempool(DepositDetails {
+            player_id: player_id.to_string(),
+            tx_hash: "0xdeposit".to_string(),
+            log_idx: i,
+            amount: PreciseNumber::from(100),
+            r#type: DepositType::Lock { eth_block_num: 0 },
+        })
+        .await
+        .unwrap();
+    }
+    add_block(&ctx).await.unwrap();
+    set_delegatees(
+        &ctx,
+        delegator.to_string(),
+        HashMap::from([(PLAYER.to_string(), 1.0)]),
+        None,
+    )
+    .await
+    .unwrap();
+    let code_id = setup_active_code(&ctx).await;
+    run_benchmark(&ctx, PLAYER, &code_id, false).await;
+    // the proof becomes active after its submission delay
+    for _ in 0..3 {
+        add_block(&ctx).await.unwrap();
+    }
+
+    let block_effective = latest_height(&ctx).await + 3;
+    set_reward_share(&ctx, PLAYER.to_string(), 0.4, Some(block_effective))
+        .await
+        .unwrap();
+    for _ in 0..4 {
+        let output = add_block_with_snapshot(&ctx).await.unwrap().output.unwrap();
+        let opow_data = &output.active_opow_block_data[PLAYER];
+        assert!(opow_data.reward > PreciseNumber::from(0));
+        let expected = if output.block_details.height < block_effective {
+            test_config().deposits.default_reward_share
+        } else {
+            0.4
+        };
+        assert_eq!(
+            opow_data.reward_share,
+            opow_data.reward * PreciseNumber::from_f64(expected),
+            "block {}",
+            output.block_details.height
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_history_is_retained() {
+    let ctx = setup().await;
+    set_reward_share(&ctx, PLAYER.to_string(), 0.2, None)
+        .await
+        .unwrap();
+    add_block(&ctx).await.unwrap();
+    set_reward_share(&ctx, PLAYER.to_string(), 0.3, Some(3))
+        .await
+        .unwrap();
+    add_block(&ctx).await.unwrap();
+
+    let reward_share = ctx
+        .get_player_state(&PLAYER.to_string())
+        .await
+        .unwrap()
+        .reward_share;
+    let history = reward_share
+        .0
+        .iter()
+        .map(|v| (v.value, v.block_set, v.block_effective))
+        .collect::<Vec<_>>();
+    assert_eq!(history, vec![(0.2, 1, 2), (0.3, 2, 3)]);
+    assert!(reward_share.value_at(1).is_none());
+    assert_eq!(reward_share.value_at(2).unwrap().value, 0.2);
+    assert_eq!(reward_share.value_at(3).unwrap().value, 0.3);
+    assert_eq!(reward_share.latest().unwrap().value, 0.3);
+}
+
+#[tokio::test]
+async fn test_invalid_block_effective() {
+    let mut config = test_config();
+    config.opow.coinbase_update_period = 5;
+    let ctx = setup_with(config, &[PLAYER]).await;
+    let height = latest_height(&ctx).await;
+    let coinbase = HashMap::from([(PLAYER.to_string(), 1.0)]);
+
+    let err = set_coinbase(&ctx, PLAYER.to_string(), coinbase.clone(), Some(height))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().starts_with("Invalid block_effective"),
+        "{}",
+        err
+    );
+
+    // at most coinbase_update_period blocks ahead
+    let err = set_coinbase(&ctx, PLAYER.to_string(), coinbase.clone(), Some(height + 6))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().starts_with("Invalid block_effective"),
+        "{}",
+        err
+    );
+
+    set_coinbase(&ctx, PLAYER.to_string(), coinbase.clone(), Some(height + 3))
+        .await
+        .unwrap();
+    // the cooldown is measured from the previous change's block_effective
+    let err = set_coinbase(&ctx, PLAYER.to_string(), coinbase.clone(), Some(height + 5))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Can only update coinbase every 5 blocks. Earliest block_effective is {}",
+            height + 8
+        )
+    );
+    for _ in 0..3 {
+        add_block(&ctx).await.unwrap();
+    }
+    set_coinbase(&ctx, PLAYER.to_string(), coinbase, Some(height + 8))
+        .await
+        .unwrap();
+}
+
+#[test]
+fn test_legacy_player_state() {
+    let state = serde_json::from_value::<PlayerState>(serde_json::json!({
+        "total_fees_paid": "0",
+        "available_fee_balance": "0",
+        "delegatees": null,
+        "votes": {},
+        "vote_delegatee": null,
+        "reward_share": {"value": 0.3, "block_set": 7},
+    }))
+    .unwrap();
+    assert!(state.delegatees.latest().is_none());
+    assert!(state.coinbase.latest().is_none());
+    assert_eq!(
+        state.reward_share.0,
+        vec![ScheduledValue {
+            value: 0.3,
+            block_set: 7,
+            block_effective: 7,
+        }]
+    );
+
+    // scheduled values round trip
+    let state =
+        serde_json::from_value::<PlayerState>(serde_json::to_value(&state).unwrap()).unwrap();
+    assert_eq!(state.reward_share.value_at(7).unwrap().value, 0.3);
+}
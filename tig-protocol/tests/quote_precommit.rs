@@ -0,0 +1,140 @@
+mod common;
+
+use common::*;
+use std::collections::HashMap;
+use tig_protocol::{add_block, context::Context, quote_precommit, submit_precommit};
+use tig_structs::core::*;
+use tig_utils::PreciseNumber;
+
+fn settings(block_id: String, code_id: &str) -> BenchmarkSettings {
+    BenchmarkSettings {
+        player_id: PLAYER.to_string(),
+        block_id,
+        challenge_id: CHALLENGE.to_string(),
+        algorithm_id: code_id.to_string(),
+        track_id: String::new(),
+    }
+}
+
+fn track_settings(num_bundles: u64, fuel_budget: u64) -> TrackSettings {
+    TrackSettings {
+        hyperparameters: None,
+        fuel_budget,
+        num_bundles,
+    }
+}
+
+#[tokio::test]
+async fn test_quote_matches_submission() {
+    let ctx = setup().await;
+    let code_id = setup_active_code(&ctx).await;
+    let settings = settings(ctx.get_latest_block_id().await, &code_id);
+    let track_settings = HashMap::from([(TRACK.to_string(), track_settings(3, 1000))]);
+    let balance = ctx
+        .get_player_state(&PLAYER.to_string())
+        .await
+        .unwrap()
+        .available_fee_balance;
+
+    let quote = quote_precommit(
+        &ctx,
+        PLAYER.to_string(),
+        settings.clone(),
+        track_settings.clone(),
+    )
+    .await;
+    assert!(quote.is_valid(), "{:?}", quote);
+    assert_eq!(quote.tracks.len(), 1);
+    let track_quote = &quote.tracks[0];
+    assert_eq!(track_quote.track_id, TRACK);
+    assert_eq!(track_quote.probability, 1.0);
+    assert_eq!(track_quote.num_nonces, 3 * NUM_NONCES_PER_BUNDLE);
+    assert_eq!(track_quote.submission_fee, PreciseNumber::from(4));
+
+    // quoting has no side effects
+    assert_eq!(ctx.mempool_len(), 0);
+    assert_eq!(
+        ctx.get_player_state(&PLAYER.to_string())
+            .await
+            .unwrap()
+            .available_fee_balance,
+        balance
+    );
+
+    let benchmark_id = submit_precommit(&ctx, PLAYER.to_string(), settings, track_settings, 0)
+        .await
+        .unwrap();
+    add_block(&ctx).await.unwrap();
+    let details = ctx.get_precommit_details(&benchmark_id).await.unwrap();
+    assert_eq!(details.num_nonces, track_quote.num_nonces);
+    assert_eq!(details.fee_paid, track_quote.submission_fee);
+    assert_eq!(
+        ctx.get_player_state(&PLAYER.to_string())
+            .await
+            .unwrap()
+            .available_fee_balance,
+        balance - track_quote.submission_fee
+    );
+}
+
+#[tokio::test]
+async fn test_quote_collects_all_errors() {
+    let mut config = test_config();
+    let challenge_config = config.challenges.get_mut(CHALLENGE).unwrap();
+    challenge_config.min_num_bundles = 2;
+    let track_config = challenge_config.active_tracks[TRACK].clone();
+    challenge_config
+        .active_tracks
+        .insert("t2".to_string(), track_config);
+    let ctx = setup_with(config, &[PLAYER]).await;
+    let code_id = setup_active_code(&ctx).await;
+
+    let mut settings = settings("unknown".to_string(), &code_id);
+    settings.player_id = "player2".to_string();
+    let track_settings = HashMap::from([
+        (TRACK.to_string(), track_settings(1, 2_000_000)),
+        ("t2".to_string(), track_settings(5000, 1000)),
+    ]);
+    let quote = quote_precommit(&ctx, PLAYER.to_string(), settings, track_settings).await;
+    assert!(!quote.is_valid());
+    assert_eq!(quote.errors.len(), 2, "{:?}", quote.errors);
+    assert!(quote.errors[0].starts_with("Invalid settings.player_id"));
+    assert!(quote.errors[1].starts_with("Invalid block_id"));
+
+    let track_ids = quote
+        .tracks
+        .iter()
+        .map(|t| t.track_id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(track_ids, vec![TRACK, "t2"]);
+    assert!(quote.tracks.iter().all(|t| t.probability == 0.5));
+    assert_eq!(
+        quote.tracks[0].errors.len(),
+        2,
+        "{:?}",
+        quote.tracks[0].errors
+    );
+    assert!(quote.tracks[0].errors[0].starts_with("Invalid num_bundles"));
+    assert!(quote.tracks[0].errors[1].starts_with("Invalid fuel_budget"));
+    assert_eq!(
+        quote.tracks[1].errors,
+        vec!["Insufficient balance".to_string()]
+    );
+    assert_eq!(quote.tracks[1].num_nonces, 5000 * NUM_NONCES_PER_BUNDLE);
+}
+
+#[tokio::test]
+async fn test_quote_invalid_challenge_and_algorithm() {
+    let ctx = setup().await;
+    let mut settings = settings(ctx.get_latest_block_id().await, "c001_a999");
+    settings.challenge_id = "c999".to_string();
+    let quote = quote_precommit(&ctx, PLAYER.to_string(), settings, HashMap::new()).await;
+    assert_eq!(
+        quote.errors,
+        vec![
+            "Invalid challenge 'c999'".to_string(),
+            "Invalid algorithm 'c001_a999'".to_string(),
+        ]
+    );
+    assert!(quote.tracks.is_empty());
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::*;
+use tig_protocol::{
+    add_block_with_snapshot,
+    replay::{replay, BlockSnapshot},
+};
+use tig_utils::{dejsonify, jsonify, PreciseNumber};
+
+async fn record_snapshots(num_blocks: usize) -> Vec<BlockSnapshot> {
+    let player_ids = [PLAYER, "player2"];
+    let ctx = setup_with(test_config(), &player_ids).await;
+    let code_id = setup_active_code(&ctx).await;
+    for player_id in player_ids {
+        run_benchmark(&ctx, player_id, &code_id, false).await;
+    }
+    let mut snapshots = Vec::new();
+    for _ in 0..num_blocks {
+        snapshots.push(add_block_with_snapshot(&ctx).await.unwrap());
+    }
+    snapshots
+}
+
+#[tokio::test]
+async fn test_replay_matches_recorded_blocks() {
+    let snapshots = record_snapshots(5).await;
+    assert!(snapshots
+        .last()
+        .unwrap()
+        .output
+        .as_ref()
+        .unwrap()
+        .active_opow_block_data
+        .contains_key(PLAYER));
+
+    let block_diffs = replay(&snapshots).await.unwrap();
+    assert_eq!(block_diffs.len(), 5);
+    for (block_diff, snapshot) in block_diffs.iter().zip(snapshots.iter()) {
+        assert_eq!(block_diff.height, snapshot.input.block_details.height);
+        assert!(block_diff.diffs.is_empty(), "{:?}", block_diff.diffs);
+    }
+}
+
+#[tokio::test]
+async fn test_replay_reports_tampered_rewards() {
+    let mut snapshots = record_snapshots(3).await;
+    let output = snapshots[1].output.as_mut().unwrap();
+    output
+        .active_opow_block_data
+        .get_mut(PLAYER)
+        .unwrap()
+        .influence = PreciseNumber::from(2);
+
+    let block_diffs = replay(&snapshots).await.unwrap();
+    assert!(block_diffs[0].diffs.is_empty());
+    assert!(block_diffs[2].diffs.is_empty());
+    let diffs = &block_diffs[1].diffs;
+    assert_eq!(diffs.len(), 1);
+    assert_eq!(diffs[0].path, format!("opow.{}.influence", PLAYER));
+    assert_eq!(
+        diffs[0].expected,
+        Some(serde_json::to_value(PreciseNumber::from(2)).unwrap())
+    );
+}
+
+#[tokio::test]
+async fn test_replay_without_recorded_outputs() {
+    let mut snapshots = record_snapshots(3).await;
+    for snapshot in snapshots.iter_mut() {
+        snapshot.output = None;
+    }
+
+    // without outputs, each block is diffed against the previous replayed block
+    let block_diffs = replay(&snapshots).await.unwrap();
+    assert!(block_diffs[0]
+        .diffs
+        .iter()
+        .any(|d| d.path.starts_with("opow.") && d.expected.is_none()));
+    assert!(block_diffs[1]
+        .diffs
+        .iter()
+        .all(|d| d.expected.is_some() && d.actual.is_some()));
+}
+
+#[tokio::test]
+async fn test_snapshot_serialization() {
+    let snapshots = record_snapshots(2).await;
+    let snapshots = dejsonify::<Vec<BlockSnapshot>>(&jsonify(&snapshots)).unwrap();
+    let block_diffs = replay(&snapshots).await.unwrap();
+    assert!(block_diffs.iter().all(|d| d.diffs.is_empty()));
+}
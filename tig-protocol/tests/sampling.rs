@@ -0,0 +1,284 @@
+mod common;
+
+use common::*;
+use std::collections::{HashMap, HashSet};
+use tig_protocol::{
+    add_block, context::Context, sample_benchmark, submit_benchmark, submit_precommit,
+};
+use tig_structs::{config::*, core::*};
+
+fn challenge_config(quality_type: QualityType, min_active_quality: i32) -> ChallengeConfig {
+    let mut challenge_config = test_config().challenges[CHALLENGE].clone();
+    challenge_config.quality_type = quality_type;
+    challenge_config.num_samples_gte_average = 2;
+    challenge_config.num_samples_lt_average = 2;
+    challenge_config
+        .active_tracks
+        .get_mut(TRACK)
+        .unwrap()
+        .min_active_quality = min_active_quality;
+    challenge_config
+}
+
+#[test]
+fn test_bundles_partition_nonces() {
+    let challenge_config = challenge_config(QualityType::Continuous, 0);
+    let solution_quality = (0..16).map(|q| q * 10).collect::<Vec<i32>>();
+    for seed in 0..20 {
+        let sample = sample_benchmark(
+            &challenge_config,
+            &TRACK.to_string(),
+            1,
+            4,
+            &solution_quality,
+            seed,
+        )
+        .unwrap();
+        assert_eq!(sample.bundles.len(), 4);
+        let nonces = sample
+            .bundles
+            .iter()
+            .flat_map(|b| b.nonces.iter().cloned())
+            .collect::<HashSet<u64>>();
+        assert_eq!(nonces, (0..16).collect());
+
+        for bundle in sample.bundles.iter() {
+            let qualities = bundle
+                .nonces
+                .iter()
+                .map(|&n| solution_quality[n as usize])
+                .collect::<Vec<_>>();
+            assert!(qualities.windows(2).all(|w| w[0] <= w[1]));
+            // median of 4 nonces
+            assert_eq!(bundle.average_quality, (qualities[1] + qualities[2]) / 2);
+            assert!(bundle.active);
+        }
+        assert_eq!(sample.num_active_bundles(), 4);
+        assert!(!sample.sampled_nonces.is_empty());
+    }
+}
+
+#[test]
+fn test_only_active_bundles_are_sampled() {
+    let solution_quality = (0..16).map(|q| q * 10).collect::<Vec<i32>>();
+    for seed in 0..20 {
+        let sample = sample_benchmark(
+            &challenge_config(QualityType::Continuous, 75),
+            &TRACK.to_string(),
+            1,
+            4,
+            &solution_quality,
+            seed,
+        )
+        .unwrap();
+        for bundle in sample.bundles.iter() {
+            assert_eq!(bundle.active, bundle.average_quality >= 75);
+        }
+        assert_eq!(
+            sample.average_quality_by_bundle(),
+            sample
+                .bundles
+                .iter()
+                .filter(|b| b.active)
+                .map(|b| b.average_quality)
+                .collect::<Vec<_>>()
+        );
+        let active_nonces = sample
+            .bundles
+            .iter()
+            .filter(|b| b.active)
+            .flat_map(|b| b.nonces.iter().cloned())
+            .collect::<HashSet<u64>>();
+        assert!(sample.sampled_nonces.is_subset(&active_nonces));
+    }
+
+    // no active bundles, nothing to prove
+    let sample = sample_benchmark(
+        &challenge_config(QualityType::Continuous, 1000),
+        &TRACK.to_string(),
+        1,
+        4,
+        &solution_quality,
+        0,
+    )
+    .unwrap();
+    assert_eq!(sample.num_active_bundles(), 0);
+    assert!(sample.sampled_nonces.is_empty());
+}
+
+#[test]
+fn test_binary_quality_is_mean() {
+    let challenge_config = challenge_config(QualityType::Binary, 0);
+    let solution_quality = vec![0, 1, 1, 0, 1, 1, 1, 1];
+    let sample = sample_benchmark(
+        &challenge_config,
+        &TRACK.to_string(),
+        1,
+        2,
+        &solution_quality,
+        0,
+    )
+    .unwrap();
+    for bundle in sample.bundles.iter() {
+        let total = bundle
+            .nonces
+            .iter()
+            .map(|&n| solution_quality[n as usize])
+            .sum::<i32>();
+        assert_eq!(bundle.average_quality, total / 4);
+    }
+}
+
+#[test]
+fn test_invalid_inputs() {
+    let challenge_config = challenge_config(QualityType::Continuous, 0);
+    let solution_quality = vec![0; 8];
+    let sample = |track_id: &str, num_bundles: usize, solution_quality: &[i32]| {
+        sample_benchmark(
+            &challenge_config,
+            &track_id.to_string(),
+            1,
+            num_bundles,
+            solution_quality,
+            0,
+        )
+    };
+    assert!(sample("unknown", 2, &solution_quality).is_err());
+    assert!(sample(TRACK, 0, &solution_quality).is_err());
+    assert!(sample(TRACK, 3, &solution_quality).is_err());
+    assert!(sample(TRACK, 2, &[]).is_err());
+}
+
+#[tokio::test]
+async fn test_sample_matches_submission() {
+    let ctx = setup().await;
+    let code_id = setup_active_code(&ctx).await;
+    let settings = BenchmarkSettings {
+        player_id: PLAYER.to_string(),
+        block_id: ctx.get_latest_block_id().await,
+        challenge_id: CHALLENGE.to_string(),
+        algorithm_id: code_id,
+        track_id: String::new(),
+    };
+    let track_settings = HashMap::from([(
+        TRACK.to_string(),
+        TrackSettings {
+            hyperparameters: None,
+            fuel_budget: 1000,
+            num_bundles: 3,
+        },
+    )]);
+    let benchmark_id = submit_precommit(&ctx, PLAYER.to_string(), settings, track_settings, 0)
+        .await
+        .unwrap();
+    add_block(&ctx).await.unwrap();
+
+    let solution_quality = (0..12).map(|q| (q * 37) % 100).collect::<Vec<i32>>();
+    let seed = 42;
+    submit_benchmark(
+        &ctx,
+        PLAYER.to_string(),
+        benchmark_id.clone(),
+        false,
+        Some(merkle_tree(12).calc_merkle_root()),
+        Some(solution_quality.clone()),
+        seed,
+    )
+    .await
+    .unwrap();
+    add_block(&ctx).await.unwrap();
+
+    let details = ctx.get_benchmark_details(&benchmark_id).await.unwrap();
+    let sample = sample_benchmark(
+        &ctx.get_config().await.challenges[CHALLENGE],
+        &TRACK.to_string(),
+        1,
+        3,
+        &solution_quality,
+        seed,
+    )
+    .unwrap();
+    assert_eq!(details.num_active_bundles, sample.num_active_bundles());
+    assert_eq!(
+        details.average_quality_by_bundle,
+        Some(sample.average_quality_by_bundle())
+    );
+    assert_eq!(details.sampled_nonces, Some(sample.sampled_nonces));
+}
+
+#[test]
+fn test_exact_number_of_samples() {
+    let mut challenge_config = challenge_config(QualityType::Continuous, 0);
+    let solution_quality = (0..32).map(|q| q * 10).collect::<Vec<i32>>();
+    for (num_samples, num_bundles) in [(1, 8), (3, 8), (6, 2), (20, 4)] {
+        challenge_config.num_samples_gte_average = num_samples;
+        challenge_config.num_samples_lt_average = num_samples;
+        for seed in 0..20 {
+            let sample = sample_benchmark(
+                &challenge_config,
+                &TRACK.to_string(),
+                1,
+                num_bundles,
+                &solution_quality,
+                seed,
+            )
+            .unwrap();
+            // the upper half of each bundle is at or above its median
+            let gte_average_pool = sample
+                .bundles
+                .iter()
+                .flat_map(|b| b.nonces[b.nonces.len() / 2..].iter().cloned())
+                .collect::<HashSet<u64>>();
+            let num_gte_average = sample
+                .sampled_nonces
+                .iter()
+                .filter(|n| gte_average_pool.contains(n))
+                .count();
+            let num_lt_average = sample.sampled_nonces.len() - num_gte_average;
+            assert_eq!(num_gte_average, num_samples.min(16));
+            assert_eq!(num_lt_average, num_samples.min(16));
+        }
+    }
+}
+
+#[test]
+fn test_binary_samples_by_quality() {
+    let mut challenge_config = challenge_config(QualityType::Binary, 0);
+    challenge_config.num_samples_gte_average = 3;
+    challenge_config.num_samples_lt_average = 3;
+    let solution_quality = (0..16).map(|n| (n % 4 == 0) as i32).collect::<Vec<i32>>();
+    for seed in 0..20 {
+        let sample = sample_benchmark(
+            &challenge_config,
+            &TRACK.to_string(),
+            1,
+            4,
+            &solution_quality,
+            seed,
+        )
+        .unwrap();
+        // zero quality nonces are only eligible in bundles with a nonzero quality
+        let (mut num_nonzero, mut num_zero) = (0, 0);
+        for bundle in sample.bundles.iter() {
+            let n = bundle
+                .nonces
+                .iter()
+                .filter(|&&n| solution_quality[n as usize] != 0)
+                .count();
+            if n > 0 {
+                num_nonzero += n;
+                num_zero += bundle.nonces.len() - n;
+            }
+        }
+        let num_gte_average = sample
+            .sampled_nonces
+            .iter()
+            .filter(|&&n| solution_quality[n as usize] != 0)
+            .count();
+        assert_eq!(num_gte_average, num_nonzero.min(3));
+        assert_eq!(
+            sample.sampled_nonces.len() - num_gte_average,
+            num_zero.min(3)
+        );
+    }
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::*;
+use serde_json::{json, Map, Value};
+use tig_protocol::{add_block, context::Context, get_settings_performance};
+use tig_structs::core::*;
+
+fn hyperparameters(value: Value) -> Option<Map<String, Value>> {
+    value.as_object().cloned()
+}
+
+#[tokio::test]
+async fn test_settings_performance() {
+    let ctx = setup().await;
+    let code_id = setup_active_code(&ctx).await;
+    let mut benchmark_ids = Vec::new();
+    for settings in [
+        track_settings(hyperparameters(json!({"a": 1, "b": [2, 3]})), 1000),
+        track_settings(hyperparameters(json!({"b": [2, 3], "a": 1})), 1000),
+        track_settings(None, 2000),
+    ] {
+        let (benchmark_id, verification_result) = run_benchmark_on(
+            &ctx,
+            PLAYER,
+            &code_id,
+            false,
+            CPUArchitecture::AMD64,
+            settings,
+        )
+        .await;
+        verification_result.unwrap().unwrap();
+        benchmark_ids.push(benchmark_id);
+    }
+    for _ in 0..3 {
+        add_block(&ctx).await.unwrap();
+    }
+    let block_id = ctx.get_latest_block_id().await;
+    let active_ids = ctx
+        .get_active_ids(&block_id, ActiveType::Benchmark)
+        .await
+        .unwrap();
+    assert!(benchmark_ids.iter().all(|id| active_ids.contains(id)));
+
+    let mut qualities = Vec::new();
+    for benchmark_id in benchmark_ids.iter() {
+        let details = ctx.get_benchmark_details(benchmark_id).await.unwrap();
+        qualities.push(details.average_quality_by_bundle.unwrap());
+    }
+    let performances = get_settings_performance(&ctx, block_id).await.unwrap();
+    assert_eq!(performances.len(), 2);
+
+    // hyperparameters differing only in key order are grouped together
+    let same_hyperparameters = &performances[0];
+    assert_eq!(same_hyperparameters.algorithm_id, code_id);
+    assert_eq!(same_hyperparameters.track_id, TRACK);
+    assert_eq!(same_hyperparameters.fuel_budget, 1000);
+    assert!(same_hyperparameters.hyperparameters_hash.is_some());
+    assert_eq!(same_hyperparameters.num_benchmarks, 2);
+    let bundles = [qualities[0].clone(), qualities[1].clone()].concat();
+    assert_eq!(same_hyperparameters.num_bundles, bundles.len() as u64);
+    assert_eq!(
+        same_hyperparameters.average_quality,
+        bundles.iter().sum::<i32>() as f64 / bundles.len() as f64
+    );
+    assert_eq!(
+        same_hyperparameters.best_quality,
+        *bundles.iter().max().unwrap()
+    );
+
+    let no_hyperparameters = &performances[1];
+    assert_eq!(no_hyperparameters.fuel_budget, 2000);
+    assert_eq!(no_hyperparameters.hyperparameters_hash, None);
+    assert_eq!(no_hyperparameters.num_benchmarks, 1);
+    assert_eq!(no_hyperparameters.num_bundles, qualities[2].len() as u64);
+
+    assert!(get_settings_performance(&ctx, "invalid".to_string())
+        .await
+        .is_err());
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use common::*;
+use tig_protocol::{
+    add_block, add_block_with_snapshot,
+    replay::BlockSnapshot,
+    simulate::{simulate, Scenario, SimulatedBenchmark, SimulatedDeposit},
+};
+use tig_structs::core::*;
+use tig_utils::PreciseNumber;
+
+const OTHER_PLAYER: &str = "player2";
+
+// snapshot of a block where both players have active benchmarks. Returns the snapshot and code id
+async fn snapshot() -> (BlockSnapshot, String) {
+    let player_ids = [PLAYER, OTHER_PLAYER];
+    let ctx = setup_with(test_config(), &player_ids).await;
+    let code_id = setup_active_code(&ctx).await;
+    for player_id in player_ids {
+        run_benchmark(&ctx, player_id, &code_id, false).await;
+    }
+    for _ in 0..3 {
+        add_block(&ctx).await.unwrap();
+    }
+    let snapshot = add_block_with_snapshot(&ctx).await.unwrap();
+    assert!(player_ids.iter().all(|player_id| snapshot
+        .output
+        .as_ref()
+        .unwrap()
+        .active_opow_block_data
+        .contains_key(*player_id)));
+    (snapshot, code_id)
+}
+
+fn scenario(benchmarks: Vec<SimulatedBenchmark>) -> Scenario {
+    Scenario {
+        player_id: PLAYER.to_string(),
+        benchmarks,
+        deposits: None,
+    }
+}
+
+fn benchmark(code_id: &str, average_quality_by_bundle: Vec<i32>) -> SimulatedBenchmark {
+    SimulatedBenchmark {
+        challenge_id: CHALLENGE.to_string(),
+        track_id: TRACK.to_string(),
+        algorithm_id: code_id.to_string(),
+        average_quality_by_bundle,
+    }
+}
+
+#[tokio::test]
+async fn test_unchanged_scenario_matches_block() {
+    let (snapshot, _) = snapshot().await;
+    let projection = simulate(&snapshot.input, &scenario(vec![])).await.unwrap();
+
+    let output = snapshot.output.unwrap();
+    let opow_data = &output.active_opow_block_data[PLAYER];
+    assert_eq!(projection.cutoff, opow_data.cutoff);
+    assert_eq!(
+        projection.num_qualifiers_by_challenge_by_track,
+        opow_data.num_qualifiers_by_challenge_by_track
+    );
+    assert_eq!(projection.imbalance, opow_data.imbalance);
+    assert_eq!(projection.influence, opow_data.influence);
+    assert_eq!(projection.reward, opow_data.reward);
+    assert_eq!(
+        projection.reward_by_type,
+        output.active_players_block_data[PLAYER].reward_by_type
+    );
+}
+
+#[tokio::test]
+async fn test_bundle_changes() {
+    let (snapshot, code_id) = snapshot().await;
+    let baseline = simulate(&snapshot.input, &scenario(vec![])).await.unwrap();
+
+    // removing the player's bundles leaves no influence
+    let projection = simulate(
+        &snapshot.input,
+        &scenario(vec![benchmark(&code_id, vec![])]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(projection.cutoff, 0);
+    assert_eq!(projection.influence, PreciseNumber::from(0));
+    assert_eq!(projection.reward, PreciseNumber::from(0));
+
+    // more bundles of higher quality than the other player win more qualifiers
+    let projection = simulate(
+        &snapshot.input,
+        &scenario(vec![benchmark(&code_id, vec![1000; 4])]),
+    )
+    .await
+    .unwrap();
+    let num_qualifiers = |p: &tig_protocol::simulate::Projection| -> u64 {
+        p.num_qualifiers_by_challenge_by_track
+            .get(CHALLENGE)
+            .map(|x| x.values().sum())
+            .unwrap_or_default()
+    };
+    assert!(num_qualifiers(&projection) > num_qualifiers(&baseline));
+    // without deposits backing them, the extra qualifiers are penalised as imbalance
+    assert!(projection.imbalance > baseline.imbalance);
+
+    // the block itself is left untouched
+    let projection = simulate(&snapshot.input, &scenario(vec![])).await.unwrap();
+    assert_eq!(projection, baseline);
+}
+
+#[tokio::test]
+async fn test_deposit_changes() {
+    let (snapshot, _) = snapshot().await;
+    let baseline = simulate(&snapshot.input, &scenario(vec![])).await.unwrap();
+
+    let mut deposit_scenario = scenario(vec![]);
+    deposit_scenario.deposits = Some(vec![SimulatedDeposit {
+        amount: PreciseNumber::from(1000),
+        r#type: DepositType::Lock { eth_block_num: 0 },
+    }]);
+    let projection = simulate(&snapshot.input, &deposit_scenario).await.unwrap();
+    assert_eq!(
+        projection.num_qualifiers_by_challenge_by_track,
+        baseline.num_qualifiers_by_challenge_by_track
+    );
+    assert_ne!(projection.influence, baseline.influence);
+}
+
+#[tokio::test]
+async fn test_invalid_scenarios() {
+    let (snapshot, code_id) = snapshot().await;
+
+    let mut invalid_player = scenario(vec![]);
+    invalid_player.player_id = "unknown".to_string();
+    assert!(simulate(&snapshot.input, &invalid_player).await.is_err());
+
+    let mut invalid_track = benchmark(&code_id, vec![100]);
+    invalid_track.track_id = "unknown".to_string();
+    assert!(simulate(&snapshot.input, &scenario(vec![invalid_track]))
+        .await
+        .is_err());
+
+    let invalid_algorithm = benchmark("c001_a999", vec![100]);
+    assert!(
+        simulate(&snapshot.input, &scenario(vec![invalid_algorithm]))
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,239 @@
+mod common;
+
+use common::*;
+use std::collections::HashMap;
+use tig_protocol::{
+    add_block, context::Context, memory::MemoryContext, submit_advance, submit_benchmark,
+    submit_code, submit_precommit, submit_proof,
+};
+use tig_structs::{config::*, core::*};
+use tig_utils::PreciseNumber;
+
+const EXPIRY_PERIOD: u32 = 3;
+
+fn limited_config() -> ProtocolConfig {
+    let mut config = test_config();
+    config.submission_limits = Some(SubmissionLimitsConfig {
+        max_outstanding_precommits: 2,
+        precommit_expiry_period: EXPIRY_PERIOD,
+        missing_proof_penalty: PreciseNumber::from(50),
+        max_codes_per_round: 1,
+        max_advances_per_round: 1,
+    });
+    config
+}
+
+async fn precommit(ctx: &MemoryContext, code_id: &str, seed: u64) -> anyhow::Result<String> {
+    let settings = BenchmarkSettings {
+        player_id: PLAYER.to_string(),
+        block_id: ctx.get_latest_block_id().await,
+        challenge_id: CHALLENGE.to_string(),
+        algorithm_id: code_id.to_string(),
+        track_id: String::new(),
+    };
+    let track_settings = HashMap::from([(TRACK.to_string(), track_settings(None, 1000))]);
+    submit_precommit(ctx, PLAYER.to_string(), settings, track_settings, seed).await
+}
+
+async fn submit(ctx: &MemoryContext, benchmark_id: &str) -> anyhow::Result<()> {
+    let num_nonces = ctx
+        .get_precommit_details(&benchmark_id.to_string())
+        .await
+        .unwrap()
+        .num_nonces;
+    submit_benchmark(
+        ctx,
+        PLAYER.to_string(),
+        benchmark_id.to_string(),
+        false,
+        Some(merkle_tree(num_nonces).calc_merkle_root()),
+        Some((0..num_nonces as i32).map(|q| q * 10).collect()),
+        0,
+    )
+    .await
+}
+
+async fn latest_round(ctx: &MemoryContext) -> u32 {
+    let block_id = ctx.get_latest_block_id().await;
+    ctx.get_block_details(&block_id).await.unwrap().round
+}
+
+async fn fee_balance(ctx: &MemoryContext) -> PreciseNumber {
+    ctx.get_player_state(&PLAYER.to_string())
+        .await
+        .unwrap()
+        .available_fee_balance
+}
+
+#[tokio::test]
+async fn test_max_outstanding_precommits() {
+    let ctx = setup_with(limited_config(), &[PLAYER]).await;
+    let code_id = setup_active_code(&ctx).await;
+    let first_id = precommit(&ctx, &code_id, 0).await.unwrap();
+    // precommits still in the mempool count towards the limit
+    precommit(&ctx, &code_id, 1).await.unwrap();
+    let err = precommit(&ctx, &code_id, 2).await.unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("Too many outstanding precommits"));
+
+    // submitting a benchmark frees up a slot
+    add_block(&ctx).await.unwrap();
+    submit(&ctx, &first_id).await.unwrap();
+    precommit(&ctx, &code_id, 3).await.unwrap();
+    assert!(precommit(&ctx, &code_id, 4).await.is_err());
+
+    // as does a precommit expiring
+    for _ in 0..EXPIRY_PERIOD {
+        add_block(&ctx).await.unwrap();
+    }
+    precommit(&ctx, &code_id, 5).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_expired_precommit() {
+    let ctx = setup_with(limited_config(), &[PLAYER]).await;
+    let code_id = setup_active_code(&ctx).await;
+    let benchmark_id = precommit(&ctx, &code_id, 0).await.unwrap();
+    for _ in 0..EXPIRY_PERIOD {
+        add_block(&ctx).await.unwrap();
+    }
+    assert_eq!(
+        submit(&ctx, &benchmark_id).await.unwrap_err().to_string(),
+        format!("Precommit has expired: {}", benchmark_id)
+    );
+    assert!(
+        submit_benchmark(&ctx, PLAYER.to_string(), benchmark_id, true, None, None, 0)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_missing_proof_penalty() {
+    let ctx = setup_with(limited_config(), &[PLAYER]).await;
+    let code_id = setup_active_code(&ctx).await;
+    let benchmark_id = precommit(&ctx, &code_id, 0).await.unwrap();
+    add_block(&ctx).await.unwrap();
+    submit(&ctx, &benchmark_id).await.unwrap();
+    add_block(&ctx).await.unwrap();
+
+    let block_started = ctx
+        .get_precommit_details(&benchmark_id)
+        .await
+        .unwrap()
+        .block_started;
+    let lifespan_period = limited_config().challenges[CHALLENGE].lifespan_period;
+    let player_state = ctx.get_player_state(&PLAYER.to_string()).await.unwrap();
+    let balance = player_state.available_fee_balance;
+    while ctx
+        .get_block_details(&ctx.get_latest_block_id().await)
+        .await
+        .unwrap()
+        .height
+        < block_started + lifespan_period
+    {
+        assert_eq!(fee_balance(&ctx).await, balance);
+        add_block(&ctx).await.unwrap();
+    }
+    assert_eq!(fee_balance(&ctx).await, balance - PreciseNumber::from(50));
+    assert_eq!(
+        ctx.get_player_state(&PLAYER.to_string())
+            .await
+            .unwrap()
+            .total_fees_paid,
+        player_state.total_fees_paid + PreciseNumber::from(50)
+    );
+
+    // proofs can no longer be submitted
+    let sampled_nonces = ctx
+        .get_benchmark_details(&benchmark_id)
+        .await
+        .unwrap()
+        .sampled_nonces
+        .unwrap();
+    let num_nonces = ctx
+        .get_precommit_details(&benchmark_id)
+        .await
+        .unwrap()
+        .num_nonces;
+    let tree = merkle_tree(num_nonces);
+    let merkle_proofs = sampled_nonces
+        .iter()
+        .map(|&nonce| MerkleProof {
+            leaf: output_data(nonce),
+            branch: tree.calc_merkle_branch(nonce as usize).unwrap(),
+        })
+        .collect();
+    assert_eq!(
+        submit_proof(
+            &ctx,
+            PLAYER.to_string(),
+            benchmark_id.clone(),
+            merkle_proofs
+        )
+        .await
+        .unwrap_err()
+        .to_string(),
+        format!("Benchmark has expired: {}", benchmark_id)
+    );
+}
+
+#[tokio::test]
+async fn test_proven_benchmark_is_not_penalised() {
+    let ctx = setup_with(limited_config(), &[PLAYER]).await;
+    let code_id = setup_active_code(&ctx).await;
+    let benchmark_id = run_benchmark(&ctx, PLAYER, &code_id, false).await;
+    let balance = fee_balance(&ctx).await;
+    let lifespan_period = limited_config().challenges[CHALLENGE].lifespan_period;
+    for _ in 0..lifespan_period {
+        add_block(&ctx).await.unwrap();
+    }
+    assert_eq!(fee_balance(&ctx).await, balance);
+    assert!(ctx.get_proof_details(&benchmark_id).await.is_some());
+}
+
+#[tokio::test]
+async fn test_max_codes_and_advances_per_round() {
+    let ctx = setup_with(limited_config(), &[PLAYER]).await;
+    let code = |name: &str| {
+        submit_code(
+            &ctx,
+            PLAYER.to_string(),
+            name.to_string(),
+            CHALLENGE.to_string(),
+            None,
+            HashMap::new(),
+        )
+    };
+    let advance = |name: &str| {
+        submit_advance(
+            &ctx,
+            PLAYER.to_string(),
+            name.to_string(),
+            CHALLENGE.to_string(),
+            String::new(),
+        )
+    };
+    // submissions still in the mempool count towards the limit
+    code("code1").await.unwrap();
+    advance("advance1").await.unwrap();
+    assert_eq!(
+        code("code2").await.unwrap_err().to_string(),
+        "Too many codes submitted this round (max 1)"
+    );
+    assert_eq!(
+        advance("advance2").await.unwrap_err().to_string(),
+        "Too many advances submitted this round (max 1)"
+    );
+
+    // the limits reset with the round
+    add_block(&ctx).await.unwrap();
+    assert!(code("code2").await.is_err());
+    let round = latest_round(&ctx).await;
+    while latest_round(&ctx).await == round {
+        add_block(&ctx).await.unwrap();
+    }
+    code("code2").await.unwrap();
+    advance("advance2").await.unwrap();
+}
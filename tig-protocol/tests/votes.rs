@@ -0,0 +1,206 @@
+mod common;
+
+use common::*;
+use tig_protocol::{
+    add_block, context::Context, get_vote_tally, memory::MemoryContext, set_vote,
+    set_vote_delegatee, submit_advance, VoteTally,
+};
+use tig_structs::core::*;
+use tig_utils::PreciseNumber;
+
+const PLAYER_IDS: [&str; 4] = [PLAYER, "player2", "player3", "player4"];
+
+// players with their locked deposits, and an advance open for voting
+async fn setup_voting(deposits: &[(&str, u32)]) -> (MemoryContext, String) {
+    let ctx = setup_with(test_config(), &PLAYER_IDS).await;
+    for (i, (player_id, amount)) in deposits.iter().enumerate() {
+        ctx.add_deposit_to_mempool(DepositDetails {
+            player_id: player_id.to_string(),
+            tx_hash: "0xdeposit".to_string(),
+            log_idx: i,
+            amount: PreciseNumber::from(*amount),
+            r#type: DepositType::Lock { eth_block_num: 0 },
+        })
+        .await
+        .unwrap();
+    }
+    let advance_id = submit_advance(
+        &ctx,
+        PLAYER.to_string(),
+        "advance".to_string(),
+        CHALLENGE.to_string(),
+        "evidence".to_string(),
+    )
+    .await
+    .unwrap();
+    add_block(&ctx).await.unwrap();
+    let round_voting_starts = ctx
+        .get_advance_state(&advance_id)
+        .await
+        .unwrap()
+        .round_voting_starts;
+    while latest_round(&ctx).await < round_voting_starts {
+        add_block(&ctx).await.unwrap();
+    }
+    (ctx, advance_id)
+}
+
+async fn latest_round(ctx: &impl Context) -> u32 {
+    let block_id = ctx.get_latest_block_id().await;
+    ctx.get_block_details(&block_id).await.unwrap().round
+}
+
+async fn vote(ctx: &impl Context, player_id: &str, advance_id: &str, yes: bool) {
+    set_vote(ctx, player_id.to_string(), advance_id.to_string(), yes)
+        .await
+        .unwrap();
+}
+
+async fn delegate(ctx: &impl Context, player_id: &str, vote_delegatee: Option<&str>) {
+    set_vote_delegatee(
+        ctx,
+        player_id.to_string(),
+        vote_delegatee.map(|d| d.to_string()),
+    )
+    .await
+    .unwrap();
+}
+
+// (player_id, yes, own_votes, delegated_votes, delegators) of each voter
+fn breakdown(tally: &VoteTally) -> Vec<(&str, bool, u32, u32, Vec<&str>)> {
+    tally
+        .voters
+        .iter()
+        .map(|v| {
+            (
+                v.player_id.as_str(),
+                v.yes,
+                v.own_votes.to_f64() as u32,
+                v.delegated_votes.to_f64() as u32,
+                v.delegators.iter().map(|d| d.as_str()).collect(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_delegated_votes() {
+    let (ctx, advance_id) = setup_voting(&[
+        (PLAYER, 100),
+        ("player2", 200),
+        ("player3", 300),
+        ("player4", 50),
+    ])
+    .await;
+    vote(&ctx, PLAYER, &advance_id, true).await;
+    vote(&ctx, "player2", &advance_id, false).await;
+    delegate(&ctx, "player3", Some(PLAYER)).await;
+    // delegation is a single hop, player3 has not voted so player4's votes are not counted
+    delegate(&ctx, "player4", Some("player3")).await;
+
+    let tally = get_vote_tally(&ctx, advance_id.clone()).await.unwrap();
+    assert_eq!(
+        breakdown(&tally),
+        vec![
+            (PLAYER, true, 100, 300, vec!["player3"]),
+            ("player2", false, 200, 0, vec![]),
+        ]
+    );
+    assert_eq!(tally.yes, PreciseNumber::from(400));
+    assert_eq!(tally.no, PreciseNumber::from(200));
+
+    // the tally of the next block matches the live tally
+    add_block(&ctx).await.unwrap();
+    let votes_tally = ctx
+        .get_advance_state(&advance_id)
+        .await
+        .unwrap()
+        .votes_tally;
+    assert_eq!(votes_tally[&true], tally.yes);
+    assert_eq!(votes_tally[&false], tally.no);
+
+    // voting directly overrides delegation
+    vote(&ctx, "player3", &advance_id, false).await;
+    let tally = get_vote_tally(&ctx, advance_id.clone()).await.unwrap();
+    assert_eq!(
+        breakdown(&tally),
+        vec![
+            (PLAYER, true, 100, 0, vec![]),
+            ("player2", false, 200, 0, vec![]),
+            ("player3", false, 300, 50, vec!["player4"]),
+        ]
+    );
+    assert_eq!(tally.yes, PreciseNumber::from(100));
+    assert_eq!(tally.no, PreciseNumber::from(550));
+}
+
+#[tokio::test]
+async fn test_change_vote() {
+    let (ctx, advance_id) = setup_voting(&[(PLAYER, 100), ("player2", 200)]).await;
+    vote(&ctx, PLAYER, &advance_id, true).await;
+    vote(&ctx, "player2", &advance_id, false).await;
+    let err = set_vote(&ctx, "player2".to_string(), advance_id.clone(), false)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().starts_with("You have already voted no"));
+
+    vote(&ctx, "player2", &advance_id, true).await;
+    let tally = get_vote_tally(&ctx, advance_id.clone()).await.unwrap();
+    assert_eq!(tally.yes, PreciseNumber::from(300));
+    assert_eq!(tally.no, PreciseNumber::from(0));
+
+    // votes are tallied at the end of the round, after which they can no longer change
+    let round_votes_tallied = tally.round_votes_tallied;
+    while latest_round(&ctx).await < round_votes_tallied {
+        add_block(&ctx).await.unwrap();
+    }
+    let advance_state = ctx.get_advance_state(&advance_id).await.unwrap();
+    assert_eq!(advance_state.round_active, Some(round_votes_tallied));
+    assert!(
+        set_vote(&ctx, "player2".to_string(), advance_id.clone(), false)
+            .await
+            .is_err()
+    );
+    assert!(get_vote_tally(&ctx, advance_id).await.is_err());
+}
+
+#[tokio::test]
+async fn test_voting_power() {
+    let (ctx, advance_id) = setup_voting(&[("player2", 200)]).await;
+    // no deposit of their own, but their vote delegator has
+    delegate(&ctx, "player2", Some(PLAYER)).await;
+    vote(&ctx, PLAYER, &advance_id, true).await;
+    let err = set_vote(&ctx, "player3".to_string(), advance_id.clone(), true)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().starts_with("You or your vote delegators"));
+
+    // removing the delegation leaves the vote without any votes
+    delegate(&ctx, "player2", None).await;
+    let tally = get_vote_tally(&ctx, advance_id).await.unwrap();
+    assert_eq!(breakdown(&tally), vec![(PLAYER, true, 0, 0, vec![])]);
+}
+
+#[tokio::test]
+async fn test_invalid_vote_delegatee() {
+    let ctx = setup_with(test_config(), &PLAYER_IDS).await;
+    let set = |vote_delegatee: Option<&str>| {
+        set_vote_delegatee(
+            &ctx,
+            PLAYER.to_string(),
+            vote_delegatee.map(|d| d.to_string()),
+        )
+    };
+    assert!(set(Some(PLAYER)).await.is_err());
+    assert!(set(Some("unknown")).await.is_err());
+    assert!(set(None).await.is_err());
+    set(Some("player2")).await.unwrap();
+    assert!(set(Some("player2")).await.is_err());
+    let vote_delegatee = ctx
+        .get_player_state(&PLAYER.to_string())
+        .await
+        .unwrap()
+        .vote_delegatee
+        .unwrap();
+    assert_eq!(vote_delegatee.value, "player2");
+}
@@ -2,10 +2,16 @@ use anyhow::{anyhow, Result};
 use clap::{arg, Command};
 use libloading::Library;
 use serde_json::{Map, Value};
-use std::{fs, panic, path::PathBuf};
+use std::{
+    fs::{self, File},
+    io::Read,
+    os::fd::AsRawFd,
+    panic,
+    path::PathBuf,
+};
 use tig_challenges::*;
 use tig_structs::core::{BenchmarkSettings, CPUArchitecture, OutputData};
-use tig_utils::{dejsonify, jsonify};
+use tig_utils::{blake3_hex, dejsonify, jsonify};
 #[cfg(feature = "cuda")]
 use {
     cudarc::{
@@ -54,18 +60,40 @@ fn cli() -> Command {
             arg!(--gpu [GPU] "Which GPU device to use")
                 .value_parser(clap::value_parser!(usize)),
         )
+        .arg(
+            arg!(--"so-hash" [SO_HASH] "If set, the blake3 hash the shared object must match")
+                .value_parser(clap::value_parser!(String)),
+        )
+        .arg(
+            arg!(--"ptx-hash" [PTX_HASH] "If set, the blake3 hash the ptx file must match")
+                .value_parser(clap::value_parser!(String)),
+        )
 }
 
 fn main() {
     let matches = cli().get_matches();
 
+    // the shared object is loaded through the handle it was hashed from, and the ptx from the
+    // content that was hashed, so neither can be swapped after the check
+    let (binary_file, _) = open_verified(
+        matches.get_one::<PathBuf>("BINARY").unwrap(),
+        matches.get_one::<String>("so-hash"),
+    );
+    let ptx_content = matches.get_one::<PathBuf>("ptx").map(|path| {
+        let (_, content) = open_verified(path, matches.get_one::<String>("ptx-hash"));
+        String::from_utf8(content).unwrap_or_else(|e| {
+            eprintln!("Runtime Error: Failed to read {}: {}", path.display(), e);
+            std::process::exit(84);
+        })
+    });
+
     if let Err(e) = compute_solution(
         matches.get_one::<String>("SETTINGS").unwrap().clone(),
         matches.get_one::<String>("RAND_HASH").unwrap().clone(),
         *matches.get_one::<u64>("NONCE").unwrap(),
-        matches.get_one::<PathBuf>("BINARY").unwrap().clone(),
+        PathBuf::from(format!("/proc/self/fd/{}", binary_file.as_raw_fd())),
         matches.get_one("hyperparameters").cloned(),
-        matches.get_one::<PathBuf>("ptx").cloned(),
+        ptx_content,
         *matches.get_one::<u64>("fuel").unwrap(),
         matches.get_one::<PathBuf>("output").cloned(),
         matches.get_one::<usize>("gpu").cloned(),
@@ -81,7 +109,7 @@ pub fn compute_solution(
     nonce: u64,
     library_path: PathBuf,
     hyperparameters: Option<String>,
-    ptx_path: Option<PathBuf>,
+    ptx_content: Option<String>,
     max_fuel: u64,
     output_folder: Option<PathBuf>,
     gpu_device: Option<usize>,
@@ -176,10 +204,10 @@ pub fn compute_solution(
                 )
             })?;
 
-            if ptx_path.is_none() {
+            if ptx_content.is_none() {
                 panic!("PTX file is required for GPU challenges.");
             }
-            let ptx_path = ptx_path.unwrap();
+            let ptx_content = ptx_content.unwrap();
             // library function may exit 87 if it runs out of fuel
             let solve_challenge_fn = unsafe {
                 library.get::<fn(
@@ -193,8 +221,6 @@ pub fn compute_solution(
             };
 
             let gpu_fuel_scale = 20; // scale fuel to loosely align with CPU
-            let ptx_content = std::fs::read_to_string(&ptx_path)
-                .map_err(|e| anyhow!("Failed to read PTX file: {}", e))?;
             let max_fuel_hex = format!("0x{:016x}", max_fuel * gpu_fuel_scale);
             let modified_ptx = ptx_content.replace("0xdeadbeefdeadbeef", &max_fuel_hex);
 
@@ -379,6 +405,27 @@ fn load_hyperparameters(hyperparameters: &str) -> String {
     hyperparameters
 }
 
+// opens the file and reads its content through the returned handle, exiting if it does not match
+// the expected hash
+fn open_verified(path: &PathBuf, expected_hash: Option<&String>) -> (File, Vec<u8>) {
+    let mut content = Vec::new();
+    let file = File::open(path).and_then(|mut file| {
+        file.read_to_end(&mut content)?;
+        Ok(file)
+    });
+    let file = file.unwrap_or_else(|e| {
+        eprintln!("Runtime Error: Failed to read {}: {}", path.display(), e);
+        std::process::exit(84);
+    });
+    if let Some(expected_hash) = expected_hash {
+        if blake3_hex(&content) != expected_hash.to_lowercase() {
+            eprintln!("Hash mismatch: {} is not {}", path.display(), expected_hash);
+            std::process::exit(88);
+        }
+    }
+    (file, content)
+}
+
 pub fn load_module(path: &PathBuf) -> Result<Library> {
     let res = panic::catch_unwind(|| unsafe { Library::new(path) });
 
@@ -7,13 +7,16 @@ use tig_utils::PreciseNumber;
 serializable_struct_with_getters! {
     ProtocolConfig {
         advances: AdvancesConfig,
+        bans: Option<BansConfig>,
         challenges: HashMap<String, ChallengeConfig>,
         codes: CodesConfig,
         deposits: DepositsConfig,
         erc20: ERC20Config,
+        frauds: Option<FraudsConfig>,
         opow: OPoWConfig,
         rounds: RoundsConfig,
         rewards: RewardsConfig,
+        submission_limits: Option<SubmissionLimitsConfig>,
         topups: TopUpsConfig,
     }
 }
@@ -30,6 +33,12 @@ serializable_struct_with_getters! {
         merge_points_threshold: u32,
     }
 }
+serializable_struct_with_getters! {
+    BansConfig {
+        // player allowed to ban and unban codes and advances
+        governance_address: String,
+    }
+}
 serializable_struct_with_getters! {
     ERC20Config {
         rpc_url: String,
@@ -51,6 +60,25 @@ serializable_struct_with_getters! {
         token_locker_weight: u32,
     }
 }
+serializable_struct_with_getters! {
+    FraudsConfig {
+        slash_fraction: f64,
+    }
+}
+serializable_struct_with_getters! {
+    SubmissionLimitsConfig {
+        // precommits a player can have without a benchmark, excluding expired ones
+        max_outstanding_precommits: usize,
+        // blocks after a precommit's block after which its benchmark can no longer be submitted
+        precommit_expiry_period: u32,
+        // deducted from the fee balance of a player whose benchmark is not proven within the
+        // challenge's `lifespan_period`
+        missing_proof_penalty: PreciseNumber,
+        // codes and advances a player can submit per round
+        max_codes_per_round: usize,
+        max_advances_per_round: usize,
+    }
+}
 serializable_struct_with_getters! {
     TopUpsConfig {
         topup_address: String,
@@ -81,7 +109,11 @@ serializable_struct_with_getters! {
         r#type: ChallengeType,
         quality_type: QualityType,
         submission_delay_multiplier: f64,
+        // nonces sampled for proof from those at or above their bundle's average quality. Sampling
+        // is uniform without replacement across all active bundles, so exactly this many are
+        // sampled unless fewer are eligible, in which case all of them are
         num_samples_gte_average: usize,
+        // same as `num_samples_gte_average`, for nonces below their bundle's average quality
         num_samples_lt_average: usize,
         lifespan_period: u32,
         per_nonce_fee: PreciseNumber,
@@ -91,6 +123,34 @@ serializable_struct_with_getters! {
         max_qualifiers_per_track: u64,
         legacy_multiplier_span: f32,
         min_num_bundles: u64,
+        // first round in which the challenge is no longer active. Its weight is phased out over
+        // the `cutoff_phase_in_period` blocks before
+        round_retired: Option<u32>,
+        // replacements of `active_tracks` from their `round_effective`, in order of round.
+        // Precommits use the tracks of their block's round, and benchmarks on a track removed
+        // since their precommit can only be submitted as stopped
+        #[serde(default)]
+        track_changes: Vec<TrackChange>,
+    }
+}
+serializable_struct_with_getters! {
+    TrackChange {
+        round_effective: u32,
+        active_tracks: HashMap<String, TrackConfig>,
+    }
+}
+impl ChallengeConfig {
+    // tracks active in `round`, once any scheduled change has taken effect
+    pub fn active_tracks_at(&self, round: u32) -> &HashMap<String, TrackConfig> {
+        self.track_changes
+            .iter()
+            .rev()
+            .find(|c| c.round_effective <= round)
+            .map_or(&self.active_tracks, |c| &c.active_tracks)
+    }
+
+    pub fn is_retired_at(&self, round: u32) -> bool {
+        self.round_retired.is_some_and(|r| r <= round)
     }
 }
 serializable_struct_with_getters! {
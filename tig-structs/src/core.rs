@@ -2,7 +2,7 @@ use crate::{
     config::{ChallengeConfig, ProtocolConfig},
     serializable_struct_with_getters,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::{Map, Value};
 use std::collections::{HashMap, HashSet};
 use tig_utils::{jsonify, u64s_from_str, u8s_from_str};
@@ -133,6 +133,10 @@ serializable_struct_with_getters! {
         round_active: Option<u32>,
         round_merged: Option<u32>,
         banned: bool,
+        #[serde(default)]
+        lifecycle: Lifecycle,
+        #[serde(default)]
+        bans: Vec<BanChange>,
     }
 }
 serializable_struct_with_getters! {
@@ -202,10 +206,33 @@ impl From<OutputData> for MerkleHash {
 }
 
 // Binary child structs
+serializable_struct_with_getters! {
+    BinaryArtifact {
+        compile_success: bool,
+        // blake3 hex digest of the artifact, which tig-runtime verifies before loading
+        hash: Option<String>,
+    }
+}
 serializable_struct_with_getters! {
     BinaryDetails {
         compile_success: bool,
+        // archive holding every successfully compiled artifact
         download_url: Option<String>,
+        #[serde(default)]
+        so_by_arch: HashMap<CPUArchitecture, BinaryArtifact>,
+        ptx: Option<BinaryArtifact>,
+        toolchain_version: Option<String>,
+    }
+}
+impl BinaryDetails {
+    pub fn has_approved_binary(&self, cpu_arch: CPUArchitecture) -> bool {
+        // binaries from before artifacts were recorded per architecture are approved on all
+        self.compile_success
+            && (self.so_by_arch.is_empty()
+                || self
+                    .so_by_arch
+                    .get(&cpu_arch)
+                    .is_some_and(|a| a.compile_success))
     }
 }
 serializable_struct_with_getters! {
@@ -268,6 +295,31 @@ serializable_struct_with_getters! {
     BlockData {
         confirmed_ids: HashMap<TxType, HashSet<String>>,
         active_ids: HashMap<ActiveType, HashSet<String>>,
+        emissions_audit: Option<EmissionsAudit>,
+    }
+}
+serializable_struct_with_getters! {
+    EmissionsAudit {
+        block_reward: PreciseNumber,
+        scaled_reward: PreciseNumber,
+        codes_pool: PreciseNumber,
+        advances_pool: PreciseNumber,
+        opow_pool: PreciseNumber,
+        challenge_owners_pool: PreciseNumber,
+        payouts: Vec<EmissionsPayout>,
+        // unallocated code and advance pools
+        bootstrap: PreciseNumber,
+        // block reward left after all other emissions
+        vault: PreciseNumber,
+    }
+}
+serializable_struct_with_getters! {
+    EmissionsPayout {
+        r#type: EmissionsType,
+        player_id: String,
+        // code or advance id for innovator rewards, otherwise the benchmarker paying out
+        source_id: String,
+        amount: PreciseNumber,
     }
 }
 
@@ -302,6 +354,10 @@ serializable_struct_with_getters! {
         round_active: Option<u32>,
         round_merged: Option<u32>,
         banned: bool,
+        #[serde(default)]
+        lifecycle: Lifecycle,
+        #[serde(default)]
+        bans: Vec<BanChange>,
     }
 }
 serializable_struct_with_getters! {
@@ -313,6 +369,102 @@ serializable_struct_with_getters! {
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleStage {
+    Submitted,
+    Pushed,
+    Active,
+    Merged,
+    Banned,
+    Expired,
+}
+impl LifecycleStage {
+    pub fn can_transition_to(&self, next: LifecycleStage) -> bool {
+        use LifecycleStage::*;
+        matches!(
+            (self, next),
+            (Submitted, Pushed | Banned | Expired)
+                | (Pushed, Active | Banned | Expired)
+                | (Active, Merged | Banned)
+                | (Merged, Banned)
+        )
+    }
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LifecycleTransition {
+    pub stage: LifecycleStage,
+    // first round in the stage
+    pub round: u32,
+}
+// stages of a code or advance in order of round. Starts with Submitted, and every transition must
+// be allowed by `LifecycleStage::can_transition_to`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(transparent)]
+pub struct Lifecycle(pub Vec<LifecycleTransition>);
+impl Lifecycle {
+    pub fn stage_at(&self, round: u32) -> Option<LifecycleStage> {
+        self.0
+            .iter()
+            .rev()
+            .find(|t| t.round <= round)
+            .map(|t| t.stage)
+    }
+    pub fn latest(&self) -> Option<LifecycleStage> {
+        self.0.last().map(|t| t.stage)
+    }
+    pub fn transition(&mut self, stage: LifecycleStage, round: u32) -> Result<(), String> {
+        match self.0.last() {
+            None if stage != LifecycleStage::Submitted => {
+                return Err(format!(
+                    "Lifecycle must start with Submitted, not {:?}",
+                    stage
+                ));
+            }
+            Some(prev) if !prev.stage.can_transition_to(stage) => {
+                return Err(format!(
+                    "Invalid transition from {:?} to {:?}",
+                    prev.stage, stage
+                ));
+            }
+            Some(prev) if round < prev.round => {
+                return Err(format!(
+                    "Transition to {:?} in round {} is before {:?} in round {}",
+                    stage, round, prev.stage, prev.round
+                ));
+            }
+            _ => {}
+        }
+        self.0.push(LifecycleTransition { stage, round });
+        Ok(())
+    }
+    // returns to the stage before the latest ban
+    pub fn unban(&mut self, round: u32) -> Result<(), String> {
+        let i = match self
+            .0
+            .iter()
+            .rposition(|t| t.stage == LifecycleStage::Banned)
+        {
+            Some(i) if i + 1 == self.0.len() && i > 0 => i,
+            _ => return Err("Lifecycle is not banned".to_string()),
+        };
+        let stage = self.0[i - 1].stage;
+        let round = round.max(self.0[i].round);
+        self.0.push(LifecycleTransition { stage, round });
+        Ok(())
+    }
+}
+
+// a ban or unban, sorted by round_effective. `banned` follows the latest change in effect
+serializable_struct_with_getters! {
+    BanChange {
+        banned: bool,
+        reason: String,
+        block_set: u32,
+        round_effective: u32,
+    }
+}
+
 // Deposit child structs
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -380,14 +532,83 @@ pub struct PlayerValue<T> {
     pub value: T,
     pub block_set: u32,
 }
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScheduledValue<T> {
+    pub value: T,
+    pub block_set: u32,
+    // first block to use the value
+    pub block_effective: u32,
+}
+// history of a setting, sorted by block_effective. Each value applies from its effective block
+// until the next value's
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(transparent)]
+pub struct ValueSchedule<T>(pub Vec<ScheduledValue<T>>);
+impl<T> Default for ValueSchedule<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+impl<T> ValueSchedule<T> {
+    pub fn value_at(&self, height: u32) -> Option<&ScheduledValue<T>> {
+        self.0.iter().rev().find(|v| v.block_effective <= height)
+    }
+    pub fn latest(&self) -> Option<&ScheduledValue<T>> {
+        self.0.last()
+    }
+    pub fn schedule(&mut self, value: ScheduledValue<T>) -> Result<(), String> {
+        if let Some(latest) = self.latest() {
+            if value.block_effective <= latest.block_effective {
+                return Err(format!(
+                    "Value effective from block {} must be scheduled after the latest, from block {}",
+                    value.block_effective, latest.block_effective
+                ));
+            }
+        }
+        self.0.push(value);
+        Ok(())
+    }
+}
+// also accepts the shape from before values were scheduled: an optional value that applied from
+// the block it was set in
+fn deserialize_value_schedule<'de, D, T>(deserializer: D) -> Result<ValueSchedule<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Shape<T> {
+        Schedule(Vec<ScheduledValue<T>>),
+        Legacy(Option<PlayerValue<T>>),
+    }
+    Ok(match Shape::deserialize(deserializer)? {
+        Shape::Schedule(values) => ValueSchedule(values),
+        Shape::Legacy(value) => ValueSchedule(
+            value
+                .into_iter()
+                .map(|v| ScheduledValue {
+                    value: v.value,
+                    block_set: v.block_set,
+                    block_effective: v.block_set,
+                })
+                .collect(),
+        ),
+    })
+}
 serializable_struct_with_getters! {
     PlayerState {
         total_fees_paid: PreciseNumber,
         available_fee_balance: PreciseNumber,
-        delegatees: Option<PlayerValue<HashMap<String, f64>>>,
+        #[serde(default, deserialize_with = "deserialize_value_schedule")]
+        delegatees: ValueSchedule<HashMap<String, f64>>,
         votes: HashMap<String, PlayerValue<bool>>,
-        reward_share: Option<PlayerValue<f64>>,
-        coinbase: Option<PlayerValue<HashMap<String, f64>>>,
+        // player voting on advances on this player's behalf, unless this player votes directly
+        vote_delegatee: Option<PlayerValue<String>>,
+        #[serde(default, deserialize_with = "deserialize_value_schedule")]
+        reward_share: ValueSchedule<f64>,
+        #[serde(default, deserialize_with = "deserialize_value_schedule")]
+        coinbase: ValueSchedule<HashMap<String, f64>>,
     }
 }
 serializable_struct_with_getters! {
@@ -2,6 +2,17 @@ pub fn u8s_from_str(input: &str) -> [u8; 32] {
     blake3::hash(input.as_bytes()).into()
 }
 
+pub fn blake3_hex(input: &[u8]) -> String {
+    blake3::hash(input).to_hex().to_string()
+}
+
+pub fn is_blake3_hex(input: &str) -> bool {
+    input.len() == 64
+        && input
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
 pub fn u64s_from_str(input: &str) -> [u64; 4] {
     let u8s = u8s_from_str(input);
 
@@ -52,6 +52,28 @@ impl PreciseNumber {
         Ok(Self(U256::from_str_radix(value, 16)?))
     }
 
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        self.0
+            .checked_mul(other.0)
+            .and_then(|x| x.checked_div(PreciseNumber::PRECISION))
+            .map(Self)
+    }
+
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        self.0
+            .checked_mul(PreciseNumber::PRECISION)
+            .and_then(|x| x.checked_div(other.0))
+            .map(Self)
+    }
+
     pub fn approx_inv_exp(x: PreciseNumber) -> PreciseNumber {
         // taylor series approximation of e^-x
         let one = PreciseNumber::from(1);